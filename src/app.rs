@@ -1,15 +1,27 @@
+use crate::breakpoint_groups::BreakpointGroups;
+use crate::breakpoint_script::{self, Create, SavedBreakpoint};
+use crate::events::Events;
 use crate::gdb;
 use crate::mi;
+use crate::mi::commands::{self, escape_regex, quote, Command};
 use crate::parsers;
-use crate::types::WatchpointType;
+use crate::requests::{self, RequestError, Requests, Response};
+use crate::types::{
+    Backtrace, BreakpointSpec, BreakpointType, CatchpointType, Event, Inferior, StopReason,
+    SymbolKind, Thread, ThreadAction, ThreadState, VarFormat, VarScope, WatchpointType,
+};
 use crate::widgets;
+use crate::widgets::breakpoints::BreakpointField;
 
 use gtk::prelude::*;
 
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fs;
 use std::io::Write;
+use std::path::Path;
 use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 struct AppInner {
     // Widgets
@@ -18,10 +30,74 @@ struct AppInner {
     // watchpoints_w: widgets::WatchpointsW,
     expressions_w: RefCell<widgets::ExpressionsW>,
     gdb_w: RefCell<widgets::GdbW>,
+    errors_w: RefCell<widgets::ErrorsW>,
+    inferiors_w: RefCell<widgets::InferiorsW>,
+    libraries_w: RefCell<widgets::LibrariesW>,
+    signals_w: RefCell<widgets::SignalsW>,
+    status_w: RefCell<widgets::StatusW>,
+    dprintf_log_w: RefCell<widgets::DprintfLogW>,
+    symbols_w: RefCell<widgets::SymbolsW>,
+    source_files_w: RefCell<widgets::SourceFilesW>,
+    source_w: RefCell<widgets::SourceW>,
+    // Inferiors (thread groups), in order of addition
+    inferiors: RefCell<Vec<Inferior>>,
     // GDB driver
     gdb: RefCell<Option<gdb::GDB>>,
-    token: RefCell<u64>, // Maybe use an atomic type?
-    callbacks: RefCell<HashMap<u64, Box<Fn(&AppInner, &App, mi::Result)>>>,
+    // Commands sent to GDB that are not answered yet
+    requests: Requests<ResponseHandler>,
+    // Console output since the last result record. GDB runs commands one at a time, so this is
+    // the output of the command of the next result (plus any messages printed in the meantime).
+    console_output: RefCell<String>,
+    // Number of the dprintf breakpoint that was just hit. Console output until the next record is
+    // the dprintf output, see `handle_async_result`.
+    dprintf_output: Cell<Option<u32>>,
+    // Hit counts of dprintf breakpoints. A dprintf is hit when a `breakpoint-modified` record
+    // increases its hit count, other modifications (e.g. `condition` in the console) don't.
+    dprintf_hits: RefCell<HashMap<u32, u32>>,
+    // Names of invalid varobjs that are being created again, see `recreate_expr`
+    recreating_exprs: RefCell<HashSet<String>>,
+    // Executable of the program whose breakpoint groups and source files are shown. These are
+    // loaded when the executable becomes known or changes, see `executable_changed`.
+    executable: RefCell<Option<String>>,
+    // Subscribers of async events
+    events: Events<EventHandler>,
+    // Incremented when threads stop or resume. Stack requests remember the generation of their
+    // thread. Follow-up requests of an outdated generation are not sent, and responses from
+    // before the thread resumed or stopped again are ignored.
+    generation: Cell<u64>,
+    // Generation of the last stop or resume of all threads
+    all_threads_generation: Cell<u64>,
+    // Generations of the last stops or resumes of individual threads (non-stop mode)
+    thread_generations: RefCell<HashMap<i32, u64>>,
+    // Incremented every time we start fetching full backtraces of all threads (for the parallel
+    // stacks view and thread filtering). Only the latest fetch is used, requests of older fetches
+    // are not sent.
+    full_stacks_fetch: Cell<u64>,
+}
+
+type ResponseHandler = Box<Fn(&AppInner, &App, Response)>;
+
+type EventHandler = Fn(&AppInner, &App, &Event);
+
+/// Requests that GDB doesn't answer in this duration (after answering the requests sent before
+/// them) are considered failed. Commands that can take long in large programs are sent without a
+/// timeout, see `send_request_with_timeout`.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Max. depth to ask for in `-stack-info-depth`. Counting frames of very deep (e.g. infinitely
+/// recursive) stacks is slow.
+const MAX_STACK_DEPTH: usize = 10000;
+
+/// Where a breakpoint is created from, see `AppInner::breakpoint_added`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BreakpointOrigin {
+    /// The "add breakpoint" form. Errors are shown in the form, the form is reset when the
+    /// breakpoint is created.
+    AddForm,
+    /// The symbol browser. Errors are shown in the error bar.
+    Symbols,
+    /// Line numbers of the source view. Errors are shown in the error bar.
+    Source,
 }
 
 #[derive(Clone)]
@@ -36,14 +112,27 @@ impl App {
         // Horizontal: | Vertical: -
 
         // Current layout:
+        // errors
         // horiz(1) ->
-        //   [ vert(1) -> [ vert(2) -> [ currently_empty, gdb logs ],
+        //   [ vert(1) -> [ vert(2) -> [ notebook -> [ inferiors, libraries, signals, dprintf log,
+        //                                             symbols, files, source ],
+        //                               gdb logs ],
         //                  flow box -> [ breakpoints, /* watchpoints */ expressions ] ]
         //   , threads
         //   ]
+        // status
+
+        let window_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        window.add(&window_box);
+
+        let errors_w = widgets::ErrorsW::new();
+        window_box.pack_start(errors_w.get_widget(), false, false, 0);
 
         let horiz1 = gtk::Paned::new(gtk::Orientation::Horizontal);
-        window.add(&horiz1);
+        window_box.pack_start(&horiz1, true, true, 0);
+
+        let status_w = widgets::StatusW::new();
+        window_box.pack_start(status_w.get_widget(), false, false, 0);
 
         let vert1 = gtk::Paned::new(gtk::Orientation::Vertical);
         horiz1.pack1(&vert1, true, false);
@@ -51,6 +140,51 @@ impl App {
         let vert2 = gtk::Paned::new(gtk::Orientation::Vertical);
         vert1.pack1(&vert2, true, false);
 
+        let notebook = gtk::Notebook::new();
+        vert2.pack1(&notebook, true, false);
+
+        let inferiors_w = widgets::InferiorsW::new();
+        notebook.append_page(
+            inferiors_w.get_widget(),
+            Some(&gtk::Label::new(Some("Inferiors"))),
+        );
+
+        let libraries_w = widgets::LibrariesW::new();
+        notebook.append_page(
+            libraries_w.get_widget(),
+            Some(&gtk::Label::new(Some("Libraries"))),
+        );
+
+        let signals_w = widgets::SignalsW::new();
+        notebook.append_page(
+            signals_w.get_widget(),
+            Some(&gtk::Label::new(Some("Signals"))),
+        );
+
+        let dprintf_log_w = widgets::DprintfLogW::new();
+        notebook.append_page(
+            dprintf_log_w.get_widget(),
+            Some(&gtk::Label::new(Some("Dprintf log"))),
+        );
+
+        let symbols_w = widgets::SymbolsW::new();
+        notebook.append_page(
+            symbols_w.get_widget(),
+            Some(&gtk::Label::new(Some("Symbols"))),
+        );
+
+        let source_files_w = widgets::SourceFilesW::new();
+        notebook.append_page(
+            source_files_w.get_widget(),
+            Some(&gtk::Label::new(Some("Files"))),
+        );
+
+        let source_w = widgets::SourceW::new();
+        notebook.append_page(
+            source_w.get_widget(),
+            Some(&gtk::Label::new(Some("Source"))),
+        );
+
         let gdb_w = widgets::GdbW::new();
         vert2.pack2(gdb_w.get_widget(), true, false);
 
@@ -81,9 +215,55 @@ impl App {
             // watchpoints_w,
             expressions_w: RefCell::new(expressions_w),
             gdb_w: RefCell::new(gdb_w),
+            errors_w: RefCell::new(errors_w),
+            inferiors_w: RefCell::new(inferiors_w),
+            libraries_w: RefCell::new(libraries_w),
+            signals_w: RefCell::new(signals_w),
+            status_w: RefCell::new(status_w),
+            dprintf_log_w: RefCell::new(dprintf_log_w),
+            symbols_w: RefCell::new(symbols_w),
+            source_files_w: RefCell::new(source_files_w),
+            source_w: RefCell::new(source_w),
+            inferiors: RefCell::new(vec![]),
             gdb: RefCell::new(None),
-            token: RefCell::new(0),
-            callbacks: RefCell::new(HashMap::new()),
+            requests: Requests::new(),
+            console_output: RefCell::new(String::new()),
+            dprintf_output: Cell::new(None),
+            dprintf_hits: RefCell::new(HashMap::new()),
+            recreating_exprs: RefCell::new(HashSet::new()),
+            executable: RefCell::new(None),
+            events: Events::new(),
+            generation: Cell::new(0),
+            all_threads_generation: Cell::new(0),
+            thread_generations: RefCell::new(HashMap::new()),
+            full_stacks_fetch: Cell::new(0),
+        }));
+
+        //
+        // Subscribe to async events
+        //
+
+        app.0.events.subscribe(Box::new(AppInner::handle_event));
+        app.0.events.subscribe(Box::new(|inner, _outer, event| {
+            inner.breakpoints_w.borrow().handle_event(event);
+        }));
+        app.0.events.subscribe(Box::new(|inner, _outer, event| {
+            inner.threads_w.borrow_mut().handle_event(event);
+        }));
+        app.0.events.subscribe(Box::new(|inner, _outer, event| {
+            inner.inferiors_w.borrow().handle_event(event);
+        }));
+        app.0.events.subscribe(Box::new(|inner, _outer, event| {
+            inner.status_w.borrow_mut().handle_event(event);
+        }));
+        app.0.events.subscribe(Box::new(|inner, _outer, event| {
+            inner.libraries_w.borrow().handle_event(event);
+        }));
+        app.0.events.subscribe(Box::new(|inner, _outer, event| {
+            inner.signals_w.borrow().handle_event(event);
+        }));
+        app.0.events.subscribe(Box::new(|inner, _outer, event| {
+            inner.source_w.borrow().handle_event(event);
         }));
 
         //
@@ -100,6 +280,38 @@ impl App {
                 }));
         }
 
+        //
+        // Connect bulk actions and groups of the breakpoint list
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .breakpoints_w
+                .borrow_mut()
+                .connect_breakpoints_enabled(Box::new(move |bp_ids, enable| {
+                    app_clone.0.breakpoints_toggled(bp_ids, enable);
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .breakpoints_w
+                .borrow_mut()
+                .connect_breakpoints_deleted(Box::new(move |bp_ids| {
+                    app_clone.0.breakpoints_deleted(bp_ids);
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .breakpoints_w
+                .borrow_mut()
+                .connect_groups_changed(Box::new(move |groups| {
+                    app_clone.0.save_breakpoint_groups(groups);
+                }));
+        }
+
         //
         // Connect "breakpoint added" (the "add breakpoint" form in the breakpoint list)
         //
@@ -109,8 +321,61 @@ impl App {
             app.0
                 .breakpoints_w
                 .borrow_mut()
-                .connect_breakpoint_added(Box::new(move |location, condition| {
-                    app_clone.0.breakpoint_added(location, condition);
+                .connect_breakpoint_added(Box::new(move |spec| {
+                    app_clone
+                        .0
+                        .breakpoint_added(spec, BreakpointOrigin::AddForm);
+                }));
+        }
+
+        //
+        // Connect "breakpoint commands" (the command editor of the breakpoint list)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .breakpoints_w
+                .borrow_mut()
+                .connect_breakpoint_commands(Box::new(move |bp_id, commands| {
+                    app_clone.0.breakpoint_commands_set(bp_id, commands);
+                }));
+        }
+
+        //
+        // Connect breakpoint import/export
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .breakpoints_w
+                .borrow_mut()
+                .connect_import(Box::new(move |path| {
+                    app_clone.0.import_breakpoints(path);
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .breakpoints_w
+                .borrow_mut()
+                .connect_export(Box::new(move |path| {
+                    app_clone.0.export_breakpoints(path);
+                }));
+        }
+
+        //
+        // Connect "catchpoint added" (the "add catchpoint" form in the breakpoint list)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .breakpoints_w
+                .borrow_mut()
+                .connect_catchpoint_added(Box::new(move |type_, arg| {
+                    app_clone.0.catchpoint_added(type_, &arg);
                 }));
         }
 
@@ -179,230 +444,1128 @@ impl App {
             app.0
                 .expressions_w
                 .borrow_mut()
-                .connect_get_children(Box::new(move |name| {
-                    app_clone.0.get_expr_children(name);
+                .connect_get_children(Box::new(move |name, from, to| {
+                    app_clone.0.get_expr_children(name, from, to);
                 }));
         }
 
-        app
-    }
+        //
+        // Connect "get stack" (threads widget)
+        //
 
-    pub fn gdb_connect(&self, args: &[String]) {
-        let (send, recv) = glib::MainContext::channel(glib::source::PRIORITY_DEFAULT);
-        let gdb = gdb::GDB::with_args(args, send); // TODO errors
-        let main_context = glib::MainContext::default();
         {
-            let app = self.clone();
-            recv.attach(&main_context, move |msg| app.mi_msg_recvd(msg));
+            let app_clone = app.clone();
+            app.0.threads_w.borrow_mut().connect_get_stack(Box::new(
+                move |thread_id, low, high| {
+                    app_clone.0.get_thread_stack(thread_id, low, high);
+                },
+            ));
         }
-        // TODO error checking
-        *self.0.gdb.borrow_mut() = Some(gdb);
-        self.0.gdb_w.borrow().enter_connected_state();
-    }
 
-    pub fn mi_msg_recvd(&self, mi_msgs: mi::Output) -> gtk::Continue {
-        for msg in mi_msgs {
-            match msg {
-                mi::ResultOrOOB::Result(result) => self.mi_result_recvd(result),
-                mi::ResultOrOOB::OOB(oob) => self.mi_oob_recvd(oob),
-            }
+        //
+        // Connect "parallel stacks toggled" (threads widget)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .threads_w
+                .borrow()
+                .connect_parallel_stacks_toggled(Box::new(move |active| {
+                    if active {
+                        app_clone.0.full_stacks_needed();
+                    }
+                }));
         }
-        gtk::Continue(true)
-    }
 
-    fn mi_result_recvd(&self, result: mi::Result) {
-        self.0.gdb_w.borrow_mut().insert_line(&format!(
-            "<span color=\"#6BDEB1\">[RESULT]</span> {}",
-            render_result(&result)
-        ));
-        self.0.handle_result(self, result);
-    }
+        //
+        // Connect "filter changed" (threads widget)
+        //
 
-    fn mi_oob_recvd(&self, oob: mi::OutOfBandResult) {
-        match oob {
-            mi::OutOfBandResult::ExecAsyncRecord(async_) => {
-                self.0.gdb_w.borrow().insert_line(&format!(
-                    "<span color=\"#505B70\">[EXEC]</span> {}",
-                    render_async_record(&async_)
-                ));
-                self.0.handle_async_result(self, async_);
-            }
-            mi::OutOfBandResult::StatusAsyncRecord(async_) => {
-                self.0.gdb_w.borrow().insert_line(&format!(
-                    "<span color=\"#3FBCA6\">[STATUS]</span> {}",
-                    render_async_record(&async_)
-                ));
-                self.0.handle_async_result(self, async_);
-            }
-            mi::OutOfBandResult::NotifyAsyncRecord(async_) => {
-                self.0.gdb_w.borrow().insert_line(&format!(
-                    "<span color=\"#CBCE79\">[NOTIFY]</span> {}",
-                    render_async_record(&async_)
-                ));
-                self.0.handle_async_result(self, async_);
-            }
-            mi::OutOfBandResult::ConsoleStreamRecord(str) => {
-                self.0.gdb_w.borrow().insert_line(&format!(
-                    "<span color=\"#A1D490\">[CONSOLE]</span> {}",
-                    glib::markup_escape_text(&str)
-                ));
-            }
-            mi::OutOfBandResult::TargetStreamRecord(str) => {
-                self.0.gdb_w.borrow().insert_line(&format!(
-                    "<span color=\"#90C3D4\">[TARGET]</span> {}",
-                    glib::markup_escape_text(&str)
-                ));
-            }
-            mi::OutOfBandResult::LogStreamRecord(str) => {
-                self.0.gdb_w.borrow().insert_line(&format!(
-                    "<span color=\"#D4A190\">[LOG]</span> {}",
-                    glib::markup_escape_text(&str)
-                ));
-            }
+        {
+            let app_clone = app.clone();
+            app.0
+                .threads_w
+                .borrow_mut()
+                .connect_filter_changed(Box::new(move || {
+                    app_clone.0.full_stacks_needed();
+                }));
         }
-    }
 
-    pub fn send_mi_msg(&self, msg: String) {
-        match *self.0.gdb.borrow_mut() {
-            None => {
-                // This should be a bug as the entry should be disabled when we're not connected
-                println!("Can't send mi msg! GDB not available!");
-            }
-            Some(ref mut gdb) => {
-                writeln!(gdb.stdin(), "{}", msg).unwrap();
-                self.0.gdb_w.borrow().insert_line(&format!(">>> {}", msg));
-                // let _ = gdb.stdin().flush();
-            }
-        }
-    }
-}
+        //
+        // Connect "select thread" (threads widget)
+        //
 
-// TODO find a better name
-macro_rules! some {
-    ( $x:expr ) => {
-        match $x {
-            Some(ret) => ret,
-            None => {
-                return;
-            }
+        {
+            let app_clone = app.clone();
+            app.0
+                .threads_w
+                .borrow_mut()
+                .connect_select_thread(Box::new(move |thread_id| {
+                    app_clone.0.select_thread(thread_id);
+                }));
         }
-    };
-}
 
-macro_rules! some_ret {
-    ( $x:expr, $ret:expr ) => {
-        match $x {
-            Some(ret) => ret,
-            None => {
-                return $ret;
-            }
+        //
+        // Connect "thread action" (threads widget context menu, non-stop mode)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0.threads_w.borrow_mut().connect_thread_action(Box::new(
+                move |thread_id, action| {
+                    app_clone.0.thread_action(thread_id, action);
+                },
+            ));
         }
-    };
-}
 
-impl AppInner {
-    fn get_token(&self) -> u64 {
-        let mut token_ref = self.token.borrow_mut();
-        let ret = *token_ref;
-        *token_ref = ret + 1;
-        ret
-    }
+        //
+        // Connect "delete expression" (expressions widget)
+        //
 
-    fn create_expr(&self, expr_str: String) {
-        let token = self.get_token();
-        let mut gdb_ref = self.gdb.borrow_mut();
-        if let Some(ref mut gdb) = *gdb_ref {
-            let stdin = gdb.stdin();
-            writeln!(stdin, "{}-var-create - @ {}", token, expr_str).unwrap();
-            drop(gdb_ref);
-            self.callbacks.borrow_mut().insert(
-                token,
-                Box::new(move |app_inner, _app, result| {
-                    if result.class != mi::ResultClass::Done {
-                        println!("Error: {:?}", result);
-                        return;
-                    }
-                    let expr = result.results;
-                    match parsers::parse_var_create_result(expr) {
-                        None => {
-                            println!("Can't parse expression");
-                        }
-                        Some(expr) => {
-                            app_inner.expressions_w.borrow_mut().add(
-                                expr.name,
-                                expr_str.to_owned(),
-                                expr.value,
-                                expr.type_,
-                                expr.n_children != 0,
-                            );
-                        }
-                    }
-                }),
-            );
+        {
+            let app_clone = app.clone();
+            app.0
+                .expressions_w
+                .borrow_mut()
+                .connect_delete_expr(Box::new(move |name| {
+                    app_clone.0.delete_expr(name);
+                }));
         }
-    }
 
-    fn get_expr_children(&self, name: &str) {
-        let token = self.get_token();
-        let mut gdb_ref = self.gdb.borrow_mut();
-        if let Some(ref mut gdb) = *gdb_ref {
-            let stdin = gdb.stdin();
-            writeln!(stdin, "{}-var-list-children --all-values {}", token, name).unwrap();
+        //
+        // Connect "set format" (expressions widget context menu)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .expressions_w
+                .borrow_mut()
+                .connect_set_format(Box::new(move |name, format| {
+                    app_clone.0.set_expr_format(name, format);
+                }));
+        }
+
+        //
+        // Connect "load symbols" (libraries widget context menu)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .libraries_w
+                .borrow_mut()
+                .connect_load_symbols(Box::new(move |path| {
+                    app_clone.0.load_library_symbols(path);
+                }));
+        }
+
+        //
+        // Connect "break on load" (libraries widget context menu)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .libraries_w
+                .borrow_mut()
+                .connect_break_on_load(Box::new(move |path| {
+                    app_clone.0.break_on_library_load(path);
+                }));
+        }
+
+        //
+        // Connect "handle signal" (toggles in the signal list)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .signals_w
+                .borrow_mut()
+                .connect_handle_signal(Box::new(move |signal, keyword| {
+                    app_clone.0.handle_signal(signal, keyword);
+                }));
+        }
+
+        //
+        // Connect "continue with signal" (received signal banner in the signal list)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .signals_w
+                .borrow_mut()
+                .connect_continue_with_signal(Box::new(move |signal| {
+                    // "signal 0" continues without a signal
+                    let command = format!("signal {}", signal.unwrap_or("0"));
+                    app_clone.0.send_console_command(&command, |_, _, _| {});
+                }));
+        }
+
+        //
+        // Connect "refresh" (signal list)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0.signals_w.borrow().connect_refresh(Box::new(move || {
+                app_clone.0.get_signals();
+            }));
+        }
+
+        //
+        // Connect symbol browser actions
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .symbols_w
+                .borrow_mut()
+                .connect_search(Box::new(move |kind, regex, nondebug| {
+                    app_clone.0.search_symbols(kind, regex, nondebug);
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .symbols_w
+                .borrow_mut()
+                .connect_break_on(Box::new(move |location| {
+                    let spec = BreakpointSpec {
+                        location: location.to_string(),
+                        ..Default::default()
+                    };
+                    app_clone
+                        .0
+                        .breakpoint_added(spec, BreakpointOrigin::Symbols);
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .symbols_w
+                .borrow_mut()
+                .connect_open_source(Box::new(move |path, line| {
+                    app_clone.0.source_w.borrow().show_file(path, line);
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .symbols_w
+                .borrow_mut()
+                .connect_add_expr(Box::new(move |expr| app_clone.0.create_expr(expr)));
+        }
+
+        //
+        // Connect source files and source view
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .source_files_w
+                .borrow_mut()
+                .connect_open(Box::new(move |path| {
+                    app_clone.0.source_w.borrow().show_file(path, None);
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .source_files_w
+                .borrow_mut()
+                .connect_refresh(Box::new(move || {
+                    app_clone.0.list_source_files();
+                }));
+        }
+        {
+            let app_clone = app.clone();
+            app.0
+                .source_w
+                .borrow_mut()
+                .connect_break_line(Box::new(move |path, line| {
+                    let spec = BreakpointSpec {
+                        location: format!("{}:{}", path, line),
+                        ..Default::default()
+                    };
+                    app_clone.0.breakpoint_added(spec, BreakpointOrigin::Source);
+                }));
+        }
+
+        //
+        // Connect "follow fork mode changed" (inferiors widget)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .inferiors_w
+                .borrow()
+                .connect_follow_fork_mode_changed(Box::new(move |mode| {
+                    app_clone
+                        .0
+                        .send_request(format!("-gdb-set follow-fork-mode {}", mode), |_, _, _| {});
+                }));
+        }
+
+        //
+        // Connect "detach on fork toggled" (inferiors widget)
+        //
+
+        {
+            let app_clone = app.clone();
+            app.0
+                .inferiors_w
+                .borrow()
+                .connect_detach_on_fork_toggled(Box::new(move |detach| {
+                    app_clone.0.send_request(
+                        format!(
+                            "-gdb-set detach-on-fork {}",
+                            if detach { "on" } else { "off" }
+                        ),
+                        |_, _, _| {},
+                    );
+                }));
+        }
+
+        app
+    }
+
+    /// Spawn GDB with the given args. In non-stop mode threads are stopped and resumed
+    /// independently.
+    pub fn gdb_connect(&self, args: &[String], non_stop: bool) {
+        let (send, recv) = glib::MainContext::channel(glib::source::PRIORITY_DEFAULT);
+        let gdb = gdb::GDB::with_args(args, send); // TODO errors
+        let main_context = glib::MainContext::default();
+        {
+            let app = self.clone();
+            recv.attach(&main_context, move |msg| app.gdb_msg_recvd(msg));
+        }
+        // TODO error checking
+        *self.0.gdb.borrow_mut() = Some(gdb);
+        self.0.gdb_w.borrow().enter_connected_state();
+        self.0.errors_w.borrow_mut().clear();
+        // Check for unanswered requests until disconnected
+        {
+            let app = self.clone();
+            glib::timeout_add_seconds_local(1, move || {
+                app.0.check_timeouts(&app);
+                gtk::Continue(app.0.gdb.borrow().is_some())
+            });
+        }
+        // These need to be set before starting the program
+        if non_stop {
+            self.0
+                .send_request("-gdb-set mi-async on".to_string(), |_, _, _| {});
+            self.0
+                .send_request("-gdb-set non-stop on".to_string(), |_, _, _| {});
+        }
+        self.0.threads_w.borrow().set_non_stop(non_stop);
+        // Render STL containers, Rust `Vec`s etc. using pretty-printers. This needs to be done
+        // before creating any varobjs.
+        self.0
+            .send_request("-enable-pretty-printing".to_string(), |_, _, _| {});
+        // Fork settings may be changed in .gdbinit
+        self.0.get_fork_settings();
+        // Also lists source files once the executable is known
+        self.0.list_thread_groups();
+        self.0.get_signals();
+    }
+
+    fn gdb_msg_recvd(&self, msg: gdb::Msg) -> gtk::Continue {
+        match msg {
+            gdb::Msg::Mi(mi_msgs) => self.mi_msg_recvd(mi_msgs),
+            gdb::Msg::Exited => {
+                self.0.gdb_w.borrow().insert_line("GDB exited");
+                self.0.gdb_disconnected(self);
+                gtk::Continue(false)
+            }
+        }
+    }
+
+    pub fn mi_msg_recvd(&self, mi_msgs: mi::Output) -> gtk::Continue {
+        for msg in mi_msgs {
+            match msg {
+                mi::ResultOrOOB::Result(result) => self.mi_result_recvd(result),
+                mi::ResultOrOOB::OOB(oob) => self.mi_oob_recvd(oob),
+            }
+        }
+        gtk::Continue(true)
+    }
+
+    fn mi_result_recvd(&self, result: mi::Result) {
+        self.0.gdb_w.borrow_mut().insert_line(&format!(
+            "<span color=\"#6BDEB1\">[RESULT]</span> {}",
+            render_result(&result)
+        ));
+        self.0.dprintf_output.set(None);
+        self.0.handle_result(self, result);
+    }
+
+    fn mi_oob_recvd(&self, oob: mi::OutOfBandResult) {
+        match oob {
+            mi::OutOfBandResult::ExecAsyncRecord(async_) => {
+                self.0.gdb_w.borrow().insert_line(&format!(
+                    "<span color=\"#505B70\">[EXEC]</span> {}",
+                    render_async_record(&async_)
+                ));
+                self.0.dprintf_output.set(None);
+                self.0.handle_async_result(self, async_);
+            }
+            mi::OutOfBandResult::StatusAsyncRecord(async_) => {
+                self.0.gdb_w.borrow().insert_line(&format!(
+                    "<span color=\"#3FBCA6\">[STATUS]</span> {}",
+                    render_async_record(&async_)
+                ));
+                self.0.dprintf_output.set(None);
+                self.0.handle_async_result(self, async_);
+            }
+            mi::OutOfBandResult::NotifyAsyncRecord(async_) => {
+                self.0.gdb_w.borrow().insert_line(&format!(
+                    "<span color=\"#CBCE79\">[NOTIFY]</span> {}",
+                    render_async_record(&async_)
+                ));
+                self.0.dprintf_output.set(None);
+                self.0.handle_async_result(self, async_);
+            }
+            mi::OutOfBandResult::ConsoleStreamRecord(str) => {
+                if let Some(bp_id) = self.0.dprintf_output.get() {
+                    self.0.dprintf_log_w.borrow().add_output(bp_id, &str);
+                    return;
+                }
+                self.0.gdb_w.borrow().insert_line(&format!(
+                    "<span color=\"#A1D490\">[CONSOLE]</span> {}",
+                    glib::markup_escape_text(&str)
+                ));
+                self.0.console_output.borrow_mut().push_str(&str);
+            }
+            mi::OutOfBandResult::TargetStreamRecord(str) => {
+                self.0.gdb_w.borrow().insert_line(&format!(
+                    "<span color=\"#90C3D4\">[TARGET]</span> {}",
+                    glib::markup_escape_text(&str)
+                ));
+            }
+            mi::OutOfBandResult::LogStreamRecord(str) => {
+                self.0.gdb_w.borrow().insert_line(&format!(
+                    "<span color=\"#D4A190\">[LOG]</span> {}",
+                    glib::markup_escape_text(&str)
+                ));
+            }
+        }
+    }
+
+    pub fn send_mi_msg(&self, msg: String) {
+        match *self.0.gdb.borrow_mut() {
+            None => {
+                // This should be a bug as the entry should be disabled when we're not connected
+                println!("Can't send mi msg! GDB not available!");
+            }
+            Some(ref mut gdb) => {
+                writeln!(gdb.stdin(), "{}", msg).unwrap();
+                self.0.gdb_w.borrow().insert_line(&format!(">>> {}", msg));
+                // let _ = gdb.stdin().flush();
+            }
+        }
+    }
+}
+
+// TODO find a better name
+macro_rules! some {
+    ( $x:expr ) => {
+        match $x {
+            Some(ret) => ret,
+            None => {
+                return;
+            }
+        }
+    };
+}
+
+macro_rules! some_ret {
+    ( $x:expr, $ret:expr ) => {
+        match $x {
+            Some(ret) => ret,
+            None => {
+                return $ret;
+            }
+        }
+    };
+}
+
+impl AppInner {
+    /// Send an MI command to GDB. `on_done` is called with the results when GDB answers with
+    /// `^done` (or `^running`). Errors are shown in the error bar.
+    fn send_request<F>(&self, command: String, on_done: F)
+    where
+        F: Fn(&AppInner, &App, HashMap<mi::Var, mi::Value>) + 'static,
+    {
+        self.send_request_with_error_handler(command, on_done, |inner, _outer, command, err| {
+            inner.show_request_error(command, err)
+        });
+    }
+
+    /// Same as `send_request`, but errors (`^error` results, timeouts, disconnects) are passed to
+    /// `on_error` instead of showing them in the error bar. `on_error` also gets the command.
+    fn send_request_with_error_handler<F, E>(&self, command: String, on_done: F, on_error: E)
+    where
+        F: Fn(&AppInner, &App, HashMap<mi::Var, mi::Value>) + 'static,
+        E: Fn(&AppInner, &App, &str, RequestError) + 'static,
+    {
+        self.send_request_with_timeout(command, Some(REQUEST_TIMEOUT), on_done, on_error);
+    }
+
+    /// Same as `send_request_with_error_handler`, with the given timeout instead of
+    /// `REQUEST_TIMEOUT`. `None` waits for the result until GDB disconnects, for commands that
+    /// can take long, e.g. listing all symbols or source files of a large program.
+    fn send_request_with_timeout<F, E>(
+        &self,
+        command: String,
+        timeout: Option<Duration>,
+        on_done: F,
+        on_error: E,
+    ) where
+        F: Fn(&AppInner, &App, HashMap<mi::Var, mi::Value>) + 'static,
+        E: Fn(&AppInner, &App, &str, RequestError) + 'static,
+    {
+        let mut gdb_ref = self.gdb.borrow_mut();
+        let gdb = match *gdb_ref {
+            None => {
+                println!("Can't send {:?}: GDB not available", command);
+                return;
+            }
+            Some(ref mut gdb) => gdb,
+        };
+        let token = self.requests.next_token();
+        if let Err(err) = writeln!(gdb.stdin(), "{}{}", token, command) {
             drop(gdb_ref);
+            self.show_error(&format!("Can't send command to GDB: {}", err));
+            return;
+        }
+        drop(gdb_ref);
+        let command_ = command.clone();
+        self.requests.add(
+            token,
+            command,
+            timeout,
+            Box::new(move |inner, outer, response| match response {
+                Ok(results) => on_done(inner, outer, results),
+                Err(err) => on_error(inner, outer, &command_, err),
+            }),
+        );
+    }
+
+    /// Run a CLI command, for things that don't have MI commands. `on_done` is called with the
+    /// console output of the command.
+    fn send_console_command<F>(&self, command: &str, on_done: F)
+    where
+        F: Fn(&AppInner, &App, &str) + 'static,
+    {
+        self.send_request(
+            format!("-interpreter-exec console {}", quote(command)),
+            move |inner, outer, _results| {
+                let output = inner.console_output.replace(String::new());
+                on_done(inner, outer, &output);
+            },
+        );
+    }
+
+    fn show_error(&self, error: &str) {
+        self.errors_w.borrow_mut().show_error(error);
+    }
+
+    fn show_request_error(&self, command: &str, err: RequestError) {
+        match err {
+            RequestError::Gdb(msg) => {
+                self.show_error(&format!("{}: {}", command, msg));
+            }
+            RequestError::Timeout => {
+                self.show_error(&format!(
+                    "{}: GDB didn't answer in {} seconds",
+                    command,
+                    REQUEST_TIMEOUT.as_secs()
+                ));
+            }
+            RequestError::Cancelled => {
+                // Disconnect is reported once, in `gdb_disconnected`
+            }
+        }
+    }
+
+    /// Time out the request GDB is running if it hasn't answered in its timeout.
+    fn check_timeouts(&self, outer: &App) {
+        if let Some(request) = self.requests.take_timed_out(Instant::now()) {
+            (request.handler)(self, outer, Err(RequestError::Timeout));
+        }
+    }
+
+    fn gdb_disconnected(&self, outer: &App) {
+        *self.gdb.borrow_mut() = None;
+        self.gdb_w.borrow().enter_disconnected_state();
+        let cancelled = self.requests.take_all();
+        if cancelled.is_empty() {
+            self.show_error("GDB disconnected");
+        } else {
+            self.show_error(&format!(
+                "GDB disconnected, {} pending commands cancelled",
+                cancelled.len()
+            ));
+        }
+        for request in cancelled {
+            (request.handler)(self, outer, Err(RequestError::Cancelled));
+        }
+    }
+
+    fn create_expr(&self, expr_str: String) {
+        self.send_request(
+            format!("-var-create - @ {}", expr_str),
+            move |app_inner, _app, results| match parsers::parse_var_create_result(results) {
+                None => {
+                    println!("Can't parse expression");
+                }
+                Some(expr) => {
+                    app_inner
+                        .expressions_w
+                        .borrow_mut()
+                        .add(expr_str.to_owned(), expr);
+                }
+            },
+        );
+    }
+
+    /// Fetch frames `low..high` of a thread. When fetching from the top of the stack first asks
+    /// for the stack depth. Frames are not fetched when the thread resumes or stops again in the
+    /// meantime, and responses that arrive after that are ignored.
+    fn get_thread_stack(&self, thread_id: i32, low: usize, high: usize) {
+        if high <= low {
+            return;
+        }
+        let generation = self.thread_generation(thread_id);
+        if low == 0 {
+            self.send_request_with_error_handler(
+                format!(
+                    "-stack-info-depth --thread {} {}",
+                    thread_id, MAX_STACK_DEPTH
+                ),
+                move |inner, outer, results| {
+                    stack_depth_cb(inner, outer, results, generation, thread_id);
+                    inner.get_thread_frames(thread_id, low, high, generation);
+                },
+                move |inner, _outer, command, err| {
+                    thread_stack_error(inner, command, err, generation, thread_id)
+                },
+            );
+        } else {
+            self.get_thread_frames(thread_id, low, high, generation);
+        }
+    }
+
+    /// Fetch frames `low..high` of a thread, unless the thread resumed or stopped again since
+    /// `generation`.
+    fn get_thread_frames(&self, thread_id: i32, low: usize, high: usize, generation: u64) {
+        if generation != self.thread_generation(thread_id) {
+            return;
+        }
+        // Both bounds are inclusive in -stack-list-frames
+        self.send_request_with_error_handler(
+            format!(
+                "-stack-list-frames --thread {} {} {}",
+                thread_id,
+                low,
+                high - 1
+            ),
+            move |inner, outer, results| {
+                thread_stack_cb(inner, outer, results, generation, thread_id, low)
+            },
+            move |inner, _outer, command, err| {
+                thread_stack_error(inner, command, err, generation, thread_id)
+            },
+        );
+    }
+
+    /// Called when the parallel stacks view is enabled or the thread filter is changed. Fetches
+    /// full backtraces of threads if they're not available yet.
+    fn full_stacks_needed(&self) {
+        let threads_w = self.threads_w.borrow();
+        if threads_w.needs_full_stacks() && !threads_w.has_full_stacks() {
+            drop(threads_w);
+            self.get_full_stacks();
+        } else {
+            threads_w.apply_filter();
+        }
+    }
+
+    /// Fetch full backtraces of all stopped threads.
+    fn get_full_stacks(&self) {
+        self.send_request("-thread-info".to_string(), |inner, _outer, results| {
+            let (threads, _) = some!(parsers::parse_thread_info_result(results));
+            inner.fetch_full_stacks(stopped_thread_ids(&threads));
+        });
+    }
+
+    /// Fetch full backtraces of the given threads, pass them to the threads widget when all of
+    /// them arrive. Threads that resume or exit in the meantime are skipped.
+    fn fetch_full_stacks(&self, thread_ids: Vec<i32>) {
+        let fetch = self.full_stacks_fetch.get() + 1;
+        self.full_stacks_fetch.set(fetch);
+        // Reversed as threads are popped from the end
+        let threads = thread_ids
+            .into_iter()
+            .rev()
+            .map(|thread_id| (thread_id, self.thread_generation(thread_id)))
+            .collect::<Vec<_>>();
+        self.fetch_next_full_stack(
+            fetch,
+            Rc::new(RefCell::new(threads)),
+            Rc::new(RefCell::new(vec![])),
+        );
+    }
+
+    /// Fetch the full backtrace of the next thread in `threads` (thread ids and generations), then
+    /// the rest of the threads. Backtraces are fetched one at a time so that nothing is sent for a
+    /// fetch that a newer fetch replaced, or for threads that resumed or stopped again.
+    fn fetch_next_full_stack(
+        &self,
+        fetch: u64,
+        threads: Rc<RefCell<Vec<(i32, u64)>>>,
+        stacks: Rc<RefCell<Vec<(i32, Backtrace)>>>,
+    ) {
+        if fetch != self.full_stacks_fetch.get() {
+            return;
+        }
+        let (thread_id, generation) = loop {
+            match threads.borrow_mut().pop() {
+                None => {
+                    let stacks = std::mem::replace(&mut *stacks.borrow_mut(), vec![]);
+                    self.threads_w.borrow_mut().set_full_stacks(stacks);
+                    return;
+                }
+                Some((thread_id, generation)) => {
+                    if generation == self.thread_generation(thread_id) {
+                        break (thread_id, generation);
+                    }
+                }
+            }
+        };
+        let threads_ = threads.clone();
+        let stacks_ = stacks.clone();
+        // No timeout, deep stacks take long to unwind
+        self.send_request_with_timeout(
+            format!(
+                "-stack-list-frames --thread {} 0 {}",
+                thread_id,
+                MAX_STACK_DEPTH - 1
+            ),
+            None,
+            move |inner, _outer, mut results| {
+                let bt = results
+                    .remove("stack")
+                    .and_then(|bt| bt.get_result_list())
+                    .and_then(parsers::parse_backtrace);
+                if let Some(bt) = bt {
+                    if generation == inner.thread_generation(thread_id) {
+                        stacks_.borrow_mut().push((thread_id, bt));
+                    }
+                }
+                inner.fetch_next_full_stack(fetch, threads_.clone(), stacks_.clone());
+            },
+            move |inner, _outer, _command, _err| {
+                inner.fetch_next_full_stack(fetch, threads.clone(), stacks.clone());
+            },
+        );
+    }
+
+    /// Generation of the last stop or resume of a thread.
+    fn thread_generation(&self, thread_id: i32) -> u64 {
+        let thread_generation = self
+            .thread_generations
+            .borrow()
+            .get(&thread_id)
+            .cloned()
+            .unwrap_or(0);
+        std::cmp::max(thread_generation, self.all_threads_generation.get())
+    }
 
-            self.callbacks.borrow_mut().insert(
-                token,
-                Box::new(move |app_inner, _app, mut result| {
-                    if result.class != mi::ResultClass::Done {
-                        println!("Error: {:?}", result);
-                        return;
+    /// Start a new generation for the given threads (`None` means all threads), after the threads
+    /// stop or resume.
+    fn new_generation(&self, thread_ids: Option<&[i32]>) {
+        let generation = self.generation.get() + 1;
+        self.generation.set(generation);
+        match thread_ids {
+            None => {
+                self.all_threads_generation.set(generation);
+            }
+            Some(thread_ids) => {
+                let mut thread_generations = self.thread_generations.borrow_mut();
+                for thread_id in thread_ids {
+                    thread_generations.insert(*thread_id, generation);
+                }
+            }
+        }
+    }
+
+    /// Show current values of fork settings in the inferiors widget.
+    fn get_fork_settings(&self) {
+        self.send_request(
+            "-gdb-show follow-fork-mode".to_string(),
+            |inner, _outer, results| {
+                // [RESULT] Done: value = parent
+                let mode = some!(results.get("value").and_then(|v| v.get_const_ref()));
+                inner.inferiors_w.borrow().set_follow_fork_mode(mode);
+            },
+        );
+        self.send_request(
+            "-gdb-show detach-on-fork".to_string(),
+            |inner, _outer, results| {
+                let detach = some!(results.get("value").and_then(|v| v.get_const_ref()));
+                inner
+                    .inferiors_w
+                    .borrow()
+                    .set_detach_on_fork(detach == "on");
+            },
+        );
+    }
+
+    /// Fetch inferiors with their pids and executables.
+    fn list_thread_groups(&self) {
+        self.send_request(
+            "-list-thread-groups".to_string(),
+            |inner, _outer, results| {
+                let groups = some!(parsers::parse_list_thread_groups_result(results));
+                for group in groups {
+                    inner.update_inferior(&group.id, |inferior| {
+                        inferior.pid = group.pid;
+                        inferior.executable = group.executable.clone();
+                    });
+                }
+                inner.executable_changed();
+            },
+        );
+    }
+
+    /// Load breakpoint groups and source files of the program (executable of the first inferior)
+    /// if it changed.
+    fn executable_changed(&self) {
+        let executable = some!(self
+            .inferiors
+            .borrow()
+            .first()
+            .and_then(|inferior| inferior.executable.clone()));
+        if self.executable.borrow().as_ref() == Some(&executable) {
+            return;
+        }
+        self.breakpoints_w
+            .borrow()
+            .set_groups(BreakpointGroups::load(&executable));
+        *self.executable.borrow_mut() = Some(executable);
+        self.list_source_files();
+    }
+
+    fn save_breakpoint_groups(&self, groups: &BreakpointGroups) {
+        match *self.executable.borrow() {
+            None => {
+                self.show_error("Can't save breakpoint groups: program is not known yet");
+            }
+            Some(ref executable) => {
+                if let Err(err) = groups.save(executable) {
+                    self.show_error(&format!("Can't save breakpoint groups: {}", err));
+                }
+            }
+        }
+    }
+
+    /// Update an inferior, adding it if it doesn't exist, and render it in the inferiors and
+    /// threads widgets.
+    fn update_inferior<F: FnOnce(&mut Inferior)>(&self, id: &str, update: F) {
+        let mut inferiors = self.inferiors.borrow_mut();
+        let idx = match inferiors.iter().position(|inferior| inferior.id == id) {
+            Some(idx) => idx,
+            None => {
+                inferiors.push(Inferior::new(id.to_string()));
+                inferiors.len() - 1
+            }
+        };
+        let inferior = &mut inferiors[idx];
+        update(inferior);
+        self.inferiors_w.borrow().add_or_update_inferior(inferior);
+        self.threads_w.borrow_mut().update_inferior(inferior);
+    }
+
+    fn search_symbols(&self, kind: SymbolKind, regex: &str, nondebug: bool) {
+        // No timeout, searching all symbols of a large program takes long
+        self.send_request_with_timeout(
+            commands::symbol_info(kind, regex, nondebug, widgets::symbols::MAX_SYMBOLS),
+            None,
+            move |inner, _outer, results| {
+                let info = some!(parsers::parse_symbol_info_result(results));
+                inner.symbols_w.borrow().show_symbols(kind, &info);
+            },
+            |inner, _outer, command, err| {
+                // e.g. invalid regex, or GDB older than 10 without `-symbol-info-*`
+                inner.symbols_w.borrow().show_error("Search failed");
+                inner.show_request_error(command, err);
+            },
+        );
+    }
+
+    /// Fetch source files of the program. Does nothing if the program is not known yet (GDB
+    /// started without a program), source files are fetched when it becomes known.
+    fn list_source_files(&self) {
+        if self.executable.borrow().is_none() {
+            return;
+        }
+        // No timeout, GDB reads the debug info of all compilation units
+        self.send_request_with_timeout(
+            "-file-list-exec-source-files".to_string(),
+            None,
+            |inner, _outer, results| {
+                let files = some!(parsers::parse_source_files_result(results));
+                inner.source_files_w.borrow().set_files(files);
+            },
+            |inner, _outer, command, err| inner.show_request_error(command, err),
+        );
+    }
+
+    /// Fetch shared libraries of the current inferior.
+    fn list_shared_libraries(&self) {
+        self.send_request(
+            "-file-list-shared-libraries".to_string(),
+            |inner, _outer, results| {
+                let libraries = some!(parsers::parse_shared_libraries_result(results));
+                let libraries_w = inner.libraries_w.borrow();
+                for library in &libraries {
+                    libraries_w.add_or_update_library(library);
+                }
+            },
+        );
+    }
+
+    fn load_library_symbols(&self, path: &str) {
+        // There's no MI command for this
+        let command = format!("sharedlibrary ^{}$", escape_regex(path));
+        self.send_console_command(&command, |inner, _outer, _output| {
+            // No `library-loaded` record for symbols loaded afterwards
+            inner.list_shared_libraries();
+        });
+    }
+
+    /// Stop when a library with the same file name is loaded, e.g. with `dlopen`. Directory of
+    /// the library is ignored as it may be loaded from a different path in the next run.
+    fn break_on_library_load(&self, path: &str) {
+        let file_name = path.rsplit('/').next().unwrap_or(path);
+        let regex = format!("{}$", escape_regex(file_name));
+        self.catchpoint_added(CatchpointType::Load, &regex);
+    }
+
+    /// Fetch how signals are handled.
+    fn get_signals(&self) {
+        self.send_console_command("info signals", |inner, _outer, output| {
+            let signals = parsers::parse_signal_table(output);
+            inner.signals_w.borrow().update_signals(&signals);
+        });
+    }
+
+    /// Change how a signal is handled. `keyword` is one of the `handle` keywords, e.g. "nostop".
+    fn handle_signal(&self, signal: &str, keyword: &str) {
+        // `handle` prints the new settings of the signal. Note that some settings imply others
+        // (e.g. "stop" implies "print") so we can't just update the toggled setting.
+        self.send_console_command(
+            &format!("handle {} {}", signal, keyword),
+            |inner, _outer, output| {
+                let signals = parsers::parse_signal_table(output);
+                inner.signals_w.borrow().update_signals(&signals);
+            },
+        );
+    }
+
+    /// Make a thread the current thread, so that stepping and evaluating expressions apply to it.
+    fn select_thread(&self, thread_id: i32) {
+        self.send_request(
+            format!("-thread-select {}", thread_id),
+            move |inner, _outer, _results| {
+                // [RESULT] Done: new-thread-id = 2, frame = {...}
+                inner.threads_w.borrow().set_current_thread(thread_id);
+            },
+        );
+    }
+
+    /// Continue, interrupt or step a single thread (non-stop mode).
+    fn thread_action(&self, thread_id: i32, action: ThreadAction) {
+        let command = match action {
+            ThreadAction::Continue => "-exec-continue",
+            ThreadAction::Interrupt => "-exec-interrupt",
+            ThreadAction::Step => "-exec-step",
+            ThreadAction::Next => "-exec-next",
+        };
+        self.send_request(format!("{} --thread {}", command, thread_id), |_, _, _| {});
+    }
+
+    fn get_expr_children(&self, name: &str, from: usize, to: usize) {
+        let name_ = name.to_owned();
+        self.send_request(
+            format!("-var-list-children --all-values {} {} {}", name, from, to),
+            move |app_inner, _app, results| {
+                let name = &name_;
+                match parsers::parse_var_list_children_result(results) {
+                    None => {
+                        println!("Can't parse children list");
                     }
-                    match parsers::parse_var_list_children_result(result.results) {
-                        None => {
-                            println!("Can't parse children list");
-                            return;
-                        }
-                        Some(exprs) => {
-                            for expr in exprs {
-                                app_inner.expressions_w.borrow_mut().add(
-                                    expr.name,
-                                    expr.expr.unwrap(),
-                                    expr.value,
-                                    expr.type_,
-                                    expr.n_children != 0,
-                                )
+                    Some((exprs, has_more)) => {
+                        // There's no way to set format of all children at once, so the format is
+                        // only set for children with values affected by it
+                        let child_names = exprs
+                            .iter()
+                            .filter(|expr| widgets::expressions::format_affects(&expr.value))
+                            .map(|expr| expr.name.clone())
+                            .collect::<Vec<_>>();
+                        app_inner
+                            .expressions_w
+                            .borrow_mut()
+                            .add_children(name, to, exprs, has_more);
+                        // Children inherit display format of the parent
+                        let format = app_inner.expressions_w.borrow().get_format(name);
+                        if let Some(format) = format {
+                            if format != VarFormat::Natural {
+                                for child_name in child_names {
+                                    app_inner.set_expr_format(&child_name, format);
+                                }
                             }
                         }
                     }
-                }),
-            );
+                }
+            },
+        );
+    }
+
+    fn set_expr_format(&self, name: &str, format: VarFormat) {
+        let format_str = match format {
+            VarFormat::Natural => "natural",
+            VarFormat::Hexadecimal => "hexadecimal",
+            VarFormat::Binary => "binary",
+            VarFormat::Octal => "octal",
+            VarFormat::Decimal => "decimal",
+        };
+        let name_ = name.to_owned();
+        self.send_request(
+            format!("-var-set-format {} {}", name, format_str),
+            move |app_inner, _app, mut results| {
+                let value = some!(results.remove("value"));
+                let value = some!(value.get_const());
+                app_inner
+                    .expressions_w
+                    .borrow_mut()
+                    .set_formatted_value(name_.clone(), value);
+            },
+        );
+    }
+
+    /// Create a new varobj for an invalid top-level expression (e.g. after the program is re-run
+    /// or a shared library is reloaded), and delete the invalid one. Does nothing if the varobj is
+    /// already being created again.
+    fn recreate_expr(&self, name: &str) {
+        let expr_str = some!(self.expressions_w.borrow().get_expr(name));
+        if !self.recreating_exprs.borrow_mut().insert(name.to_owned()) {
+            return;
         }
+        let name = name.to_owned();
+        let name_ = name.clone();
+        self.send_request_with_error_handler(
+            format!("-var-create - @ {}", expr_str),
+            move |inner, _outer, results| {
+                inner.recreating_exprs.borrow_mut().remove(&name);
+                let expr = some!(parsers::parse_var_create_result(results));
+                let new_name = expr.name.clone();
+                let format = inner.expressions_w.borrow_mut().replace(&name, expr);
+                match format {
+                    None => {
+                        // The expression was removed in the meantime
+                        inner.send_request(format!("-var-delete {}", new_name), |_, _, _| {});
+                    }
+                    Some(format) => {
+                        inner.send_request(format!("-var-delete {}", name), |_, _, _| {});
+                        if format != VarFormat::Natural {
+                            inner.set_expr_format(&new_name, format);
+                        }
+                    }
+                }
+            },
+            move |inner, _outer, _command, _err| {
+                // Expression can't be evaluated in the current context. It stays greyed out,
+                // GDB reports invalid varobjs on every update so this is tried again.
+                inner.recreating_exprs.borrow_mut().remove(&name_);
+            },
+        );
+    }
+
+    fn delete_expr(&self, name: &str) {
+        let name_ = name.to_owned();
+        self.send_request(
+            format!("-var-delete {}", name),
+            move |app_inner, _app, _| {
+                app_inner.expressions_w.borrow_mut().remove(&name_);
+            },
+        );
     }
 
     fn breakpoint_toggled(&self, bp_id: u32, enable: bool) {
-        // TODO: We should get token if gdb is available, but can't move this below as it borrowchk
-        // still not smart enough.
-        let token = self.get_token();
-        let mut gdb_ref = self.gdb.borrow_mut();
-        if let Some(ref mut gdb) = *gdb_ref {
-            let stdin = gdb.stdin();
+        let command = if enable {
+            format!("-break-enable {}", bp_id)
+        } else {
+            format!("-break-disable {}", bp_id)
+        };
+        self.send_request_with_error_handler(
+            command,
+            move |app_inner, _app, _results| {
+                app_inner
+                    .breakpoints_w
+                    .borrow_mut()
+                    .toggle_breakpoint(bp_id, enable)
+            },
+            move |app_inner, _app, command, err| {
+                // Revert the toggle button
+                app_inner
+                    .breakpoints_w
+                    .borrow_mut()
+                    .toggle_breakpoint(bp_id, !enable);
+                app_inner.show_request_error(command, err);
+            },
+        );
+    }
+
+    /// Enable or disable multiple breakpoints with one command.
+    fn breakpoints_toggled(&self, bp_ids: &[u32], enable: bool) {
+        let bp_ids = bp_ids.to_vec();
+        let command = format!(
+            "{} {}",
             if enable {
-                writeln!(stdin, "{}-break-enable {}", token, bp_id).unwrap();
+                "-break-enable"
             } else {
-                writeln!(stdin, "{}-break-disable {}", token, bp_id).unwrap();
+                "-break-disable"
+            },
+            join_numbers(&bp_ids)
+        );
+        self.send_request(command, move |inner, _outer, _results| {
+            let breakpoints_w = inner.breakpoints_w.borrow();
+            for bp_id in &bp_ids {
+                breakpoints_w.toggle_breakpoint(*bp_id, enable);
             }
-            drop(gdb_ref);
-            self.callbacks.borrow_mut().insert(
-                token,
-                Box::new(move |app_inner, _app, _result| {
-                    // TODO: Check if the result class is "Done"
-                    app_inner
-                        .breakpoints_w
-                        .borrow_mut()
-                        .toggle_breakpoint(bp_id, enable)
-                }),
-            );
-        }
+        });
+    }
+
+    /// Delete multiple breakpoints with one command.
+    fn breakpoints_deleted(&self, bp_ids: &[u32]) {
+        let bp_ids = bp_ids.to_vec();
+        let command = format!("-break-delete {}", join_numbers(&bp_ids));
+        self.send_request(command, move |inner, outer, _results| {
+            // No `breakpoint-deleted` records for MI commands
+            for bp_id in &bp_ids {
+                inner.emit_event(outer, &Event::BreakpointDeleted { id: *bp_id });
+            }
+        });
     }
 
     /*
@@ -428,35 +1591,275 @@ impl AppInner {
     }
     */
 
-    fn breakpoint_added(&self, location: String, condition: String) {
-        // TODO: Same as above, we need token only if gdb is available
-        let token = self.get_token();
-        let mut gdb_ref = self.gdb.borrow_mut();
-        if let Some(ref mut gdb) = *gdb_ref {
-            let stdin = gdb.stdin();
-            if condition.is_empty() {
-                writeln!(stdin, "{}-break-insert {}", token, location).unwrap();
-            } else {
-                writeln!(
-                    stdin,
-                    "{}-break-insert -c \"{}\" {}",
-                    token, condition, location
-                )
-                .unwrap();
+    /// Create a breakpoint of the "add breakpoint" form. The breakpoint is inserted disabled and
+    /// without the condition first, so that errors in the location and in the condition can be
+    /// shown next to the right entries, and the breakpoint can't be hit before it's validated.
+    fn breakpoint_added(&self, spec: BreakpointSpec, origin: BreakpointOrigin) {
+        let insert_spec = BreakpointSpec {
+            condition: String::new(),
+            disabled: true,
+            ..spec.clone()
+        };
+        let spec_ = spec.clone();
+        self.send_request_with_error_handler(
+            commands::break_insert(&insert_spec),
+            move |inner, _outer, results| {
+                let bkpt = some!(parsers::parse_break_insert_result(results));
+                inner.new_breakpoint_inserted(bkpt.number, &spec, origin);
+            },
+            move |inner, _outer, _command, err| {
+                let field = match err {
+                    RequestError::Gdb(ref msg) => insert_error_field(&spec_, msg),
+                    _ => BreakpointField::Location,
+                };
+                inner.breakpoint_add_failed(origin, field, &request_error_msg(&err));
+            },
+        );
+    }
+
+    /// Second step of `breakpoint_added`: set the condition, which GDB parses in the scope of the
+    /// breakpoint's locations. The breakpoint is deleted if the condition doesn't parse.
+    fn new_breakpoint_inserted(&self, bp_id: u32, spec: &BreakpointSpec, origin: BreakpointOrigin) {
+        let disabled = spec.disabled;
+        if spec.condition.is_empty() {
+            self.new_breakpoint_validated(bp_id, disabled, origin);
+            return;
+        }
+        self.send_request_with_error_handler(
+            format!("-break-condition {} {}", bp_id, quote(&spec.condition)),
+            move |inner, _outer, _results| {
+                inner.new_breakpoint_validated(bp_id, disabled, origin);
+            },
+            move |inner, _outer, _command, err| {
+                inner.new_breakpoint_failed(bp_id, origin, BreakpointField::Condition, &err);
+            },
+        );
+    }
+
+    /// Third step of `breakpoint_added`: enable the breakpoint unless it's created disabled. The
+    /// breakpoint is deleted if it can't be enabled.
+    fn new_breakpoint_validated(&self, bp_id: u32, disabled: bool, origin: BreakpointOrigin) {
+        if disabled {
+            self.new_breakpoint_created(bp_id, origin);
+            return;
+        }
+        self.send_request_with_error_handler(
+            format!("-break-enable {}", bp_id),
+            move |inner, _outer, _results| {
+                inner.new_breakpoint_created(bp_id, origin);
+            },
+            move |inner, _outer, _command, err| {
+                inner.new_breakpoint_failed(bp_id, origin, BreakpointField::Location, &err);
+            },
+        );
+    }
+
+    /// Last step of `breakpoint_added`: render the breakpoint.
+    fn new_breakpoint_created(&self, bp_id: u32, origin: BreakpointOrigin) {
+        if origin == BreakpointOrigin::AddForm {
+            self.breakpoints_w.borrow().breakpoint_add_done();
+        }
+        self.fetch_new_breakpoint(bp_id);
+    }
+
+    /// A step of `breakpoint_added` after inserting the breakpoint failed. Deletes the breakpoint,
+    /// so that no half-configured (e.g. disabled) breakpoint is left in GDB, and shows the error.
+    fn new_breakpoint_failed(
+        &self,
+        bp_id: u32,
+        origin: BreakpointOrigin,
+        field: BreakpointField,
+        err: &RequestError,
+    ) {
+        self.send_request(format!("-break-delete {}", bp_id), |_, _, _| {});
+        self.breakpoint_add_failed(origin, field, &request_error_msg(err));
+    }
+
+    /// Show why a breakpoint of `breakpoint_added` can't be created.
+    fn breakpoint_add_failed(&self, origin: BreakpointOrigin, field: BreakpointField, msg: &str) {
+        match origin {
+            BreakpointOrigin::AddForm => {
+                self.breakpoints_w
+                    .borrow()
+                    .breakpoint_add_failed(field, msg);
             }
-            drop(gdb_ref);
-            self.callbacks.borrow_mut().insert(
-                token,
-                Box::new(move |app_inner, _app, result| {
-                    let results = result.results;
+            BreakpointOrigin::Symbols | BreakpointOrigin::Source => {
+                self.show_error(&format!("Can't create breakpoint: {}", msg));
+            }
+        }
+    }
+
+    /// Fetch a breakpoint created with MI commands and render it. GDB doesn't send
+    /// `breakpoint-created` records for those.
+    fn fetch_new_breakpoint(&self, bp_id: u32) {
+        self.send_request(format!("-break-info {}", bp_id), |inner, outer, results| {
+            for bkpt in some!(parsers::parse_break_list_result(results)) {
+                inner.emit_event(outer, &Event::BreakpointCreated(bkpt));
+            }
+        });
+    }
+
+    /// Create a catchpoint. See `mi::commands::catchpoint` for `arg`.
+    fn catchpoint_added(&self, type_: CatchpointType, arg: &str) {
+        match commands::catchpoint(type_, arg) {
+            None => {
+                println!("Can't create catchpoint of type {:?}", type_);
+            }
+            Some(Command::Mi(command)) => {
+                self.send_request(command, |inner, outer, results| {
+                    inner.breakpoint_inserted(outer, results);
+                });
+            }
+            Some(Command::Console(command)) => {
+                // GDB sends `breakpoint-created` records for catchpoints created with CLI
+                // commands, so no need to handle the result
+                self.send_console_command(&command, |_, _, _| {});
+            }
+        }
+    }
+
+    /// Write breakpoints to a GDB script.
+    fn export_breakpoints(&self, path: &Path) {
+        let path = path.to_path_buf();
+        self.send_request("-break-list".to_string(), move |inner, _outer, results| {
+            let bkpts = some!(parsers::parse_break_list_result(results));
+            if let Err(err) = fs::write(&path, breakpoint_script::write_script(&bkpts)) {
+                inner.show_error(&format!("Can't write {}: {}", path.display(), err));
+            }
+        });
+    }
+
+    /// Create breakpoints in a GDB script, e.g. one written by `export_breakpoints` or by
+    /// `save breakpoints`.
+    fn import_breakpoints(&self, path: &Path) {
+        let script = match fs::read_to_string(path) {
+            Ok(script) => script,
+            Err(err) => {
+                self.show_error(&format!("Can't read {}: {}", path.display(), err));
+                return;
+            }
+        };
+        let (bps, skipped) = breakpoint_script::parse_script(&script);
+        for bp in bps {
+            self.import_breakpoint(bp);
+        }
+        if !skipped.is_empty() {
+            self.show_error(&format!(
+                "Skipped lines of {}: {}",
+                path.display(),
+                skipped.join("; ")
+            ));
+        }
+    }
+
+    fn import_breakpoint(&self, bp: SavedBreakpoint) {
+        let command = match bp.create {
+            Create::Breakpoint(ref spec) => Command::Mi(commands::break_insert(spec)),
+            Create::Catchpoint(type_, ref arg) => some!(commands::catchpoint(type_, arg)),
+            Create::Other(ref command) => Command::Console(command.clone()),
+        };
+        match command {
+            Command::Mi(command) => {
+                self.send_request(command, move |inner, outer, results| {
                     let bkpt = some!(parsers::parse_break_insert_result(results));
-                    app_inner
-                        .breakpoints_w
-                        .borrow_mut()
-                        .add_or_update_breakpoint(&bkpt);
-                }),
-            );
+                    let number = bkpt.number;
+                    inner.emit_event(outer, &Event::BreakpointCreated(bkpt));
+                    inner.configure_imported_breakpoint(number, &bp);
+                });
+            }
+            Command::Console(command) => {
+                // Breakpoint is added to widgets by the `breakpoint-created` record, we just need
+                // its number. `$bpnum` is the number of the last created breakpoint, it's compared
+                // to the number before the command as the command may not create a breakpoint
+                // (e.g. an unsupported `catch` command).
+                let before: Rc<Cell<Option<u32>>> = Rc::new(Cell::new(None));
+                {
+                    let before = before.clone();
+                    self.get_last_breakpoint_number(move |_inner, number| before.set(number));
+                }
+                self.send_console_command(&command, |_, _, _| {});
+                self.get_last_breakpoint_number(move |inner, number| match number {
+                    Some(number) if Some(number) != before.get() => {
+                        inner.configure_imported_breakpoint(number, &bp);
+                    }
+                    _ => {
+                        inner.show_error(&format!("Can't import breakpoint: {}", command));
+                    }
+                });
+            }
+        }
+    }
+
+    /// Get `$bpnum`, the number of the last created breakpoint. `None` if no breakpoint was
+    /// created yet.
+    fn get_last_breakpoint_number<F>(&self, on_done: F)
+    where
+        F: Fn(&AppInner, Option<u32>) + 'static,
+    {
+        self.send_request(
+            "-data-evaluate-expression $bpnum".to_string(),
+            move |inner, _outer, mut results| {
+                // "void" when no breakpoint was created
+                let number = results
+                    .remove("value")
+                    .and_then(|value| value.get_const())
+                    .and_then(|value| value.parse::<u32>().ok());
+                on_done(inner, number);
+            },
+        );
+    }
+
+    /// Set condition, ignore count, commands and enabled state of an imported breakpoint.
+    fn configure_imported_breakpoint(&self, number: u32, bp: &SavedBreakpoint) {
+        let mut mi_commands = vec![];
+        if let Some(ref cond) = bp.condition {
+            mi_commands.push(format!("-break-condition {} {}", number, quote(cond)));
+        }
+        if bp.ignore_count != 0 {
+            mi_commands.push(format!("-break-after {} {}", number, bp.ignore_count));
+        }
+        if !bp.commands.is_empty() {
+            mi_commands.push(commands::break_commands(number, &bp.commands));
+        }
+        if !bp.enabled {
+            mi_commands.push(format!("-break-disable {}", number));
+        }
+        if mi_commands.is_empty() {
+            return;
+        }
+        for command in mi_commands {
+            self.send_request(command, |_, _, _| {});
         }
+        // Commands are run in order, so this gets the final state
+        self.update_breakpoint(number);
+    }
+
+    /// Handle result of a command that creates a breakpoint (`-break-insert`, `-catch-throw`
+    /// etc.). GDB only sends `breakpoint-created` records for breakpoints not created with MI
+    /// commands, so we emit the event ourselves.
+    fn breakpoint_inserted(&self, outer: &App, results: HashMap<mi::Var, mi::Value>) {
+        let bkpt = some!(parsers::parse_break_insert_result(results));
+        self.emit_event(outer, &Event::BreakpointCreated(bkpt));
+    }
+
+    /// Fetch a breakpoint and update it in widgets. Needed after modifying breakpoints with MI
+    /// commands, as GDB only sends `breakpoint-modified` records for changes made otherwise.
+    fn update_breakpoint(&self, bp_id: u32) {
+        self.send_request(format!("-break-info {}", bp_id), |inner, outer, results| {
+            for bkpt in some!(parsers::parse_break_list_result(results)) {
+                inner.emit_event(outer, &Event::BreakpointModified(bkpt));
+            }
+        });
+    }
+
+    /// Replace commands of a breakpoint. Empty `commands` removes the commands.
+    fn breakpoint_commands_set(&self, bp_id: u32, commands: Vec<String>) {
+        self.send_request(
+            commands::break_commands(bp_id, &commands),
+            move |inner, _outer, _results| {
+                inner.update_breakpoint(bp_id);
+            },
+        );
     }
 
     /*
@@ -498,133 +1901,309 @@ impl AppInner {
     */
 
     fn handle_result(&self, outer: &App, result: mi::Result) {
-        if let Some(ref token) = result.token {
-            let cb = self.callbacks.borrow_mut().remove(&token);
-            match cb {
+        if let Some(token) = result.token {
+            match self.requests.take(token) {
                 None => {
+                    // Results of timed out requests also end up here
                     println!("Can't find callback for result {}", token);
                 }
-                Some(cb) => {
-                    cb(self, outer, result);
+                Some(request) => {
+                    (request.handler)(self, outer, requests::result_response(result));
                 }
             }
         }
+        // Console output belongs to this result, handlers of console commands take it
+        self.console_output.borrow_mut().clear();
     }
 
-    // true -> execution stopped, false -> something else
-    fn handle_async_result(&self, _outer: &App, mut async_: mi::AsyncRecord) {
-        match async_.class.as_str() {
-            "breakpoint-created" | "breakpoint-modified" => {
-                let bkpt = some!(async_.results.remove("bkpt"));
-                let bkpt = some!(bkpt.get_tuple());
-                let bkpt = some!(parsers::parse_breakpoint(bkpt));
-                self.breakpoints_w
-                    .borrow_mut()
-                    .add_or_update_breakpoint(&bkpt);
-            }
-            "stopped" => {
-                // Execution stopped. Update threads.
-                let token = self.get_token();
-                let mut gdb_ref = self.gdb.borrow_mut();
-                let mut gdb = gdb_ref.as_mut().unwrap();
-                writeln!(gdb.stdin(), "{}-thread-info", token).unwrap();
-                let token = self.get_token();
-                self.threads_w.borrow_mut().clear();
-                self.callbacks
-                    .borrow_mut()
-                    .insert(token, Box::new(thread_info_cb));
+    fn handle_async_result(&self, outer: &App, async_: mi::AsyncRecord) {
+        let event = some!(parsers::parse_async_record(async_));
+        if let Event::BreakpointModified(ref bp) = event {
+            // GDB sends `breakpoint-modified` (for the hit count) right before running the printf
+            // of a dprintf
+            let hit = match self.dprintf_hits.borrow().get(&bp.number) {
+                None => bp.hits > 0,
+                Some(hits) => bp.hits > *hits,
+            };
+            if bp.type_ == BreakpointType::Dprintf && hit {
+                self.dprintf_output.set(Some(bp.number));
+            }
+        }
+        self.emit_event(outer, &event);
+    }
+
+    fn emit_event(&self, outer: &App, event: &Event) {
+        match event {
+            Event::BreakpointCreated(bp) | Event::BreakpointModified(bp)
+                if bp.type_ == BreakpointType::Dprintf =>
+            {
+                self.dprintf_hits.borrow_mut().insert(bp.number, bp.hits);
+            }
+            Event::BreakpointDeleted { id } => {
+                self.dprintf_hits.borrow_mut().remove(id);
+            }
+            _ => {}
+        }
+        self.events.emit(|handler| handler(self, outer, event));
+    }
+
+    /// Reactions to events that need to send commands or update state shared by widgets. Events
+    /// that only update a single widget are handled by the widget, see `App::new`.
+    fn handle_event(&self, _outer: &App, event: &Event) {
+        match event {
+            Event::Stopped(stop) => {
+                // Execution stopped. Update threads. Threads are updated in place when the
+                // backtraces arrive. In non-stop mode only the stopped threads are updated.
+                let stopped_threads = stop.stopped_threads.clone();
+                self.new_generation(stopped_threads.as_ref().map(|ids| ids.as_slice()));
+                self.send_request("-thread-info".to_string(), move |inner, outer, results| {
+                    thread_info_cb(inner, outer, results, stopped_threads.as_ref())
+                });
                 // Update expressions
-                writeln!(gdb.stdin(), "{}-var-update --all-values *", token).unwrap();
-                drop(gdb_ref);
-                self.callbacks
+                self.send_request("-var-update --all-values *".to_string(), var_update_cb);
+                if let Some(StopReason::Exec { .. }) = stop.reason {
+                    // New executable, update breakpoint groups and source files
+                    self.list_thread_groups();
+                }
+            }
+            Event::Running { thread_ids } => {
+                self.new_generation(thread_ids.as_ref().map(|ids| ids.as_slice()));
+            }
+            Event::ThreadGroupAdded { id } => {
+                // New inferior, e.g. a forked process with detach-on-fork off
+                self.update_inferior(id, |_| {});
+            }
+            Event::ThreadGroupRemoved { id } => {
+                self.inferiors
                     .borrow_mut()
-                    .insert(token, Box::new(var_update_cb));
+                    .retain(|inferior| &inferior.id != id);
+                self.inferiors_w.borrow().remove_inferior(id);
+                self.threads_w.borrow_mut().remove_inferior(id);
+            }
+            Event::ThreadGroupStarted { id, pid } => {
+                self.update_inferior(id, |inferior| {
+                    inferior.pid = *pid;
+                    inferior.exit_code = None;
+                });
+                // Executable of the process is only available in -list-thread-groups
+                self.list_thread_groups();
+            }
+            Event::ThreadGroupExited { id, exit_code } => {
+                self.update_inferior(id, |inferior| {
+                    inferior.pid = None;
+                    inferior.exit_code = *exit_code;
+                });
+            }
+            Event::MemoryChanged { .. } => {
+                // Memory changed with "set var" etc. in the console
+                self.send_request("-var-update --all-values *".to_string(), var_update_cb);
             }
             _ => {}
         }
     }
 }
 
-fn thread_info_cb(inner: &AppInner, _outer: &App, mut result: mi::Result) {
+/// `stopped_threads`: threads to update, `None` means all threads.
+fn thread_info_cb(
+    inner: &AppInner,
+    _outer: &App,
+    results: HashMap<mi::Var, mi::Value>,
+    stopped_threads: Option<&Vec<i32>>,
+) {
     // [RESULT] Done: current-thread-id = 1, threads = [{core = 4, frame = {level = 0, file = ../sysdeps/unix/sysv/linux/write.c, fullname = /build/glibc-OTsEL5/glibc-2.27/nptl/../sysdeps/unix/sysv/linux/write.c, func = __libc_write, addr = 0x00007ffff591e2b7, args = [{value = 11, name = fd}, {value = 0x555555d44860, name = buf}, {value = 4, name = nbytes}], line = 27}, state = stopped, target-id = Thread 0x7ffff7fbdb80 (LWP 19785), id = 1, name = guru}, {id = 2, target-id = Thread 0x7fffed538700 (LWP 19789), frame = {fullname = /build/glibc-OTsEL5/glibc-2.27/io/../sysdeps/unix/sysv/linux/poll.c, addr = 0x00007ffff5418bf9, func = __GI___poll, file = ../sysdeps/unix/sysv/linux/poll.c, args = [{value = 0x55555592e740, name = fds}, {value = 1, name = nfds}, {name = timeout, value = -1}], line = 29, level = 0}, state = stopped, core = 4, name = gmain}, {name = gdbus, state = stopped, target-id = Thread 0x7fffecd37700 (LWP 19790), id = 3, frame = {level = 0, func = __GI___poll, line = 29, args = [{value = 0x555555942bf0, name = fds}, {value = 2, name = nfds}, {value = -1, name = timeout}], addr = 0x00007ffff5418bf9, file = ../sysdeps/unix/sysv/linux/poll.c, fullname = /build/glibc-OTsEL5/glibc-2.27/io/../sysdeps/unix/sysv/linux/poll.c}, core = 1}, {target-id = Thread 0x7fffe778e700 (LWP 19792), core = 7, id = 5, name = pool, frame = {args = [], func = syscall, level = 0, file = ../sysdeps/unix/sysv/linux/x86_64/syscall.S, fullname = /build/glibc-OTsEL5/glibc-2.27/misc/../sysdeps/unix/sysv/linux/x86_64/syscall.S, addr = 0x00007ffff541f839, line = 38}, state = stopped}]
-    if result.class != mi::ResultClass::Done {
-        return;
+    let (threads, current_thread_id) = some!(parsers::parse_thread_info_result(results));
+
+    // Remove exited threads
+    let thread_ids = threads.iter().map(|thread| thread.id).collect::<Vec<_>>();
+    inner.threads_w.borrow_mut().retain_threads(&thread_ids);
+
+    let mut fetch = vec![];
+    let mut threads_w = inner.threads_w.borrow_mut();
+    for thread in &threads {
+        // Threads that didn't stop are not changed, new threads are added
+        let stopped = stopped_threads.map_or(true, |ids| ids.contains(&thread.id));
+        if !stopped && threads_w.has_thread(thread.id) {
+            continue;
+        }
+        let current = current_thread_id == Some(thread.id);
+        if let Some(n_frames) = threads_w.update_thread_info(thread, current) {
+            fetch.push((thread.id, n_frames));
+        }
     }
-    let threads = result
-        .results
-        .remove("threads")
-        .unwrap()
-        .get_value_list()
-        .unwrap();
-    for thread in threads {
-        let mut thread = thread.get_tuple().unwrap();
-        let thread_id =
-            str::parse::<i32>(thread.remove("id").unwrap().get_const_ref().unwrap()).unwrap();
-        let target_id = thread.remove("target-id").unwrap().get_const().unwrap();
-        let token = inner.get_token();
-        let mut gdb_ref = inner.gdb.borrow_mut();
-        let mut gdb = gdb_ref.as_mut().unwrap();
-        writeln!(
-            gdb.stdin(),
-            "{}-stack-list-frames --thread {}",
-            token,
-            thread_id
-        )
-        .unwrap();
-        inner.callbacks.borrow_mut().insert(
-            token,
-            Box::new(move |inner, outer, result| {
-                thread_stack_cb(inner, outer, result, thread_id, &target_id)
-            }),
-        );
+    if let Some(current_thread_id) = current_thread_id {
+        threads_w.set_current_thread(current_thread_id);
+    }
+    drop(threads_w);
+
+    // Only fetch backtraces of expanded threads, other threads are fetched when expanded
+    for (thread_id, n_frames) in fetch {
+        inner.get_thread_stack(thread_id, 0, n_frames);
+    }
+
+    // The parallel stacks view and filtering need backtraces of all threads
+    if inner.threads_w.borrow().needs_full_stacks() {
+        inner.fetch_full_stacks(stopped_thread_ids(&threads));
     }
 }
 
-fn var_update_cb(inner: &AppInner, _outer: &App, mut result: mi::Result) {
-    println!("var_update_cb: {:?}", result);
-    if result.class != mi::ResultClass::Done {
-        return;
+fn stopped_thread_ids(threads: &[Thread]) -> Vec<i32> {
+    threads
+        .iter()
+        .filter(|thread| thread.state == ThreadState::Stopped)
+        .map(|thread| thread.id)
+        .collect()
+}
+
+/// Message of a request error, for showing next to the input of the request.
+fn request_error_msg(err: &RequestError) -> String {
+    match err {
+        RequestError::Gdb(msg) => msg.clone(),
+        RequestError::Timeout => {
+            format!("GDB didn't answer in {} seconds", REQUEST_TIMEOUT.as_secs())
+        }
+        RequestError::Cancelled => "GDB disconnected".to_string(),
+    }
+}
+
+/// Entry of the "add breakpoint" form to show a `-break-insert` error next to.
+fn insert_error_field(spec: &BreakpointSpec, msg: &str) -> BreakpointField {
+    if spec.thread.is_some() && msg.starts_with("Unknown thread") {
+        BreakpointField::Thread
+    } else if spec.dprintf.is_some() && msg.contains("ormat string") {
+        // "Bad format string", "Format string required"
+        BreakpointField::Format
+    } else if spec.dprintf.is_some() && msg.starts_with("No symbol \"") {
+        // Location errors are e.g. "Function "foo" not defined.", arguments are parsed in the
+        // scope of the location
+        BreakpointField::Arguments
+    } else {
+        BreakpointField::Location
     }
-    let changelist = result
-        .results
-        .remove("changelist")
-        .unwrap()
-        .get_value_list()
-        .unwrap();
+}
+
+/// Breakpoint numbers as arguments of `-break-enable` etc., e.g. "1 4 5".
+fn join_numbers(bp_ids: &[u32]) -> String {
+    bp_ids
+        .iter()
+        .map(|bp_id| bp_id.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn var_update_cb(inner: &AppInner, _outer: &App, results: HashMap<mi::Var, mi::Value>) {
+    println!("var_update_cb: {:?}", results);
+    let changelist = some!(parsers::parse_var_update_result(results));
+
+    // Expanded expressions whose children were dropped, children of these should be requested
+    // again.
+    let mut refresh_children = vec![];
+
+    // Invalid top-level expressions, these should be created again
+    let mut recreate = vec![];
 
     let mut expressions_w = inner.expressions_w.borrow_mut();
+    expressions_w.clear_changed();
     for change in changelist {
-        let mut tuple = change.get_tuple().unwrap();
-        let name = tuple.remove("name").unwrap().get_const().unwrap();
-        let value = tuple.remove("value").unwrap().get_const().unwrap();
-        println!("{} -> {}", name, value);
-        expressions_w.update_value(name, value);
+        match change.in_scope {
+            VarScope::InScope => {
+                expressions_w.set_in_scope(&change.name, true);
+            }
+            VarScope::OutOfScope => {
+                // Value is not available for out-of-scope varobjs
+                expressions_w.set_in_scope(&change.name, false);
+                continue;
+            }
+            VarScope::Invalid => {
+                expressions_w.set_in_scope(&change.name, false);
+                // Only `-var-create`d varobjs (not children) can be created again
+                if !change.name.contains('.') {
+                    recreate.push(change.name);
+                }
+                continue;
+            }
+        }
+        let n_children = change.new_num_children.unwrap_or(0);
+        if change.type_changed {
+            if let Some(new_type) = change.new_type {
+                if expressions_w.update_type(&change.name, new_type, n_children, change.has_more) {
+                    refresh_children.push(change.name.clone());
+                }
+            }
+        } else if change.dynamic && change.new_num_children.is_some() {
+            // Children of a pretty-printed value (e.g. elements of a vector) changed
+            if expressions_w.reset_children(&change.name, n_children, change.has_more) {
+                refresh_children.push(change.name.clone());
+            }
+        }
+        if let Some(value) = change.value {
+            println!("{} -> {}", change.name, value);
+            expressions_w.update_value(change.name, value);
+        }
+    }
+    drop(expressions_w);
+
+    for name in refresh_children {
+        inner.get_expr_children(&name, 0, widgets::expressions::CHILDREN_PAGE_SIZE);
+    }
+
+    for name in recreate {
+        inner.recreate_expr(&name);
     }
 }
 
 fn thread_stack_cb(
     inner: &AppInner,
     _outer: &App,
-    mut result: mi::Result,
+    mut results: HashMap<mi::Var, mi::Value>,
+    generation: u64,
     thread_id: i32,
-    target_id: &str,
+    low: usize,
 ) {
     // [RESULT] Done: stack = [frame = {file = ../sysdeps/unix/sysv/linux/x86_64/syscall.S, func = syscall, level = 0, line = 38, addr = 0x00007ffff541f839, fullname = /build/glibc-OTsEL5/glibc-2.27/misc/../sysdeps/unix/sysv/linux/x86_64/syscall.S}, frame = {from = /usr/lib/x86_64-linux-gnu/libglib-2.0.so.0, level = 1, addr = 0x00007ffff5fca29a, func = g_cond_wait_until}, frame = {from = /usr/lib/x86_64-linux-gnu/libglib-2.0.so.0, addr = 0x00007ffff5f574f1, level = 2, func = ??}, frame = {func = g_async_queue_timeout_pop, from = /usr/lib/x86_64-linux-gnu/libglib-2.0.so.0, level = 3, addr = 0x00007ffff5f57aac}, frame = {func = ??, from = /usr/lib/x86_64-linux-gnu/libglib-2.0.so.0, level = 4, addr = 0x00007ffff5facbae}, frame = {from = /usr/lib/x86_64-linux-gnu/libglib-2.0.so.0, func = ??, addr = 0x00007ffff5fac105, level = 5}, frame = {fullname = /build/glibc-OTsEL5/glibc-2.27/nptl/pthread_create.c, level = 6, line = 463, func = start_thread, file = pthread_create.c, addr = 0x00007ffff59146db}, frame = {file = ../sysdeps/unix/sysv/linux/x86_64/clone.S, func = clone, addr = 0x00007ffff542588f, line = 95, fullname = /build/glibc-OTsEL5/glibc-2.27/misc/../sysdeps/unix/sysv/linux/x86_64/clone.S, level = 7}
-    let bt = result
-        .results
-        .remove("stack")
-        .unwrap()
-        .get_result_list()
-        .unwrap();
-    let bt = parsers::parse_backtrace(bt).unwrap();
-    inner
-        .threads_w
-        .borrow_mut()
-        .add_thread(thread_id, target_id, &bt);
+    if generation != inner.thread_generation(thread_id) {
+        // Response to a request from before the thread resumed or stopped again
+        return;
+    }
+    let bt = some!(results.remove("stack"));
+    let bt = some!(bt.get_result_list());
+    let bt = some!(parsers::parse_backtrace(bt));
+    inner.threads_w.borrow().set_frames(thread_id, low, &bt);
     // TODO: Doing this on every update is not a good idea!
     // inner.threads_w.reset_cols();
 }
 
+/// Errors are expected when the thread resumed (non-stop mode) before we got the answer, these are
+/// not shown.
+fn thread_stack_error(
+    inner: &AppInner,
+    command: &str,
+    err: RequestError,
+    generation: u64,
+    thread_id: i32,
+) {
+    if generation == inner.thread_generation(thread_id) {
+        inner.show_request_error(command, err);
+    }
+}
+
+fn stack_depth_cb(
+    inner: &AppInner,
+    _outer: &App,
+    results: HashMap<mi::Var, mi::Value>,
+    generation: u64,
+    thread_id: i32,
+) {
+    // [RESULT] Done: depth = 12
+    if generation != inner.thread_generation(thread_id) {
+        return;
+    }
+    let depth = some!(results
+        .get("depth")
+        .and_then(|depth| depth.get_const_ref())
+        .and_then(|depth| depth.parse::<usize>().ok()));
+    inner.threads_w.borrow().set_depth(thread_id, depth);
+}
+
 fn render_async_record(async_: &mi::AsyncRecord) -> String {
     let mut ret = String::new();
     ret.push_str(&format!("<b>{}</b> ", async_.class));
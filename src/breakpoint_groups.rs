@@ -0,0 +1,178 @@
+//! Named groups of breakpoints, for enabling and disabling related breakpoints together.
+//!
+//! Groups are saved per program (executable) in the user's config directory. Breakpoint numbers
+//! change between sessions, so breakpoints are identified by keys made of their locations (as
+//! given when creating them) and conditions, see `breakpoint_key`. A breakpoint is in at most one
+//! group. File format:
+//!
+//! ```text
+//! [parser bug]
+//! parser.c:120
+//! lexer.c:45 if c == '"'
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BreakpointGroups {
+    /// In order of creation
+    groups: Vec<Group>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Group {
+    name: String,
+    keys: Vec<String>,
+}
+
+/// Key of a breakpoint in groups, e.g. `lexer.c:45 if c == '"'`. Breakpoints at the same location
+/// with different conditions are in different groups.
+pub fn breakpoint_key(location: &str, condition: Option<&str>) -> String {
+    match condition {
+        None | Some("") => location.to_string(),
+        Some(condition) => format!("{} if {}", location, condition),
+    }
+}
+
+impl BreakpointGroups {
+    pub fn new() -> BreakpointGroups {
+        BreakpointGroups { groups: vec![] }
+    }
+
+    pub fn parse(s: &str) -> BreakpointGroups {
+        let mut groups: Vec<Group> = vec![];
+        for line in s.lines().map(str::trim) {
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                groups.push(Group {
+                    name: line[1..line.len() - 1].to_string(),
+                    keys: vec![],
+                });
+            } else if let Some(group) = groups.last_mut() {
+                group.keys.push(line.to_string());
+            }
+        }
+        BreakpointGroups { groups }
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut s = String::new();
+        for group in &self.groups {
+            s.push_str(&format!("[{}]\n", group.name));
+            for key in &group.keys {
+                s.push_str(key);
+                s.push('\n');
+            }
+        }
+        s
+    }
+
+    /// Load groups of a program. Returns no groups if there's no file for the program.
+    pub fn load(executable: &str) -> BreakpointGroups {
+        match groups_file(executable).map(fs::read_to_string) {
+            Some(Ok(s)) => BreakpointGroups::parse(&s),
+            _ => BreakpointGroups::new(),
+        }
+    }
+
+    pub fn save(&self, executable: &str) -> io::Result<()> {
+        let path = groups_file(executable).ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, "Can't find config directory")
+        })?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, self.serialize())
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.groups
+            .iter()
+            .map(|group| group.name.as_str())
+            .collect()
+    }
+
+    /// Group of the breakpoint with the given key.
+    pub fn group_of(&self, key: &str) -> Option<&str> {
+        self.groups
+            .iter()
+            .find(|group| group.keys.iter().any(|k| k == key))
+            .map(|group| group.name.as_str())
+    }
+
+    /// Keys of breakpoints in a group.
+    pub fn keys(&self, name: &str) -> &[String] {
+        match self.groups.iter().find(|group| group.name == name) {
+            None => &[],
+            Some(group) => &group.keys,
+        }
+    }
+
+    /// Move the breakpoint with the given key to a group, creating the group if it doesn't exist.
+    pub fn add(&mut self, name: &str, key: &str) {
+        self.remove(key);
+        match self.groups.iter_mut().find(|group| group.name == name) {
+            Some(group) => {
+                group.keys.push(key.to_string());
+            }
+            None => {
+                self.groups.push(Group {
+                    name: name.to_string(),
+                    keys: vec![key.to_string()],
+                });
+            }
+        }
+    }
+
+    /// Remove the breakpoint with the given key from its group. Groups without breakpoints are
+    /// removed.
+    pub fn remove(&mut self, key: &str) {
+        for group in &mut self.groups {
+            group.keys.retain(|k| k != key);
+        }
+        self.groups.retain(|group| !group.keys.is_empty());
+    }
+}
+
+/// e.g. `~/.config/guru/breakpoint-groups/%home%user%test`
+fn groups_file(executable: &str) -> Option<PathBuf> {
+    let mut path = glib::get_user_config_dir()?;
+    path.push("guru");
+    path.push("breakpoint-groups");
+    path.push(executable.replace('/', "%"));
+    Some(path)
+}
+
+#[test]
+fn breakpoint_groups_tests() {
+    let mut groups = BreakpointGroups::new();
+    groups.add("parser bug", "parser.c:120");
+    groups.add("parser bug", "lexer.c:45");
+    groups.add("gc", "gc.c:10");
+    assert_eq!(groups.names(), vec!["parser bug", "gc"]);
+    assert_eq!(groups.group_of("lexer.c:45"), Some("parser bug"));
+    assert_eq!(groups.group_of("main"), None);
+
+    // Moving the last breakpoint of a group removes the group
+    groups.add("parser bug", "gc.c:10");
+    assert_eq!(groups.names(), vec!["parser bug"]);
+    assert_eq!(
+        groups.keys("parser bug"),
+        &["parser.c:120", "lexer.c:45", "gc.c:10"]
+    );
+
+    // A conditional breakpoint at the same location is not in the group
+    let key = breakpoint_key("lexer.c:45", Some("c == '\"'"));
+    assert_eq!(key, "lexer.c:45 if c == '\"'");
+    assert_eq!(groups.group_of(&key), None);
+    assert_eq!(breakpoint_key("lexer.c:45", Some("")), "lexer.c:45");
+
+    groups.remove("parser.c:120");
+    let s = groups.serialize();
+    assert_eq!(s, "[parser bug]\nlexer.c:45\ngc.c:10\n");
+    assert_eq!(BreakpointGroups::parse(&s), groups);
+}
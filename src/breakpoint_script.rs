@@ -0,0 +1,501 @@
+//! Breakpoints as GDB scripts, in the format of `save breakpoints`:
+//!
+//! ```text
+//! tbreak test.c:3 thread 2
+//!   condition $bpnum x > 1
+//!   ignore $bpnum 3
+//!   commands
+//!     print x
+//!     continue
+//!   end
+//! disable $bpnum
+//! ```
+//!
+//! Exported scripts can be loaded with `source`. Imported scripts are parsed to breakpoints that
+//! are created with MI commands.
+
+use crate::types::{
+    Breakpoint, BreakpointDisposition, BreakpointSpec, BreakpointType, CatchpointType,
+};
+
+/// A breakpoint read from a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedBreakpoint {
+    pub create: Create,
+    pub condition: Option<String>,
+    pub ignore_count: u32,
+    pub commands: Vec<String>,
+    pub enabled: bool,
+}
+
+/// How to create a saved breakpoint.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Create {
+    /// `break`, `tbreak`, `hbreak`, `thbreak` and `dprintf`
+    Breakpoint(BreakpointSpec),
+    /// `catch`, with the catchpoint argument
+    Catchpoint(CatchpointType, String),
+    /// Other commands that create breakpoints (e.g. `watch`, or catchpoints we don't know about),
+    /// run as they are.
+    Other(String),
+}
+
+/// Write breakpoints as a script. Breakpoints that can't be recreated with a command (e.g.
+/// catchpoints created with commands we don't know about) are skipped.
+pub fn write_script(bps: &[Breakpoint]) -> String {
+    let mut script = String::new();
+    for bp in bps {
+        let create = match create_command(bp) {
+            None => {
+                continue;
+            }
+            Some(create) => create,
+        };
+        script.push_str(&create);
+        script.push('\n');
+        if let Some(ref cond) = bp.cond {
+            script.push_str(&format!("  condition $bpnum {}\n", cond));
+        }
+        if bp.ignore_count != 0 {
+            script.push_str(&format!("  ignore $bpnum {}\n", bp.ignore_count));
+        }
+        // Script of a dprintf is the printf, created by the `dprintf` command
+        if !bp.script.is_empty() && bp.type_ != BreakpointType::Dprintf {
+            script.push_str("  commands\n");
+            for command in &bp.script {
+                script.push_str(&format!("    {}\n", command));
+            }
+            script.push_str("  end\n");
+        }
+        if !bp.enabled {
+            script.push_str("disable $bpnum\n");
+        }
+    }
+    script
+}
+
+/// Command that creates the breakpoint, e.g. `tbreak test.c:3 thread 2`.
+fn create_command(bp: &Breakpoint) -> Option<String> {
+    let thread = match bp.thread {
+        None => "".to_string(),
+        Some(thread) => format!(" thread {}", thread),
+    };
+    let temporary = bp.disposition == BreakpointDisposition::Delete;
+    match bp.type_ {
+        BreakpointType::Breakpoint | BreakpointType::HardwareBreakpoint => {
+            let hardware = bp.type_ == BreakpointType::HardwareBreakpoint;
+            let command = match (temporary, hardware) {
+                (false, false) => "break",
+                (true, false) => "tbreak",
+                (false, true) => "hbreak",
+                (true, true) => "thbreak",
+            };
+            Some(format!("{} {}{}", command, bp.original_location, thread))
+        }
+        BreakpointType::Dprintf => Some(format!(
+            "dprintf {},{}",
+            bp.original_location,
+            bp.dprintf_format()?
+        )),
+        BreakpointType::Watchpoint => None,
+        BreakpointType::Catchpoint(type_) => {
+            // We only have the description of what's caught, e.g. `syscalls "write, read"`, so
+            // exception regexes are lost
+            let what = &bp.original_location;
+            let quoted = || {
+                let start = what.find('"')?;
+                let end = what.rfind('"')?;
+                if end > start {
+                    Some(what[start + 1..end].replace(",", " "))
+                } else {
+                    None
+                }
+            };
+            Some(match type_ {
+                CatchpointType::Throw => "catch throw".to_string(),
+                CatchpointType::Catch => "catch catch".to_string(),
+                CatchpointType::Rethrow => "catch rethrow".to_string(),
+                CatchpointType::Syscall => match quoted() {
+                    Some(ref names) if !names.starts_with('<') => {
+                        format!("catch syscall {}", names)
+                    }
+                    // `syscall "<any syscall>"`
+                    _ => "catch syscall".to_string(),
+                },
+                CatchpointType::Fork => "catch fork".to_string(),
+                CatchpointType::Vfork => "catch vfork".to_string(),
+                CatchpointType::Exec => "catch exec".to_string(),
+                CatchpointType::Load | CatchpointType::Unload => {
+                    let command = if type_ == CatchpointType::Load {
+                        "catch load"
+                    } else {
+                        "catch unload"
+                    };
+                    // "load of library matching libfoo\.so$"
+                    match what.find("matching ") {
+                        None => command.to_string(),
+                        Some(idx) => format!("{} {}", command, &what[idx + "matching ".len()..]),
+                    }
+                }
+                CatchpointType::Signal => {
+                    // "SIGUSR1 SIGUSR2" or "<standard signals>"
+                    if what.starts_with('<') {
+                        "catch signal".to_string()
+                    } else {
+                        format!("catch signal {}", what)
+                    }
+                }
+                CatchpointType::Other => {
+                    return None;
+                }
+            })
+        }
+    }
+}
+
+/// Parse a script. Returns the breakpoints and the lines that are skipped, e.g. `set` commands,
+/// or `condition` for a breakpoint other than `$bpnum`.
+pub fn parse_script(script: &str) -> (Vec<SavedBreakpoint>, Vec<String>) {
+    let mut bps: Vec<SavedBreakpoint> = vec![];
+    let mut skipped: Vec<String> = vec![];
+    let mut lines = script.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (command, args) = split_command(line);
+        match command {
+            "condition" | "ignore" | "commands" | "disable" | "enable" => {
+                // Only `$bpnum` (the last breakpoint) is supported. `commands`, `disable` and
+                // `enable` without arguments are also for the last breakpoint.
+                let (bp_num, args) = split_command(args);
+                let last = bp_num == "$bpnum" || (bp_num.is_empty() && command != "condition");
+                // Consume the block even when it's not for the last breakpoint
+                let commands = if command == "commands" {
+                    parse_commands(&mut lines)
+                } else {
+                    vec![]
+                };
+                let bp = match bps.last_mut() {
+                    Some(bp) if last => bp,
+                    _ => {
+                        skipped.push(line.to_string());
+                        continue;
+                    }
+                };
+                match command {
+                    "condition" => {
+                        bp.condition = if args.is_empty() {
+                            None
+                        } else {
+                            Some(args.to_string())
+                        };
+                    }
+                    "ignore" => {
+                        if let Ok(ignore_count) = args.parse::<u32>() {
+                            bp.ignore_count = ignore_count;
+                        }
+                    }
+                    "commands" => {
+                        bp.commands = commands;
+                    }
+                    "disable" => {
+                        bp.enabled = false;
+                    }
+                    _ => {
+                        bp.enabled = true;
+                    }
+                }
+            }
+            _ => match parse_create(command, args) {
+                None => {
+                    skipped.push(line.to_string());
+                }
+                Some(create) => {
+                    bps.push(SavedBreakpoint {
+                        create,
+                        condition: None,
+                        ignore_count: 0,
+                        commands: vec![],
+                        enabled: true,
+                    });
+                }
+            },
+        }
+    }
+    (bps, skipped)
+}
+
+/// Read lines of a `commands` block until its `end`.
+fn parse_commands<'a, I: Iterator<Item = &'a str>>(lines: &mut I) -> Vec<String> {
+    let mut commands = vec![];
+    // Nested blocks (`while`, `if` etc.) also end with `end`
+    let mut depth = 0;
+    for line in lines {
+        if line.is_empty() {
+            continue;
+        }
+        let (command, args) = split_command(line);
+        match command {
+            "end" => {
+                if depth == 0 {
+                    break;
+                }
+                depth -= 1;
+            }
+            "while" | "if" | "while-stepping" | "stepping" | "ws" | "commands" => {
+                depth += 1;
+            }
+            "python" | "py" | "guile" | "gu" | "compile" | "expression" if args.is_empty() => {
+                depth += 1;
+            }
+            _ => {}
+        }
+        commands.push(line.to_string());
+    }
+    commands
+}
+
+/// Parse a command that creates a breakpoint. Returns `None` for other commands. Breakpoint
+/// commands that can't be parsed (e.g. `thread 1.2` with an inferior-qualified thread id) are run
+/// as they are.
+fn parse_create(command: &str, args: &str) -> Option<Create> {
+    // Breakpoint commands, with their abbreviations
+    let (temporary, hardware) = match command {
+        "break" | "brea" | "bre" | "br" | "b" => (false, false),
+        "tbreak" | "tbrea" | "tbre" | "tbr" | "tb" => (true, false),
+        "hbreak" | "hbrea" | "hbre" | "hbr" | "hb" => (false, true),
+        "thbreak" | "thbrea" | "thbre" | "thbr" | "thb" => (true, true),
+        "dprintf" => (false, false),
+        "catch" => {
+            let (what, arg) = split_command(args);
+            let type_ = match what {
+                "throw" => CatchpointType::Throw,
+                "catch" => CatchpointType::Catch,
+                "rethrow" => CatchpointType::Rethrow,
+                "syscall" => CatchpointType::Syscall,
+                "fork" => CatchpointType::Fork,
+                "vfork" => CatchpointType::Vfork,
+                "exec" => CatchpointType::Exec,
+                "load" => CatchpointType::Load,
+                "unload" => CatchpointType::Unload,
+                "signal" => CatchpointType::Signal,
+                _ => {
+                    return Some(Create::Other(format!("catch {}", args)));
+                }
+            };
+            return Some(Create::Catchpoint(type_, arg.to_string()));
+        }
+        "tcatch" | "watch" | "rwatch" | "awatch" => {
+            return Some(Create::Other(format!("{} {}", command, args)));
+        }
+        _ => {
+            return None;
+        }
+    };
+    Some(
+        parse_breakpoint(command == "dprintf", temporary, hardware, args)
+            .unwrap_or_else(|| Create::Other(format!("{} {}", command, args))),
+    )
+}
+
+/// Parse arguments of a breakpoint command: `LOCATION [thread N] [if COND]`, for dprintf:
+/// `LOCATION [thread N] [if COND],"FORMAT",ARGS`.
+fn parse_breakpoint(dprintf: bool, temporary: bool, hardware: bool, args: &str) -> Option<Create> {
+    // The format string may contain " if " etc., so it's split first
+    let (args, dprintf) = if dprintf {
+        let (location, format, args) = split_dprintf_args(args)?;
+        (location, Some((format.to_string(), args.to_string())))
+    } else {
+        (args, None)
+    };
+    let (args, condition) = match args.find(" if ") {
+        None => (args, ""),
+        Some(idx) => (&args[..idx], args[idx + " if ".len()..].trim()),
+    };
+    let (location, thread) = match args.rfind(" thread ") {
+        None => (args, None),
+        Some(idx) => (
+            &args[..idx],
+            Some(args[idx + " thread ".len()..].trim().parse::<i32>().ok()?),
+        ),
+    };
+
+    Some(Create::Breakpoint(BreakpointSpec {
+        location: location.trim().to_string(),
+        condition: condition.to_string(),
+        dprintf,
+        temporary,
+        hardware,
+        // Libraries may not be loaded when importing
+        pending: true,
+        disabled: false,
+        thread,
+        ignore_count: None,
+    }))
+}
+
+/// Split `LOCATION,"FORMAT",ARGS` to location, format (contents of the string literal) and args.
+fn split_dprintf_args(s: &str) -> Option<(&str, &str, &str)> {
+    // Location can have commas (e.g. `foo(int, char)`), but not followed by a quote
+    let format_start = s.find(",\"")?;
+    let location = s[..format_start].trim();
+    let format = &s[format_start + 2..];
+    let mut escaped = false;
+    for (idx, c) in format.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            let args = format[idx + 1..].trim();
+            let args = if args.starts_with(',') {
+                args[1..].trim()
+            } else {
+                args
+            };
+            return Some((location, &format[..idx], args));
+        }
+    }
+    None
+}
+
+/// Split a line to the command and its arguments.
+fn split_command(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.find(char::is_whitespace) {
+        None => (line, ""),
+        Some(idx) => (&line[..idx], line[idx..].trim()),
+    }
+}
+
+#[test]
+fn parse_script_tests() {
+    let script = "\
+# Saved breakpoints
+break main
+  condition $bpnum argc > 1
+tbreak test.c:3 thread 2
+  ignore $bpnum 3
+  commands
+    silent
+    if x > 1
+      print x
+    end
+    continue
+  end
+disable $bpnum
+dprintf foo(int, char),\"x = %d, \\\"%s\\\"\\n\",x,s
+catch syscall write read
+watch x
+set pagination off
+break test.c:5 thread 1.2
+dprintf test.c:7,\"stop if x thread %d\\n\",n
+";
+    let (bps, skipped) = parse_script(script);
+    assert_eq!(bps.len(), 7);
+    assert_eq!(skipped, vec!["set pagination off"]);
+
+    match bps[0].create {
+        Create::Breakpoint(ref spec) => {
+            assert_eq!(spec.location, "main");
+            assert!(!spec.temporary);
+        }
+        ref other => panic!("{:?}", other),
+    }
+    assert_eq!(bps[0].condition, Some("argc > 1".to_string()));
+    assert!(bps[0].enabled);
+
+    match bps[1].create {
+        Create::Breakpoint(ref spec) => {
+            assert_eq!(spec.location, "test.c:3");
+            assert_eq!(spec.thread, Some(2));
+            assert!(spec.temporary);
+        }
+        ref other => panic!("{:?}", other),
+    }
+    assert_eq!(bps[1].ignore_count, 3);
+    assert_eq!(
+        bps[1].commands,
+        vec!["silent", "if x > 1", "print x", "end", "continue"]
+    );
+    assert!(!bps[1].enabled);
+
+    match bps[2].create {
+        Create::Breakpoint(ref spec) => {
+            assert_eq!(spec.location, "foo(int, char)");
+            assert_eq!(
+                spec.dprintf,
+                Some(("x = %d, \\\"%s\\\"\\n".to_string(), "x,s".to_string()))
+            );
+        }
+        ref other => panic!("{:?}", other),
+    }
+
+    assert_eq!(
+        bps[3].create,
+        Create::Catchpoint(CatchpointType::Syscall, "write read".to_string())
+    );
+    assert_eq!(bps[4].create, Create::Other("watch x".to_string()));
+
+    // Inferior-qualified thread ids are left to GDB
+    assert_eq!(
+        bps[5].create,
+        Create::Other("break test.c:5 thread 1.2".to_string())
+    );
+
+    match bps[6].create {
+        Create::Breakpoint(ref spec) => {
+            assert_eq!(spec.location, "test.c:7");
+            assert_eq!(spec.condition, "");
+            assert_eq!(spec.thread, None);
+            assert_eq!(
+                spec.dprintf,
+                Some(("stop if x thread %d\\n".to_string(), "n".to_string()))
+            );
+        }
+        ref other => panic!("{:?}", other),
+    }
+}
+
+#[test]
+fn write_script_tests() {
+    let bp = Breakpoint {
+        number: 1,
+        type_: BreakpointType::Breakpoint,
+        disposition: BreakpointDisposition::Delete,
+        enabled: false,
+        address: "0x0000000000001131".to_string(),
+        pending: false,
+        original_location: "test.c:3".to_string(),
+        file: Some("test.c".to_string()),
+        fullname: Some("/tmp/test.c".to_string()),
+        line: Some(3),
+        cond: Some("x > 1".to_string()),
+        thread: Some(2),
+        ignore_count: 3,
+        hits: 0,
+        script: vec!["print x".to_string(), "continue".to_string()],
+    };
+    let script = write_script(&[bp]);
+    assert_eq!(
+        script,
+        "tbreak test.c:3 thread 2
+  condition $bpnum x > 1
+  ignore $bpnum 3
+  commands
+    print x
+    continue
+  end
+disable $bpnum
+"
+    );
+
+    let (bps, skipped) = parse_script(&script);
+    assert_eq!(bps.len(), 1);
+    assert!(skipped.is_empty());
+    assert_eq!(bps[0].condition, Some("x > 1".to_string()));
+    assert_eq!(bps[0].ignore_count, 3);
+    assert_eq!(bps[0].commands, vec!["print x", "continue"]);
+    assert!(!bps[0].enabled);
+}
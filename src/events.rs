@@ -0,0 +1,61 @@
+//! Subscriptions to async events from GDB.
+//!
+//! Async records (`*stopped`, `=thread-created` etc.) are parsed to `types::Event`s and passed to
+//! all subscribers, in order of subscription. Subscribers match on the events they're interested
+//! in and ignore the rest.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub struct Events<S: ?Sized> {
+    subscribers: RefCell<Vec<Rc<S>>>,
+}
+
+impl<S: ?Sized> Events<S> {
+    pub fn new() -> Events<S> {
+        Events {
+            subscribers: RefCell::new(vec![]),
+        }
+    }
+
+    pub fn subscribe(&self, subscriber: Box<S>) {
+        self.subscribers.borrow_mut().push(Rc::from(subscriber));
+    }
+
+    /// Call `f` with every subscriber. Subscribers added while emitting are called starting with
+    /// the next event.
+    pub fn emit<F: Fn(&S)>(&self, f: F) {
+        let subscribers = self
+            .subscribers
+            .borrow()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        for subscriber in subscribers {
+            f(&subscriber);
+        }
+    }
+}
+
+#[test]
+fn events_tests() {
+    let events: Rc<Events<Fn(&RefCell<Vec<u32>>, u32)>> = Rc::new(Events::new());
+    let log = RefCell::new(vec![]);
+
+    events.subscribe(Box::new(|log, event| log.borrow_mut().push(event)));
+    {
+        // Subscribes another subscriber on first event
+        let events_ = events.clone();
+        events.subscribe(Box::new(move |log, event| {
+            log.borrow_mut().push(event * 10);
+            if event == 1 {
+                events_.subscribe(Box::new(|log, event| log.borrow_mut().push(event * 100)));
+            }
+        }));
+    }
+    events.emit(|subscriber| subscriber(&log, 1));
+    assert_eq!(*log.borrow(), vec![1, 10]);
+
+    events.emit(|subscriber| subscriber(&log, 2));
+    assert_eq!(*log.borrow(), vec![1, 10, 2, 20, 200]);
+}
@@ -10,6 +10,14 @@ use glib::Sender;
 
 use crate::mi;
 
+/// Messages sent by the thread that reads gdb stdout.
+pub enum Msg {
+    /// Parsed mi messages.
+    Mi(mi::Output),
+    /// gdb stdout is closed, i.e. gdb exited.
+    Exited,
+}
+
 pub struct GDB {
     process: Child,
     _message_handler: thread::JoinHandle<()>,
@@ -21,8 +29,8 @@ impl GDB {
     /// $ gdb --args <args>
     /// ```
     /// A spawns that reads gdb stdout and sends parsed mi messages to `msg_sender` will be
-    /// spawned.
-    pub fn with_args(args0: &[String], mut msg_sender: Sender<mi::Output>) -> GDB {
+    /// spawned. `Msg::Exited` is sent when gdb exits.
+    pub fn with_args(args0: &[String], mut msg_sender: Sender<Msg>) -> GDB {
         let mut args = vec!["-n".to_string(), "-i=mi".to_string(), "--args".to_string()];
         args.extend_from_slice(args0);
         let mut process = Command::new("gdb")
@@ -54,7 +62,7 @@ impl GDB {
 static MI_MSG_SEP: &'static str = "(gdb) \n";
 
 /// Read "(gdb)\n" delimited mi messages from `stdout`, send parsed messages to `msg_handler`.
-fn message_handler(stdout: &mut ChildStdout, msg_sender: &mut Sender<mi::Output>) {
+fn message_handler(stdout: &mut ChildStdout, msg_sender: &mut Sender<Msg>) {
     // We can't do incremental parsing yet so collect output until we see a "(gdb) \n".
     // We also can't search in a [u8] (nothing like str::find for other slices) so we try to first
     // convert the accumulated output to str.
@@ -83,7 +91,10 @@ fn message_handler(stdout: &mut ChildStdout, msg_sender: &mut Sender<mi::Output>
                     }
                     Some(mi_msgs) => {
                         // println!("mi message parsed: {:?}", mi_msgs);
-                        msg_sender.send(mi_msgs).unwrap();
+                        if msg_sender.send(Msg::Mi(mi_msgs)).is_err() {
+                            // Receiver is gone, nothing to do
+                            return;
+                        }
                         msg_bytes.drain(0..idx + MI_MSG_SEP.len());
                     }
                 }
@@ -91,10 +102,16 @@ fn message_handler(stdout: &mut ChildStdout, msg_sender: &mut Sender<mi::Output>
             None => {
                 // Read more from the socket
                 let mut read_buf: [u8; 10000] = [0; 10000];
-                let len = stdout.read(&mut read_buf).unwrap();
+                let len = match stdout.read(&mut read_buf) {
+                    Ok(len) => len,
+                    Err(err) => {
+                        println!("Can't read gdb stdout: {}", err);
+                        0
+                    }
+                };
                 // println!("Message handler read {} bytes", len);
                 if len == 0 {
-                    // TODO: Somehow signal socket closure
+                    let _ = msg_sender.send(Msg::Exited);
                     return;
                 }
                 msg_bytes.extend_from_slice(&read_buf[0..len]);
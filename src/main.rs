@@ -5,9 +5,14 @@ extern crate glib;
 extern crate gtk;
 
 mod app;
+mod breakpoint_groups;
+mod breakpoint_script;
+mod events;
 mod gdb;
 mod mi;
 mod parsers;
+mod requests;
+mod stack_groups;
 mod types;
 mod widgets;
 
@@ -26,11 +31,16 @@ fn main() {
 fn build_ui(gtk_app: &gtk::Application) {
     let app = app::App::new(gtk_app);
 
-    // Currently all args are considered gdb args and passed to gdb as --args, e.g.
+    // All args other than a leading `--non-stop` are considered gdb args and passed to gdb as
+    // --args, e.g.
     // $ gdb --args <program args>
-    let gdb_args = std::env::args().skip(1).collect::<Vec<String>>();
+    let mut gdb_args = std::env::args().skip(1).collect::<Vec<String>>();
+    let non_stop = gdb_args.first().map_or(false, |arg| arg == "--non-stop");
+    if non_stop {
+        gdb_args.remove(0);
+    }
     println!("args: {:?}", gdb_args);
 
     // Connect to gdb with no args, for testing
-    app.gdb_connect(&gdb_args);
+    app.gdb_connect(&gdb_args, non_stop);
 }
@@ -1 +1,256 @@
+//! Helpers for building MI commands.
 
+use crate::types::{BreakpointSpec, CatchpointType, SymbolKind};
+
+/// A command to send to GDB. Some things can only be done with CLI commands, those are run with
+/// `-interpreter-exec console`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+    Mi(String),
+    Console(String),
+}
+
+/// Quote a string as a c-string in MI input syntax, e.g. for arguments with spaces or for CLI
+/// commands in `-interpreter-exec console "..."`.
+pub fn quote(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len() + 2);
+    ret.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\t' => ret.push_str("\\t"),
+            _ => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Quote a dprintf format string given as it'd be written in C (without the quotes). Escape
+/// sequences (e.g. `\n`, `\"`) are kept as they are, MI interprets them the same way C does.
+/// Quotes and newlines that are not escaped are escaped, so that they don't end the argument.
+fn quote_format(format: &str) -> String {
+    let mut ret = String::with_capacity(format.len() + 2);
+    ret.push('"');
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                None => ret.push_str("\\\\"),
+                Some(next) => {
+                    ret.push('\\');
+                    ret.push(next);
+                }
+            },
+            '"' => ret.push_str("\\\""),
+            '\n' => ret.push_str("\\n"),
+            _ => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Escape a string to be matched literally by commands that take (basic) regular expressions,
+/// like `sharedlibrary` and `catch load`. Note that in basic regexps `+` and `?` are not special,
+/// escaping them would make them special in GNU regex.
+pub fn escape_regex(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        if let '.' | '*' | '[' | ']' | '^' | '$' | '\\' = c {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
+
+/// `-break-insert` or `-dprintf-insert` command for a breakpoint.
+pub fn break_insert(spec: &BreakpointSpec) -> String {
+    let mut command = match spec.dprintf {
+        None => "-break-insert".to_string(),
+        Some(_) => "-dprintf-insert".to_string(),
+    };
+    if spec.temporary {
+        command.push_str(" -t");
+    }
+    // Not supported by `-dprintf-insert`
+    if spec.hardware && spec.dprintf.is_none() {
+        command.push_str(" -h");
+    }
+    if spec.pending {
+        command.push_str(" -f");
+    }
+    if spec.disabled {
+        command.push_str(" -d");
+    }
+    if let Some(thread) = spec.thread {
+        command.push_str(&format!(" -p {}", thread));
+    }
+    if let Some(ignore_count) = spec.ignore_count {
+        command.push_str(&format!(" -i {}", ignore_count));
+    }
+    if !spec.condition.is_empty() {
+        command.push_str(&format!(" -c {}", quote(&spec.condition)));
+    }
+    // Quoted as one argument, e.g. for source paths with spaces
+    command.push(' ');
+    command.push_str(&quote(&spec.location));
+    if let Some((ref format, ref args)) = spec.dprintf {
+        // Arguments are joined with commas by GDB, so all can be passed as one.
+        command.push(' ');
+        command.push_str(&quote_format(format));
+        if !args.is_empty() {
+            command.push(' ');
+            command.push_str(&quote(args));
+        }
+    }
+    command
+}
+
+/// `-break-commands` command to replace commands of a breakpoint. Empty `commands` removes the
+/// commands.
+pub fn break_commands(bp_id: u32, commands: &[String]) -> String {
+    let mut command = format!("-break-commands {}", bp_id);
+    for bp_command in commands {
+        command.push(' ');
+        command.push_str(&quote(bp_command));
+    }
+    command
+}
+
+/// Command to create a catchpoint. `arg` is the exception regex for C++ exception catchpoints,
+/// syscall names for syscall catchpoints, the library regex for load catchpoints and signal names
+/// for signal catchpoints. Empty `arg` catches all exceptions/syscalls/libraries/signals.
+///
+/// Only C++ exception and library catchpoints have MI commands, others are created with CLI
+/// commands. GDB sends `breakpoint-created` records for those.
+pub fn catchpoint(type_: CatchpointType, arg: &str) -> Option<Command> {
+    let exception_command = |command: &str| {
+        if arg.is_empty() {
+            command.to_string()
+        } else {
+            format!("{} -r {}", command, quote(arg))
+        }
+    };
+    let cli_command = |command: &str| {
+        if arg.is_empty() {
+            command.to_string()
+        } else {
+            format!("{} {}", command, arg)
+        }
+    };
+    // Unlike `catch load` the MI commands need a regex
+    let regex = if arg.is_empty() { "." } else { arg };
+    Some(match type_ {
+        CatchpointType::Throw => Command::Mi(exception_command("-catch-throw")),
+        CatchpointType::Catch => Command::Mi(exception_command("-catch-catch")),
+        CatchpointType::Rethrow => Command::Mi(exception_command("-catch-rethrow")),
+        CatchpointType::Load => Command::Mi(format!("-catch-load {}", quote(regex))),
+        CatchpointType::Unload => Command::Mi(format!("-catch-unload {}", quote(regex))),
+        CatchpointType::Syscall => Command::Console(cli_command("catch syscall")),
+        CatchpointType::Fork => Command::Console("catch fork".to_string()),
+        CatchpointType::Vfork => Command::Console("catch vfork".to_string()),
+        CatchpointType::Exec => Command::Console("catch exec".to_string()),
+        CatchpointType::Signal => Command::Console(cli_command("catch signal")),
+        CatchpointType::Other => {
+            return None;
+        }
+    })
+}
+
+/// `-symbol-info-functions`, `-symbol-info-variables` or `-symbol-info-types` command. Empty
+/// `regex` matches all symbols. `nondebug` includes symbols without debug info (not supported for
+/// types). GDB stops searching after `max_results` symbols.
+pub fn symbol_info(kind: SymbolKind, regex: &str, nondebug: bool, max_results: usize) -> String {
+    let mut command = match kind {
+        SymbolKind::Function => "-symbol-info-functions",
+        SymbolKind::Variable => "-symbol-info-variables",
+        SymbolKind::Type => "-symbol-info-types",
+    }
+    .to_string();
+    if nondebug && kind != SymbolKind::Type {
+        command.push_str(" --include-nondebug");
+    }
+    if !regex.is_empty() {
+        command.push_str(&format!(" --name {}", quote(regex)));
+    }
+    command.push_str(&format!(" --max-results {}", max_results));
+    command
+}
+
+#[test]
+fn quote_tests() {
+    assert_eq!(quote("foo"), "\"foo\"");
+    assert_eq!(quote("a \"b\" c"), "\"a \\\"b\\\" c\"");
+    assert_eq!(quote("C:\\x\n"), "\"C:\\\\x\\n\"");
+}
+
+#[test]
+fn escape_regex_tests() {
+    assert_eq!(
+        escape_regex("/usr/lib/libstdc++.so.6"),
+        "/usr/lib/libstdc++\\.so\\.6"
+    );
+    assert_eq!(escape_regex("a[1]*$"), "a\\[1\\]\\*\\$");
+}
+
+#[test]
+fn break_insert_tests() {
+    let mut spec = BreakpointSpec {
+        location: "test.c:3".to_string(),
+        condition: "x > 1".to_string(),
+        dprintf: None,
+        temporary: true,
+        hardware: true,
+        pending: false,
+        disabled: false,
+        thread: Some(2),
+        ignore_count: None,
+    };
+    assert_eq!(
+        break_insert(&spec),
+        "-break-insert -t -h -p 2 -c \"x > 1\" \"test.c:3\""
+    );
+
+    spec.condition = "".to_string();
+    spec.dprintf = Some(("x = %d\\n".to_string(), "x".to_string()));
+    spec.temporary = false;
+    spec.thread = None;
+    assert_eq!(
+        break_insert(&spec),
+        "-dprintf-insert \"test.c:3\" \"x = %d\\n\" \"x\""
+    );
+
+    // Quotes in the format don't end the argument, escape sequences are kept
+    spec.dprintf = Some((
+        "name=\"%s\" \\\"%d\\\"\\".to_string(),
+        "name, n".to_string(),
+    ));
+    assert_eq!(
+        break_insert(&spec),
+        "-dprintf-insert \"test.c:3\" \"name=\\\"%s\\\" \\\"%d\\\"\\\\\" \"name, n\""
+    );
+
+    spec.dprintf = None;
+    spec.hardware = false;
+    spec.location = "/home/user/my project/test.c:3".to_string();
+    assert_eq!(
+        break_insert(&spec),
+        "-break-insert \"/home/user/my project/test.c:3\""
+    );
+}
+
+#[test]
+fn symbol_info_tests() {
+    assert_eq!(
+        symbol_info(SymbolKind::Function, "^parse_", true, 100),
+        "-symbol-info-functions --include-nondebug --name \"^parse_\" --max-results 100"
+    );
+    assert_eq!(
+        symbol_info(SymbolKind::Type, "", true, 100),
+        "-symbol-info-types --max-results 100"
+    );
+}
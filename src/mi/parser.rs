@@ -297,6 +297,8 @@ fn parse_variable(s: &str) -> Option<(Var, &str)> {
 // value → const | tuple | list
 // const → c-string
 // tuple → "{}" | "{" result ( "," result )* "}"
+//         (also "{" value ( "," value )* "}", which GDB uses for breakpoint scripts before MI 4.
+//         Parsed as a value list.)
 // list  → "[]" | "[" value ( "," value )* "]" | "[" result ( "," result )* "]"
 pub fn parse_value(s: &str) -> Option<(Value, &str)> {
     let c = s.chars().next()?;
@@ -311,6 +313,23 @@ pub fn parse_value(s: &str) -> Option<(Value, &str)> {
                     None => {
                         if s.chars().next()? == '}' {
                             return Some((Value::Tuple(tuple), &s['}'.len_utf8()..]));
+                        } else if tuple.is_empty() {
+                            // e.g. script={"silent","print x"}
+                            let (value0, s_) = parse_value(s)?;
+                            let mut values = vec![value0];
+                            s = s_;
+                            loop {
+                                let c = s.chars().next()?;
+                                if c == ',' {
+                                    let (value, s_) = parse_value(&s[c.len_utf8()..])?;
+                                    values.push(value);
+                                    s = s_;
+                                } else if c == '}' {
+                                    return Some((Value::ValueList(values), &s[c.len_utf8()..]));
+                                } else {
+                                    return None;
+                                }
+                            }
                         } else {
                             return None;
                         }
@@ -447,6 +466,16 @@ fn parse_value_tests() {
     );
     assert_eq!(parse_value("{}"), Some((Value::Tuple(HashMap::new()), "")));
     assert_eq!(parse_value("[]"), Some((Value::ValueList(vec![]), "")));
+    assert_eq!(
+        parse_value("{\"silent\",\"print x\"}"),
+        Some((
+            Value::ValueList(vec![
+                Value::Const("silent".to_string()),
+                Value::Const("print x".to_string())
+            ]),
+            ""
+        ))
+    );
 
     let input = "[frame={level=\"0\",addr=\"0x00000000006eff82\",func=\"initCapabilities\",file=\
                  \"rts/Capability.c\",fullname=\"/home/omer/haskell/ghc-gc/rts/Capability.c\",\
@@ -32,7 +32,10 @@ pub fn parse_frame(v: HashMap<mi::Var, mi::Value>) -> Option<Frame> {
             None => None,
             Some(line) => Some(line.get_const_ref()?.parse::<usize>().ok()?),
         },
-        from: None, // TODO
+        from: match v.get("from") {
+            None => None,
+            Some(from) => Some(from.get_const_ref()?.to_string()),
+        },
     })
 }
 
@@ -45,15 +48,76 @@ pub fn parse_backtrace(v: Vec<(mi::Var, mi::Value)>) -> Option<Backtrace> {
     Some(Backtrace(frames))
 }
 
+/// Parse a thread in `-thread-info` results.
+pub fn parse_thread(mut v: HashMap<mi::Var, mi::Value>) -> Option<Thread> {
+    Some(Thread {
+        id: v.get("id")?.get_const_ref()?.parse::<i32>().ok()?,
+        target_id: v.get("target-id")?.get_const_ref()?.to_string(),
+        name: match v.get("name") {
+            None => None,
+            Some(name) => Some(name.get_const_ref()?.to_string()),
+        },
+        state: match v.get("state").and_then(|state| state.get_const_ref()) {
+            Some("running") => ThreadState::Running,
+            _ => ThreadState::Stopped,
+        },
+        core: match v.get("core") {
+            None => None,
+            Some(core) => Some(core.get_const_ref()?.parse::<u32>().ok()?),
+        },
+        frame: match v.remove("frame") {
+            None => None,
+            Some(frame) => Some(parse_frame(frame.get_tuple()?)?),
+        },
+    })
+}
+
+/// Parse `-thread-info` results. Returns the threads and the current thread id. Current thread id
+/// is not available when there are no threads.
+pub fn parse_thread_info_result(
+    mut results: HashMap<mi::Var, mi::Value>,
+) -> Option<(Vec<Thread>, Option<i32>)> {
+    let current_thread_id = match results.get("current-thread-id") {
+        None => None,
+        Some(id) => Some(id.get_const_ref()?.parse::<i32>().ok()?),
+    };
+    let mut threads = vec![];
+    for thread in results.remove("threads")?.get_value_list()? {
+        threads.push(parse_thread(thread.get_tuple()?)?);
+    }
+    Some((threads, current_thread_id))
+}
+
 pub fn parse_breakpoint(v: HashMap<mi::Var, mi::Value>) -> Option<Breakpoint> {
     let number = v.get("number")?.get_const_ref()?.parse::<u32>().ok()?;
-    let type_ = {
-        guard!(v.get("type")?.get_const_ref()? == "breakpoint");
-        BreakpointType::Breakpoint
+    let type_ = match v.get("type")?.get_const_ref()? {
+        "breakpoint" => BreakpointType::Breakpoint,
+        "hw breakpoint" => BreakpointType::HardwareBreakpoint,
+        "dprintf" => BreakpointType::Dprintf,
+        "catchpoint" => BreakpointType::Catchpoint(
+            match v.get("catch-type").and_then(|type_| type_.get_const_ref()) {
+                Some("throw") => CatchpointType::Throw,
+                Some("catch") => CatchpointType::Catch,
+                Some("rethrow") => CatchpointType::Rethrow,
+                Some("syscall") => CatchpointType::Syscall,
+                Some("fork") => CatchpointType::Fork,
+                Some("vfork") => CatchpointType::Vfork,
+                Some("exec") => CatchpointType::Exec,
+                Some("load") => CatchpointType::Load,
+                Some("unload") => CatchpointType::Unload,
+                Some("signal") => CatchpointType::Signal,
+                _ => CatchpointType::Other,
+            },
+        ),
+        _ => {
+            return None;
+        }
     };
     let disposition = match v.get("disp")?.get_const_ref()? {
         "keep" => BreakpointDisposition::Keep,
         "nokeep" => BreakpointDisposition::NoKeep,
+        "del" => BreakpointDisposition::Delete,
+        "dis" => BreakpointDisposition::Disable,
         _ => {
             return None;
         }
@@ -65,10 +129,29 @@ pub fn parse_breakpoint(v: HashMap<mi::Var, mi::Value>) -> Option<Breakpoint> {
             return None;
         }
     };
-    let address = v.get("addr")?.get_const_ref()?.to_string();
-    // TODO: what's the difference between "original-location" and "func"? "func" isn't always
-    // available
-    let original_location = v.get("original-location")?.get_const_ref()?.to_string();
+    let (address, original_location) = match type_ {
+        BreakpointType::Catchpoint(_) => {
+            // {number="2",type="catchpoint",disp="keep",enabled="y",what="exception throw",
+            //  catch-type="throw",times="0"}
+            (
+                "".to_string(),
+                v.get("what")?.get_const_ref()?.trim().to_string(),
+            )
+        }
+        _ => (
+            v.get("addr")?.get_const_ref()?.to_string(),
+            // TODO: what's the difference between "original-location" and "func"? "func" isn't
+            // always available
+            // Older GDBs only have "pending" for pending breakpoints
+            v.get("original-location")
+                .or_else(|| v.get("pending"))?
+                .get_const_ref()?
+                .to_string(),
+        ),
+    };
+    // {number="1",type="breakpoint",disp="keep",enabled="y",addr="<PENDING>",pending="foo",
+    //  times="0",original-location="foo"}
+    let pending = v.contains_key("pending");
     let file = match v.get("file") {
         None => None,
         Some(file) => Some(file.get_const_ref()?.to_string()),
@@ -86,7 +169,25 @@ pub fn parse_breakpoint(v: HashMap<mi::Var, mi::Value>) -> Option<Breakpoint> {
         None => None,
         Some(cond) => Some(cond.get_const_ref()?.to_string()),
     };
+    let thread = match v.get("thread") {
+        None => None,
+        Some(thread) => Some(thread.get_const_ref()?.parse::<i32>().ok()?),
+    };
+    let ignore_count = match v.get("ignore") {
+        None => 0,
+        Some(ignore) => ignore.get_const_ref()?.parse::<u32>().ok()?,
+    };
     let hits = v.get("times")?.get_const_ref()?.parse::<u32>().ok()?;
+    let script = match v.get("script") {
+        None => vec![],
+        Some(mi::Value::ValueList(commands)) => commands
+            .iter()
+            .map(|command| command.get_const_ref().map(str::to_string))
+            .collect::<Option<Vec<_>>>()?,
+        Some(_) => {
+            return None;
+        }
+    };
 
     Some(Breakpoint {
         number,
@@ -94,15 +195,37 @@ pub fn parse_breakpoint(v: HashMap<mi::Var, mi::Value>) -> Option<Breakpoint> {
         disposition,
         enabled,
         address,
+        pending,
         original_location,
         file,
         fullname,
         line,
         cond,
+        thread,
+        ignore_count,
         hits,
+        script,
     })
 }
 
+/// Parse result of a `-break-list` or `-break-info` command. Watchpoints are skipped.
+pub fn parse_break_list_result(
+    mut results: HashMap<mi::Var, mi::Value>,
+) -> Option<Vec<Breakpoint>> {
+    let mut table = results.remove("BreakpointTable")?.get_tuple()?;
+    match table.remove("body")? {
+        mi::Value::ResultList(bkpts) => Some(
+            bkpts
+                .into_iter()
+                .filter_map(|(_, bkpt)| parse_breakpoint(bkpt.get_tuple()?))
+                .collect(),
+        ),
+        // Empty list
+        mi::Value::ValueList(_) => Some(vec![]),
+        _ => None,
+    }
+}
+
 pub fn parse_break_insert_result(mut results: HashMap<mi::Var, mi::Value>) -> Option<Breakpoint> {
     parse_breakpoint(results.remove("bkpt")?.get_tuple()?)
 }
@@ -114,31 +237,118 @@ fn parse_expr(mut v: HashMap<mi::Var, mi::Value>) -> Option<Value> {
         None => None,
         Some(expr) => Some(expr.get_const()?),
     };
-    let value = v.remove("value")?.get_const()?;
+    // Pretty-printers without `to_string` don't have a value
+    let value = match v.remove("value") {
+        None => "".to_string(),
+        Some(value) => value.get_const()?,
+    };
     let name = v.remove("name")?.get_const()?;
     let type_ = v.remove("type")?.get_const()?;
     let n_children = v.remove("numchild")?.get_const()?.parse::<usize>().ok()?;
+    let dynamic = parse_flag(&v, "dynamic")?;
+    let has_more = parse_flag(&v, "has_more")?;
+    let display_hint = match v.get("displayhint") {
+        None => None,
+        Some(hint) => parse_display_hint(hint.get_const_ref()?),
+    };
     Some(Value {
         expr,
         value,
         name,
         type_,
         n_children,
+        dynamic,
+        has_more,
+        display_hint,
     })
 }
 
-pub fn parse_var_create_result(mut results: HashMap<mi::Var, mi::Value>) -> Option<Value> {
+/// Parse an optional "0"/"1" field. Missing fields are parsed as `false`.
+fn parse_flag(v: &HashMap<mi::Var, mi::Value>, field: &str) -> Option<bool> {
+    match v.get(field) {
+        None => Some(false),
+        Some(flag) => match flag.get_const_ref()? {
+            "0" => Some(false),
+            "1" => Some(true),
+            _ => None,
+        },
+    }
+}
+
+fn parse_display_hint(hint: &str) -> Option<DisplayHint> {
+    match hint {
+        "array" => Some(DisplayHint::Array),
+        "map" => Some(DisplayHint::Map),
+        "string" => Some(DisplayHint::String),
+        _ => None,
+    }
+}
+
+pub fn parse_var_create_result(results: HashMap<mi::Var, mi::Value>) -> Option<Value> {
     parse_expr(results)
 }
 
+/// Parse result of a `-var-list-children` command. Returns the children and the `has_more` field,
+/// which says whether there are more children after the requested range (only for dynamic
+/// varobjs).
 pub fn parse_var_list_children_result(
     mut results: HashMap<mi::Var, mi::Value>,
-) -> Option<Vec<Value>> {
+) -> Option<(Vec<Value>, bool)> {
     println!("parse_var_list_children_result({:?})", results);
-    let list = results.remove("children")?.get_result_list()?;
     let mut ret = vec![];
-    for (_, child) in list {
-        ret.push(parse_expr(child.get_tuple()?)?);
+    // "children" is omitted when there aren't any children
+    if let Some(list) = results.remove("children") {
+        for (_, child) in list.get_result_list()? {
+            ret.push(parse_expr(child.get_tuple()?)?);
+        }
+    }
+    let has_more = parse_flag(&results, "has_more")?;
+    Some((ret, has_more))
+}
+
+/// Parse a `changelist` entry of a `-var-update` result.
+fn parse_var_change(mut v: HashMap<mi::Var, mi::Value>) -> Option<VarChange> {
+    let name = v.remove("name")?.get_const()?;
+    let value = match v.remove("value") {
+        None => None,
+        Some(value) => Some(value.get_const()?),
+    };
+    let in_scope = match v.get("in_scope")?.get_const_ref()? {
+        "true" => VarScope::InScope,
+        "false" => VarScope::OutOfScope,
+        "invalid" => VarScope::Invalid,
+        _ => {
+            return None;
+        }
+    };
+    let type_changed = v.get("type_changed")?.get_const_ref()? == "true";
+    let new_type = match v.remove("new_type") {
+        None => None,
+        Some(new_type) => Some(new_type.get_const()?),
+    };
+    let new_num_children = match v.get("new_num_children") {
+        None => None,
+        Some(n) => Some(n.get_const_ref()?.parse::<usize>().ok()?),
+    };
+    let dynamic = parse_flag(&v, "dynamic")?;
+    let has_more = parse_flag(&v, "has_more")?;
+    Some(VarChange {
+        name,
+        value,
+        in_scope,
+        type_changed,
+        new_type,
+        new_num_children,
+        dynamic,
+        has_more,
+    })
+}
+
+pub fn parse_var_update_result(mut results: HashMap<mi::Var, mi::Value>) -> Option<Vec<VarChange>> {
+    let list = results.remove("changelist")?.get_value_list()?;
+    let mut ret = vec![];
+    for change in list {
+        ret.push(parse_var_change(change.get_tuple()?)?);
     }
     Some(ret)
 }
@@ -162,3 +372,811 @@ pub fn _parse_asm_insts(insts: Vec<mi::Value>) -> Option<Vec<AsmInst>> {
     }
     Some(ret)
 }
+
+/// Parse `thread-id` field of `*running` and `stopped-threads` field of `*stopped` records. These
+/// are either "all" or thread ids. Returns `None` for "all", and for unexpected values, so that
+/// all threads are updated when in doubt.
+pub fn parse_thread_ids(v: &mi::Value) -> Option<Vec<i32>> {
+    match v {
+        mi::Value::Const(ref s) => s.parse::<i32>().ok().map(|id| vec![id]),
+        mi::Value::ValueList(ref vs) => vs
+            .iter()
+            .map(|v| v.get_const_ref().and_then(|s| s.parse::<i32>().ok()))
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Parse a thread group in `-list-thread-groups` results, e.g.
+/// `{id="i1",type="process",pid="19785",executable="/usr/bin/guru",cores=["4"]}`.
+pub fn parse_thread_group(v: HashMap<mi::Var, mi::Value>) -> Option<Inferior> {
+    Some(Inferior {
+        id: v.get("id")?.get_const_ref()?.to_string(),
+        pid: match v.get("pid") {
+            None => None,
+            Some(pid) => Some(pid.get_const_ref()?.parse::<u32>().ok()?),
+        },
+        executable: match v.get("executable") {
+            None => None,
+            Some(executable) => Some(executable.get_const_ref()?.to_string()),
+        },
+        // Not in `-list-thread-groups` results, see `parse_exit_code`
+        exit_code: None,
+    })
+}
+
+pub fn parse_list_thread_groups_result(
+    mut results: HashMap<mi::Var, mi::Value>,
+) -> Option<Vec<Inferior>> {
+    let mut inferiors = vec![];
+    for group in results.remove("groups")?.get_value_list()? {
+        inferiors.push(parse_thread_group(group.get_tuple()?)?);
+    }
+    Some(inferiors)
+}
+
+/// Parse `exit-code` field of `=thread-group-exited` records. GDB prints exit codes in octal.
+pub fn parse_exit_code(exit_code: &str) -> Option<u32> {
+    u32::from_str_radix(exit_code, 8).ok()
+}
+
+/// Parse an optional string field.
+fn parse_opt_string(v: &HashMap<mi::Var, mi::Value>, field: &str) -> Option<Option<String>> {
+    match v.get(field) {
+        None => Some(None),
+        Some(value) => Some(Some(value.get_const_ref()?.to_string())),
+    }
+}
+
+/// Parse an optional integer field.
+fn parse_opt_u32(v: &HashMap<mi::Var, mi::Value>, field: &str) -> Option<Option<u32>> {
+    match v.get(field) {
+        None => Some(None),
+        Some(value) => Some(Some(value.get_const_ref()?.parse::<u32>().ok()?)),
+    }
+}
+
+fn parse_i32(v: &HashMap<mi::Var, mi::Value>, field: &str) -> Option<i32> {
+    v.get(field)?.get_const_ref()?.parse::<i32>().ok()
+}
+
+fn parse_string(v: &HashMap<mi::Var, mi::Value>, field: &str) -> Option<String> {
+    Some(v.get(field)?.get_const_ref()?.to_string())
+}
+
+/// Parse an exec, status or notify async record. Returns `None` for records we don't know about.
+pub fn parse_async_record(mut async_: mi::AsyncRecord) -> Option<Event> {
+    let v = &mut async_.results;
+    Some(match async_.class.as_str() {
+        "stopped" => Event::Stopped(parse_stop_info(std::mem::replace(v, HashMap::new()))),
+        "running" => Event::Running {
+            // thread-id="all" in all-stop mode
+            thread_ids: v.get("thread-id").and_then(parse_thread_ids),
+        },
+        "thread-created" => Event::ThreadCreated {
+            id: parse_i32(v, "id")?,
+            group_id: parse_string(v, "group-id")?,
+        },
+        "thread-exited" => Event::ThreadExited {
+            id: parse_i32(v, "id")?,
+            group_id: parse_string(v, "group-id")?,
+        },
+        "thread-selected" => Event::ThreadSelected {
+            id: parse_i32(v, "id")?,
+        },
+        "thread-group-added" => Event::ThreadGroupAdded {
+            id: parse_string(v, "id")?,
+        },
+        "thread-group-removed" => Event::ThreadGroupRemoved {
+            id: parse_string(v, "id")?,
+        },
+        "thread-group-started" => Event::ThreadGroupStarted {
+            id: parse_string(v, "id")?,
+            pid: parse_opt_u32(v, "pid")?,
+        },
+        "thread-group-exited" => Event::ThreadGroupExited {
+            id: parse_string(v, "id")?,
+            exit_code: match v.get("exit-code") {
+                None => None,
+                Some(exit_code) => Some(parse_exit_code(exit_code.get_const_ref()?)?),
+            },
+        },
+        "library-loaded" => {
+            Event::LibraryLoaded(parse_library(std::mem::replace(v, HashMap::new()))?)
+        }
+        "library-unloaded" => {
+            Event::LibraryUnloaded(parse_library(std::mem::replace(v, HashMap::new()))?)
+        }
+        "breakpoint-created" => {
+            Event::BreakpointCreated(parse_breakpoint(v.remove("bkpt")?.get_tuple()?)?)
+        }
+        "breakpoint-modified" => {
+            Event::BreakpointModified(parse_breakpoint(v.remove("bkpt")?.get_tuple()?)?)
+        }
+        "breakpoint-deleted" => Event::BreakpointDeleted {
+            id: v.get("id")?.get_const_ref()?.parse::<u32>().ok()?,
+        },
+        "cmd-param-changed" => Event::CmdParamChanged {
+            param: parse_string(v, "param")?,
+            value: parse_string(v, "value")?,
+        },
+        "memory-changed" => Event::MemoryChanged {
+            thread_group: parse_string(v, "thread-group")?,
+            addr: parse_string(v, "addr")?,
+            len: parse_string(v, "len")?,
+            code: v.get("type").and_then(|type_| type_.get_const_ref()) == Some("code"),
+        },
+        _ => {
+            return None;
+        }
+    })
+}
+
+/// Parse a `library-loaded` or `library-unloaded` record. Unloaded records don't have
+/// `symbols-loaded` and `ranges`.
+pub fn parse_library(mut v: HashMap<mi::Var, mi::Value>) -> Option<Library> {
+    let mut ranges = vec![];
+    if let Some(ranges_) = v.remove("ranges") {
+        for range in ranges_.get_value_list()? {
+            let range = range.get_tuple()?;
+            ranges.push((parse_string(&range, "from")?, parse_string(&range, "to")?));
+        }
+    }
+    Some(Library {
+        id: parse_string(&v, "id")?,
+        target_name: parse_string(&v, "target-name")?,
+        host_name: parse_string(&v, "host-name")?,
+        symbols_loaded: v.get("symbols-loaded").and_then(|s| s.get_const_ref()) == Some("1"),
+        thread_group: parse_opt_string(&v, "thread-group")?,
+        ranges,
+    })
+}
+
+/// Parse `-file-list-shared-libraries` results.
+pub fn parse_shared_libraries_result(
+    mut results: HashMap<mi::Var, mi::Value>,
+) -> Option<Vec<Library>> {
+    let mut libraries = vec![];
+    for library in results.remove("shared-libraries")?.get_value_list()? {
+        libraries.push(parse_library(library.get_tuple()?)?);
+    }
+    Some(libraries)
+}
+
+/// Parse `-symbol-info-functions`, `-symbol-info-variables` and `-symbol-info-types` results.
+pub fn parse_symbol_info_result(mut results: HashMap<mi::Var, mi::Value>) -> Option<SymbolInfo> {
+    let mut symbols = results.remove("symbols")?.get_tuple()?;
+    let mut info = SymbolInfo::default();
+    if let Some(debug) = symbols.remove("debug") {
+        for file in debug.get_value_list()? {
+            let mut file = file.get_tuple()?;
+            let mut file_symbols = vec![];
+            for symbol in file.remove("symbols")?.get_value_list()? {
+                let symbol = symbol.get_tuple()?;
+                file_symbols.push(Symbol {
+                    name: parse_string(&symbol, "name")?,
+                    line: parse_opt_u32(&symbol, "line")?,
+                    type_: parse_opt_string(&symbol, "type")?,
+                    description: parse_opt_string(&symbol, "description")?,
+                });
+            }
+            info.debug.push(SymbolFile {
+                filename: parse_string(&file, "filename")?,
+                fullname: parse_opt_string(&file, "fullname")?,
+                symbols: file_symbols,
+            });
+        }
+    }
+    if let Some(nondebug) = symbols.remove("nondebug") {
+        for symbol in nondebug.get_value_list()? {
+            let symbol = symbol.get_tuple()?;
+            info.nondebug.push(NondebugSymbol {
+                name: parse_string(&symbol, "name")?,
+                address: parse_string(&symbol, "address")?,
+            });
+        }
+    }
+    Some(info)
+}
+
+/// Parse `-file-list-exec-source-files` results.
+pub fn parse_source_files_result(
+    mut results: HashMap<mi::Var, mi::Value>,
+) -> Option<Vec<SourceFile>> {
+    let mut files = vec![];
+    for file in results.remove("files")?.get_value_list()? {
+        let file = file.get_tuple()?;
+        files.push(SourceFile {
+            file: parse_string(&file, "file")?,
+            fullname: parse_opt_string(&file, "fullname")?,
+        });
+    }
+    Some(files)
+}
+
+/// Parse output of `info signals` and `handle` console commands. Lines other than signal table
+/// rows are ignored. Example:
+///
+/// ```text
+/// Signal        Stop	Print	Pass to program	Description
+///
+/// SIGHUP        Yes	Yes	Yes		Hangup
+/// SIGINT        Yes	Yes	No		Interrupt
+/// ```
+pub fn parse_signal_table(output: &str) -> Vec<Signal> {
+    let yes_no = |s: &str| match s {
+        "Yes" => Some(true),
+        "No" => Some(false),
+        _ => None,
+    };
+    let mut signals = vec![];
+    for line in output.lines() {
+        let mut words = line.split_whitespace();
+        let name = match words.next() {
+            None => continue,
+            Some(name) => name,
+        };
+        let stop = words.next().and_then(yes_no);
+        let print = words.next().and_then(yes_no);
+        let pass = words.next().and_then(yes_no);
+        if let (Some(stop), Some(print), Some(pass)) = (stop, print, pass) {
+            signals.push(Signal {
+                name: name.to_string(),
+                stop,
+                print,
+                pass,
+                description: words.collect::<Vec<_>>().join(" "),
+            });
+        }
+    }
+    signals
+}
+
+/// Parse fields of a `*stopped` record. Never fails, as the stop should be handled (threads and
+/// expressions updated etc.) even when some fields are missing or can't be parsed. Fields that
+/// can't be parsed are left out, reasons with missing fields become `StopReason::Other`.
+pub fn parse_stop_info(mut v: HashMap<mi::Var, mi::Value>) -> StopInfo {
+    let reason = v
+        .remove("reason")
+        .and_then(|reason| reason.get_const())
+        .map(|reason| parse_stop_reason(&reason, &mut v).unwrap_or(StopReason::Other(reason)));
+    StopInfo {
+        reason,
+        thread_id: v
+            .get("thread-id")
+            .and_then(|id| id.get_const_ref())
+            .and_then(|id| id.parse::<i32>().ok()),
+        stopped_threads: v.get("stopped-threads").and_then(parse_thread_ids),
+        frame: v
+            .remove("frame")
+            .and_then(|frame| frame.get_tuple())
+            .and_then(parse_frame),
+        core: parse_opt_u32(&v, "core").and_then(|core| core),
+    }
+}
+
+fn parse_stop_reason(reason: &str, v: &mut HashMap<mi::Var, mi::Value>) -> Option<StopReason> {
+    Some(match reason {
+        "breakpoint-hit" => StopReason::BreakpointHit {
+            number: parse_opt_u32(v, "bkptno")??,
+        },
+        "watchpoint-trigger" | "read-watchpoint-trigger" | "access-watchpoint-trigger" => {
+            // wpt={number="2",exp="x"},value={old="1",new="2"}
+            let (type_, wpt_field) = match reason {
+                "watchpoint-trigger" => (WatchpointType::Write, "wpt"),
+                "read-watchpoint-trigger" => (WatchpointType::Read, "hw-rwpt"),
+                _ => (WatchpointType::ReadWrite, "hw-awpt"),
+            };
+            let wpt = v.remove(wpt_field)?.get_tuple()?;
+            let value = v.remove("value")?.get_tuple()?;
+            StopReason::WatchpointTrigger {
+                number: parse_opt_u32(&wpt, "number")??,
+                type_,
+                expr: parse_string(&wpt, "exp")?,
+                old_value: parse_opt_string(&value, "old")?,
+                value: match parse_opt_string(&value, "new")? {
+                    Some(new) => Some(new),
+                    None => parse_opt_string(&value, "value")?,
+                },
+            }
+        }
+        "watchpoint-scope" => StopReason::WatchpointScope {
+            number: parse_opt_u32(v, "wpnum")??,
+        },
+        "function-finished" => StopReason::FunctionFinished {
+            return_value: parse_opt_string(v, "return-value")?,
+        },
+        "location-reached" => StopReason::LocationReached,
+        "end-stepping-range" => StopReason::EndSteppingRange,
+        "signal-received" => StopReason::SignalReceived {
+            name: parse_string(v, "signal-name")?,
+            meaning: parse_opt_string(v, "signal-meaning")?.unwrap_or_default(),
+        },
+        "exited" => StopReason::Exited {
+            exit_code: parse_exit_code(v.get("exit-code")?.get_const_ref()?)?,
+        },
+        "exited-normally" => StopReason::ExitedNormally,
+        "exited-signalled" => StopReason::ExitedSignalled {
+            name: parse_string(v, "signal-name")?,
+            meaning: parse_opt_string(v, "signal-meaning")?.unwrap_or_default(),
+        },
+        "fork" => StopReason::Fork {
+            new_pid: parse_opt_u32(v, "newpid")??,
+        },
+        "vfork" => StopReason::Vfork {
+            new_pid: parse_opt_u32(v, "newpid")??,
+        },
+        "exec" => StopReason::Exec {
+            new_exec: parse_string(v, "new-exec")?,
+        },
+        "syscall-entry" => StopReason::SyscallEntry {
+            name: parse_string(v, "syscall-name")?,
+        },
+        "syscall-return" => StopReason::SyscallReturn {
+            name: parse_string(v, "syscall-name")?,
+        },
+        "solib-event" => StopReason::SolibEvent,
+        "no-history" => StopReason::NoHistory,
+        other => StopReason::Other(other.to_string()),
+    })
+}
+
+#[test]
+fn parse_var_update_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{changelist=[{name=\"var1\",value=\"3\",in_scope=\"true\",type_changed=\"false\",\
+                 has_more=\"0\"},{name=\"var2\",in_scope=\"false\",type_changed=\"false\",\
+                 has_more=\"0\"},{name=\"var3\",value=\"{...}\",in_scope=\"true\",\
+                 type_changed=\"true\",new_type=\"struct foo\",new_num_children=\"2\",\
+                 has_more=\"0\"}]}";
+    let (results, _) = parse_value(input).unwrap();
+    let changes = parse_var_update_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(changes.len(), 3);
+
+    assert_eq!(changes[0].value, Some("3".to_string()));
+    assert_eq!(changes[0].in_scope, VarScope::InScope);
+    assert!(!changes[0].type_changed);
+
+    assert_eq!(changes[1].value, None);
+    assert_eq!(changes[1].in_scope, VarScope::OutOfScope);
+
+    assert!(changes[2].type_changed);
+    assert_eq!(changes[2].new_type, Some("struct foo".to_string()));
+    assert_eq!(changes[2].new_num_children, Some(2));
+}
+
+#[test]
+fn parse_var_create_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{name=\"var1\",numchild=\"0\",value=\"std::vector of length 3, capacity 4\",\
+                 type=\"std::vector<int, std::allocator<int> >\",thread-id=\"1\",\
+                 displayhint=\"array\",dynamic=\"1\",has_more=\"1\"}";
+    let (results, _) = parse_value(input).unwrap();
+    let value = parse_var_create_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(value.n_children, 0);
+    assert!(value.dynamic);
+    assert!(value.has_more);
+    assert_eq!(value.display_hint, Some(DisplayHint::Array));
+
+    // Children are omitted when there aren't any
+    let (results, _) = parse_value("{numchild=\"0\",has_more=\"0\"}").unwrap();
+    let (children, has_more) =
+        parse_var_list_children_result(results.get_tuple().unwrap()).unwrap();
+    assert!(children.is_empty());
+    assert!(!has_more);
+}
+
+#[test]
+fn parse_thread_ids_tests() {
+    use crate::mi::parser::parse_value;
+
+    let (v, _) = parse_value("\"all\"").unwrap();
+    assert_eq!(parse_thread_ids(&v), None);
+    let (v, _) = parse_value("\"3\"").unwrap();
+    assert_eq!(parse_thread_ids(&v), Some(vec![3]));
+    let (v, _) = parse_value("[\"2\",\"5\"]").unwrap();
+    assert_eq!(parse_thread_ids(&v), Some(vec![2, 5]));
+}
+
+#[test]
+fn parse_thread_info_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{threads=[{id=\"1\",target-id=\"Thread 0x7ffff7fbdb80 (LWP 19785)\",\
+                 name=\"guru\",frame={level=\"0\",addr=\"0x00007ffff591e2b7\",\
+                 func=\"__libc_write\",args=[],file=\"write.c\",line=\"27\"},\
+                 state=\"stopped\",core=\"4\"},{id=\"2\",\
+                 target-id=\"Thread 0x7fffed538700 (LWP 19789)\",state=\"running\"}],\
+                 current-thread-id=\"1\"}";
+    let (results, _) = parse_value(input).unwrap();
+    let (threads, current) = parse_thread_info_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(current, Some(1));
+    assert_eq!(threads.len(), 2);
+
+    assert_eq!(threads[0].id, 1);
+    assert_eq!(threads[0].name, Some("guru".to_string()));
+    assert_eq!(threads[0].state, ThreadState::Stopped);
+    assert_eq!(threads[0].core, Some(4));
+    assert_eq!(threads[0].frame.as_ref().unwrap().func, "__libc_write");
+
+    assert_eq!(threads[1].id, 2);
+    assert_eq!(threads[1].name, None);
+    assert_eq!(threads[1].state, ThreadState::Running);
+    assert_eq!(threads[1].core, None);
+    assert!(threads[1].frame.is_none());
+}
+
+#[test]
+fn parse_list_thread_groups_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{groups=[{id=\"i1\",type=\"process\",pid=\"19785\",\
+                 executable=\"/usr/bin/server\",cores=[\"4\"]},\
+                 {id=\"i2\",type=\"process\"}]}";
+    let (results, _) = parse_value(input).unwrap();
+    let inferiors = parse_list_thread_groups_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(
+        inferiors,
+        vec![
+            Inferior {
+                id: "i1".to_string(),
+                pid: Some(19785),
+                executable: Some("/usr/bin/server".to_string()),
+                exit_code: None,
+            },
+            Inferior::new("i2".to_string()),
+        ]
+    );
+
+    assert_eq!(parse_exit_code("0"), Some(0));
+    assert_eq!(parse_exit_code("01"), Some(1));
+    assert_eq!(parse_exit_code("0377"), Some(255));
+    assert_eq!(parse_exit_code("x"), None);
+}
+
+#[test]
+fn parse_stop_info_tests() {
+    use crate::mi::parser::parse_value;
+
+    let parse = |input: &str| {
+        let (v, _) = parse_value(input).unwrap();
+        parse_stop_info(v.get_tuple().unwrap())
+    };
+
+    let stop = parse(
+        "{reason=\"breakpoint-hit\",disp=\"keep\",bkptno=\"1\",frame={level=\"0\",\
+         addr=\"0x0000555555555131\",func=\"main\",args=[],file=\"test.c\",\
+         fullname=\"/tmp/test.c\",line=\"3\"},thread-id=\"1\",stopped-threads=\"all\",\
+         core=\"5\"}",
+    );
+    assert_eq!(stop.reason, Some(StopReason::BreakpointHit { number: 1 }));
+    assert_eq!(stop.thread_id, Some(1));
+    assert_eq!(stop.stopped_threads, None);
+    assert_eq!(stop.frame.unwrap().line, Some(3));
+    assert_eq!(stop.core, Some(5));
+
+    let stop = parse(
+        "{reason=\"watchpoint-trigger\",wpt={number=\"2\",exp=\"x\"},\
+         value={old=\"1\",new=\"2\"},thread-id=\"1\",stopped-threads=[\"1\"]}",
+    );
+    assert_eq!(
+        stop.reason,
+        Some(StopReason::WatchpointTrigger {
+            number: 2,
+            type_: WatchpointType::Write,
+            expr: "x".to_string(),
+            old_value: Some("1".to_string()),
+            value: Some("2".to_string()),
+        })
+    );
+    assert_eq!(stop.stopped_threads, Some(vec![1]));
+
+    let stop = parse(
+        "{reason=\"read-watchpoint-trigger\",hw-rwpt={number=\"3\",exp=\"y\"},\
+         value={value=\"7\"}}",
+    );
+    assert_eq!(
+        stop.reason,
+        Some(StopReason::WatchpointTrigger {
+            number: 3,
+            type_: WatchpointType::Read,
+            expr: "y".to_string(),
+            old_value: None,
+            value: Some("7".to_string()),
+        })
+    );
+
+    let stop = parse(
+        "{reason=\"signal-received\",signal-name=\"SIGSEGV\",\
+         signal-meaning=\"Segmentation fault\"}",
+    );
+    assert_eq!(
+        stop.reason,
+        Some(StopReason::SignalReceived {
+            name: "SIGSEGV".to_string(),
+            meaning: "Segmentation fault".to_string(),
+        })
+    );
+
+    // Exit codes are in octal
+    let stop = parse("{reason=\"exited\",exit-code=\"012\"}");
+    assert_eq!(stop.reason, Some(StopReason::Exited { exit_code: 10 }));
+    assert!(stop.frame.is_none());
+
+    let stop = parse("{thread-id=\"1\"}");
+    assert_eq!(stop.reason, None);
+
+    // GDB doesn't send names of syscalls it doesn't know about
+    let stop = parse(
+        "{reason=\"syscall-entry\",syscall-number=\"999\",thread-id=\"1\",\
+         stopped-threads=\"all\"}",
+    );
+    assert_eq!(
+        stop.reason,
+        Some(StopReason::Other("syscall-entry".to_string()))
+    );
+    assert_eq!(stop.thread_id, Some(1));
+
+    // Other fields are still available when one can't be parsed
+    let stop = parse("{reason=\"breakpoint-hit\",bkptno=\"x\",thread-id=\"2\",core=\"1\"}");
+    assert_eq!(
+        stop.reason,
+        Some(StopReason::Other("breakpoint-hit".to_string()))
+    );
+    assert_eq!(stop.thread_id, Some(2));
+    assert_eq!(stop.core, Some(1));
+}
+
+#[test]
+fn parse_async_record_tests() {
+    use crate::mi::parser::parse_value;
+
+    let record = |class: &str, results: &str| {
+        let (v, _) = parse_value(results).unwrap();
+        mi::AsyncRecord {
+            token: None,
+            class: class.to_string(),
+            results: v.get_tuple().unwrap(),
+        }
+    };
+
+    match parse_async_record(record(
+        "library-loaded",
+        "{id=\"/lib/libc.so.6\",target-name=\"/lib/libc.so.6\",\
+         host-name=\"/lib/libc.so.6\",symbols-loaded=\"0\",thread-group=\"i1\",\
+         ranges=[{from=\"0x00007ffff7dc5630\",to=\"0x00007ffff7f3a27d\"}]}",
+    )) {
+        Some(Event::LibraryLoaded(lib)) => {
+            assert_eq!(lib.target_name, "/lib/libc.so.6");
+            assert!(!lib.symbols_loaded);
+            assert_eq!(lib.thread_group, Some("i1".to_string()));
+            assert_eq!(
+                lib.ranges,
+                vec![(
+                    "0x00007ffff7dc5630".to_string(),
+                    "0x00007ffff7f3a27d".to_string()
+                )]
+            );
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    match parse_async_record(record(
+        "cmd-param-changed",
+        "{param=\"follow-fork-mode\",value=\"child\"}",
+    )) {
+        Some(Event::CmdParamChanged { param, value }) => {
+            assert_eq!(param, "follow-fork-mode");
+            assert_eq!(value, "child");
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    match parse_async_record(record(
+        "thread-group-exited",
+        "{id=\"i1\",exit-code=\"01\"}",
+    )) {
+        Some(Event::ThreadGroupExited { id, exit_code }) => {
+            assert_eq!(id, "i1");
+            assert_eq!(exit_code, Some(1));
+        }
+        other => panic!("Unexpected event: {:?}", other),
+    }
+
+    assert!(parse_async_record(record("tsv-created", "{name=\"trace_timestamp\"}")).is_none());
+}
+
+#[test]
+fn parse_shared_libraries_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{shared-libraries=[{id=\"/lib64/ld-linux-x86-64.so.2\",\
+                 target-name=\"/lib64/ld-linux-x86-64.so.2\",\
+                 host-name=\"/lib64/ld-linux-x86-64.so.2\",symbols-loaded=\"1\",\
+                 thread-group=\"i1\",ranges=[{from=\"0x00007ffff7fd0100\",\
+                 to=\"0x00007ffff7ff2684\"}]}]}";
+    let (results, _) = parse_value(input).unwrap();
+    let libraries = parse_shared_libraries_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(libraries.len(), 1);
+    assert_eq!(libraries[0].id, "/lib64/ld-linux-x86-64.so.2");
+    assert!(libraries[0].symbols_loaded);
+    assert_eq!(libraries[0].ranges.len(), 1);
+
+    // Frames in libraries without debug info have `from` instead of file and line
+    let input = "{level=\"0\",addr=\"0x00007ffff7e4a2b7\",func=\"write\",\
+                 from=\"/lib/x86_64-linux-gnu/libc.so.6\"}";
+    let (frame, _) = parse_value(input).unwrap();
+    let frame = parse_frame(frame.get_tuple().unwrap()).unwrap();
+    assert_eq!(
+        frame.from,
+        Some("/lib/x86_64-linux-gnu/libc.so.6".to_string())
+    );
+    assert_eq!(frame.file, None);
+}
+
+#[test]
+fn parse_signal_table_tests() {
+    let output = "Signal        Stop\tPrint\tPass to program\tDescription\n\
+                  \n\
+                  SIGHUP        Yes\tYes\tYes\t\tHangup\n\
+                  SIGINT        Yes\tYes\tNo\t\tInterrupt\n\
+                  SIGUSR1       No\tNo\tYes\t\tUser defined signal 1\n\
+                  \n\
+                  Use the \"handle\" command to change these tables.\n";
+    let signals = parse_signal_table(output);
+    assert_eq!(signals.len(), 3);
+    assert_eq!(
+        signals[1],
+        Signal {
+            name: "SIGINT".to_string(),
+            stop: true,
+            print: true,
+            pass: false,
+            description: "Interrupt".to_string(),
+        }
+    );
+    assert_eq!(signals[2].description, "User defined signal 1");
+    assert!(!signals[2].stop);
+}
+
+#[test]
+fn parse_breakpoint_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{number=\"1\",type=\"breakpoint\",disp=\"keep\",enabled=\"y\",\
+                 addr=\"0x0000000000001131\",func=\"main\",file=\"test.c\",\
+                 fullname=\"/tmp/test.c\",line=\"3\",thread-groups=[\"i1\"],times=\"0\",\
+                 original-location=\"main\"}";
+    let (bkpt, _) = parse_value(input).unwrap();
+    let bkpt = parse_breakpoint(bkpt.get_tuple().unwrap()).unwrap();
+    assert_eq!(bkpt.type_, BreakpointType::Breakpoint);
+    assert_eq!(bkpt.original_location, "main");
+    assert_eq!(bkpt.line, Some(3));
+
+    let input = "{number=\"2\",type=\"catchpoint\",disp=\"keep\",enabled=\"y\",\
+                 what=\"exception throw\",catch-type=\"throw\",thread-groups=[\"i1\"],\
+                 times=\"0\"}";
+    let (bkpt, _) = parse_value(input).unwrap();
+    let bkpt = parse_breakpoint(bkpt.get_tuple().unwrap()).unwrap();
+    assert_eq!(
+        bkpt.type_,
+        BreakpointType::Catchpoint(CatchpointType::Throw)
+    );
+    assert_eq!(bkpt.original_location, "exception throw");
+    assert_eq!(bkpt.address, "");
+
+    let input = "{number=\"3\",type=\"catchpoint\",disp=\"keep\",enabled=\"y\",\
+                 what=\"syscall \\\"write\\\" \",catch-type=\"syscall\",times=\"1\"}";
+    let (bkpt, _) = parse_value(input).unwrap();
+    let bkpt = parse_breakpoint(bkpt.get_tuple().unwrap()).unwrap();
+    assert_eq!(
+        bkpt.type_,
+        BreakpointType::Catchpoint(CatchpointType::Syscall)
+    );
+    assert_eq!(bkpt.original_location, "syscall \"write\"");
+    assert_eq!(bkpt.hits, 1);
+
+    let input = "{number=\"4\",type=\"dprintf\",disp=\"keep\",enabled=\"y\",\
+                 addr=\"0x0000000000001131\",func=\"main\",file=\"test.c\",\
+                 fullname=\"/tmp/test.c\",line=\"3\",thread-groups=[\"i1\"],times=\"0\",\
+                 script={\"printf \\\"x = %d\\\\n\\\",x\"},original-location=\"test.c:3\"}";
+    let (bkpt, _) = parse_value(input).unwrap();
+    let bkpt = parse_breakpoint(bkpt.get_tuple().unwrap()).unwrap();
+    assert_eq!(bkpt.type_, BreakpointType::Dprintf);
+    assert_eq!(bkpt.script, vec!["printf \"x = %d\\n\",x".to_string()]);
+    assert_eq!(bkpt.dprintf_format(), Some("\"x = %d\\n\",x"));
+
+    let input = "{number=\"5\",type=\"hw breakpoint\",disp=\"del\",enabled=\"n\",\
+                 addr=\"<PENDING>\",pending=\"libfoo.so:foo\",thread=\"2\",ignore=\"3\",\
+                 times=\"0\",original-location=\"libfoo.so:foo\"}";
+    let (bkpt, _) = parse_value(input).unwrap();
+    let bkpt = parse_breakpoint(bkpt.get_tuple().unwrap()).unwrap();
+    assert_eq!(bkpt.type_, BreakpointType::HardwareBreakpoint);
+    assert_eq!(bkpt.disposition, BreakpointDisposition::Delete);
+    assert!(!bkpt.enabled);
+    assert!(bkpt.pending);
+    assert_eq!(bkpt.thread, Some(2));
+    assert_eq!(bkpt.ignore_count, 3);
+}
+
+#[test]
+fn parse_break_list_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{BreakpointTable={nr_rows=\"1\",nr_cols=\"6\",hdr=[{width=\"7\",\
+                 alignment=\"-1\",col_name=\"number\",colhdr=\"Num\"}],body=[bkpt={\
+                 number=\"2\",type=\"breakpoint\",disp=\"keep\",enabled=\"y\",\
+                 addr=\"0x0000000000001131\",func=\"main\",file=\"test.c\",\
+                 fullname=\"/tmp/test.c\",line=\"3\",thread-groups=[\"i1\"],times=\"0\",\
+                 script={\"print x\",\"bt 3\",\"continue\"},original-location=\"main\"}]}}";
+    let (results, _) = parse_value(input).unwrap();
+    let results = results.get_tuple().unwrap();
+    let bkpts = parse_break_list_result(results).unwrap();
+    assert_eq!(bkpts.len(), 1);
+    assert_eq!(bkpts[0].number, 2);
+    assert_eq!(bkpts[0].script, vec!["print x", "bt 3", "continue"]);
+
+    let input = "{BreakpointTable={nr_rows=\"0\",nr_cols=\"6\",hdr=[],body=[]}}";
+    let (results, _) = parse_value(input).unwrap();
+    let results = results.get_tuple().unwrap();
+    assert_eq!(parse_break_list_result(results).unwrap().len(), 0);
+}
+
+#[test]
+fn parse_symbol_info_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{symbols={debug=[{filename=\"test.c\",fullname=\"/tmp/test.c\",\
+                 symbols=[{line=\"3\",name=\"f\",type=\"int (int)\",\
+                 description=\"static int f(int);\"},{line=\"8\",name=\"main\",\
+                 type=\"int (void)\",description=\"int main(void);\"}]}],\
+                 nondebug=[{address=\"0x0000000000001000\",name=\"_init\"}]}}";
+    let (results, _) = parse_value(input).unwrap();
+    let info = parse_symbol_info_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(info.debug.len(), 1);
+    assert_eq!(info.debug[0].fullname, Some("/tmp/test.c".to_string()));
+    assert_eq!(
+        info.debug[0].symbols[0],
+        Symbol {
+            name: "f".to_string(),
+            line: Some(3),
+            type_: Some("int (int)".to_string()),
+            description: Some("static int f(int);".to_string()),
+        }
+    );
+    assert_eq!(info.nondebug[0].name, "_init");
+
+    // Types don't have descriptions, base types don't have lines
+    let input = "{symbols={debug=[{filename=\"test.c\",fullname=\"/tmp/test.c\",\
+                 symbols=[{line=\"1\",name=\"point\"},{name=\"int\"}]}]}}";
+    let (results, _) = parse_value(input).unwrap();
+    let info = parse_symbol_info_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(info.debug[0].symbols[1].line, None);
+    assert_eq!(info.debug[0].symbols[1].description, None);
+    assert!(info.nondebug.is_empty());
+}
+
+#[test]
+fn parse_source_files_result_tests() {
+    use crate::mi::parser::parse_value;
+
+    let input = "{files=[{file=\"test.c\",fullname=\"/tmp/test.c\",\
+                 debug-fully-read=\"true\"},{file=\"../sysdeps/x86_64/start.S\"}]}";
+    let (results, _) = parse_value(input).unwrap();
+    let files = parse_source_files_result(results.get_tuple().unwrap()).unwrap();
+    assert_eq!(
+        files,
+        vec![
+            SourceFile {
+                file: "test.c".to_string(),
+                fullname: Some("/tmp/test.c".to_string()),
+            },
+            SourceFile {
+                file: "../sysdeps/x86_64/start.S".to_string(),
+                fullname: None,
+            },
+        ]
+    );
+}
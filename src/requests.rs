@@ -0,0 +1,241 @@
+//! Bookkeeping for MI commands sent to GDB.
+//!
+//! Every command we send is prefixed with a unique token, and GDB prefixes the result record of
+//! the command with the same token. `Requests` allocates the tokens and maps them to the handlers
+//! of the commands. A request is removed when its result arrives, when it times out, or when GDB
+//! disconnects, so every handler is called exactly once.
+//!
+//! GDB runs commands one at a time, in the order they're sent. A request waits for the ones sent
+//! before it, so its timeout starts when it becomes the oldest pending request.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::mi;
+
+pub struct Requests<H> {
+    next_token: AtomicU64,
+    pending: RefCell<HashMap<u64, Request<H>>>,
+    /// When the last request was answered or timed out, i.e. when GDB started running the oldest
+    /// pending request (unless it was sent later).
+    last_done: Cell<Option<Instant>>,
+}
+
+pub struct Request<H> {
+    /// The command, without the token. Used in error messages.
+    pub command: String,
+    /// When the command was sent.
+    pub sent: Instant,
+    /// How long to wait for the result, after the requests sent before this one are answered.
+    /// `None` for commands that can take arbitrarily long, e.g. searching all symbols of a large
+    /// program.
+    pub timeout: Option<Duration>,
+    pub handler: H,
+}
+
+/// Why a request failed.
+#[derive(Debug, PartialEq, Eq)]
+pub enum RequestError {
+    /// GDB answered with `^error`. The field is the error message.
+    Gdb(String),
+    /// GDB didn't answer in time.
+    Timeout,
+    /// GDB disconnected before answering.
+    Cancelled,
+}
+
+/// Result of a request. `Ok` has the results in the `^done` (or `^running`, `^connected`) record.
+pub type Response = Result<HashMap<mi::Var, mi::Value>, RequestError>;
+
+impl<H> Requests<H> {
+    pub fn new() -> Requests<H> {
+        Requests {
+            next_token: AtomicU64::new(0),
+            pending: RefCell::new(HashMap::new()),
+            last_done: Cell::new(None),
+        }
+    }
+
+    /// Allocate a new token. Tokens are never reused.
+    pub fn next_token(&self) -> u64 {
+        self.next_token.fetch_add(1, Ordering::Relaxed)
+    }
+
+    /// Register a command sent with the given token.
+    pub fn add(&self, token: u64, command: String, timeout: Option<Duration>, handler: H) {
+        let request = Request {
+            command,
+            sent: Instant::now(),
+            timeout,
+            handler,
+        };
+        if self.pending.borrow_mut().insert(token, request).is_some() {
+            // Can't happen as tokens are not reused
+            println!("Token {} was already used", token);
+        }
+    }
+
+    /// Remove the request with the given token, to be called when its result arrives.
+    pub fn take(&self, token: u64) -> Option<Request<H>> {
+        let request = self.pending.borrow_mut().remove(&token);
+        if request.is_some() {
+            self.last_done.set(Some(Instant::now()));
+        }
+        request
+    }
+
+    /// Remove the oldest pending request if GDB has been running it for longer than its timeout.
+    /// Requests sent after it are waiting for it, so they don't time out.
+    pub fn take_timed_out(&self, now: Instant) -> Option<Request<H>> {
+        let mut pending = self.pending.borrow_mut();
+        let token = *pending.keys().min()?;
+        let request = &pending[&token];
+        let started = match self.last_done.get() {
+            Some(last_done) if last_done > request.sent => last_done,
+            _ => request.sent,
+        };
+        match request.timeout {
+            Some(timeout) if now.duration_since(started) >= timeout => {
+                self.last_done.set(Some(now));
+                pending.remove(&token)
+            }
+            _ => None,
+        }
+    }
+
+    /// Remove all requests, e.g. after GDB disconnects. Returned requests are sorted by tokens.
+    pub fn take_all(&self) -> Vec<Request<H>> {
+        let mut requests = self.pending.borrow_mut().drain().collect::<Vec<_>>();
+        requests.sort_by_key(|(token, _)| *token);
+        requests.into_iter().map(|(_, request)| request).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.borrow().is_empty()
+    }
+}
+
+/// Convert a result record to a `Response`.
+pub fn result_response(result: mi::Result) -> Response {
+    match result.class {
+        mi::ResultClass::Error => {
+            // ^error,msg="No symbol \"x\" in current context."
+            let msg = result
+                .results
+                .get("msg")
+                .and_then(|msg| msg.get_const_ref())
+                .unwrap_or("Unknown error");
+            Err(RequestError::Gdb(msg.to_string()))
+        }
+        mi::ResultClass::Done
+        | mi::ResultClass::Running
+        | mi::ResultClass::Connected
+        | mi::ResultClass::Exit => Ok(result.results),
+    }
+}
+
+#[test]
+fn requests_tests() {
+    let requests: Requests<u32> = Requests::new();
+    let t0 = requests.next_token();
+    let t1 = requests.next_token();
+    assert_ne!(t0, t1);
+    let timeout = Some(Duration::from_secs(10));
+    requests.add(t0, "-thread-info".to_string(), timeout, 0);
+    requests.add(t1, "-var-update --all-values *".to_string(), timeout, 1);
+    // Each token maps to its own handler
+    assert_eq!(requests.take(t1).map(|r| r.handler), Some(1));
+    assert_eq!(requests.take(t1).map(|r| r.handler), None);
+    assert_eq!(requests.take(t0).map(|r| r.handler), Some(0));
+    assert!(requests.is_empty());
+
+    // Requests time out one at a time, the second one's timeout starts when the first one times
+    // out
+    requests.add(requests.next_token(), "a".to_string(), timeout, 2);
+    requests.add(requests.next_token(), "b".to_string(), timeout, 3);
+    let now = Instant::now();
+    assert!(requests.take_timed_out(now).is_none());
+    assert_eq!(
+        requests
+            .take_timed_out(now + Duration::from_secs(10))
+            .map(|r| r.handler),
+        Some(2)
+    );
+    assert!(requests
+        .take_timed_out(now + Duration::from_secs(15))
+        .is_none());
+    assert_eq!(
+        requests
+            .take_timed_out(now + Duration::from_secs(20))
+            .map(|r| r.handler),
+        Some(3)
+    );
+    assert!(requests.is_empty());
+
+    // Requests without a timeout are never timed out, requests queued behind them wait
+    let slow = requests.next_token();
+    requests.add(slow, "-symbol-info-functions".to_string(), None, 6);
+    requests.add(
+        requests.next_token(),
+        "-thread-info".to_string(),
+        timeout,
+        7,
+    );
+    let now = Instant::now();
+    assert!(requests
+        .take_timed_out(now + Duration::from_secs(3600))
+        .is_none());
+    // The timeout of the queued request starts when the slow one is answered
+    assert_eq!(requests.take(slow).map(|r| r.handler), Some(6));
+    let now = Instant::now();
+    assert!(requests
+        .take_timed_out(now + Duration::from_secs(5))
+        .is_none());
+    assert_eq!(
+        requests
+            .take_timed_out(now + Duration::from_secs(10))
+            .map(|r| r.handler),
+        Some(7)
+    );
+
+    requests.add(requests.next_token(), "c".to_string(), timeout, 4);
+    requests.add(requests.next_token(), "d".to_string(), timeout, 5);
+    assert_eq!(
+        requests
+            .take_all()
+            .iter()
+            .map(|r| r.command.as_str())
+            .collect::<Vec<_>>(),
+        vec!["c", "d"]
+    );
+    assert!(requests.is_empty());
+}
+
+#[test]
+fn result_response_tests() {
+    let mut results = HashMap::new();
+    results.insert(
+        "msg".to_string(),
+        mi::Value::Const("No symbol \"x\" in current context.".to_string()),
+    );
+    let result = mi::Result {
+        token: Some(1),
+        class: mi::ResultClass::Error,
+        results,
+    };
+    assert_eq!(
+        result_response(result),
+        Err(RequestError::Gdb(
+            "No symbol \"x\" in current context.".to_string()
+        ))
+    );
+
+    let result = mi::Result {
+        token: Some(2),
+        class: mi::ResultClass::Done,
+        results: HashMap::new(),
+    };
+    assert_eq!(result_response(result), Ok(HashMap::new()));
+}
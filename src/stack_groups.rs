@@ -0,0 +1,192 @@
+//! Grouping threads by common call stack prefixes, for the "parallel stacks" view.
+//!
+//! Stacks are compared starting from the outermost frame. Threads that share a sequence of frames
+//! are grouped into a node, and where their stacks diverge the node gets a child node for each
+//! continuation. For example, with stacks (outermost frame first)
+//!
+//! ```text
+//! thread 1: clone, start_thread, worker, pthread_cond_wait
+//! thread 2: clone, start_thread, worker, pthread_cond_wait
+//! thread 3: clone, start_thread, worker, process
+//! ```
+//!
+//! the result is a node with frames `clone, start_thread, worker` and threads 1, 2, 3, with two
+//! children: `pthread_cond_wait` with threads 1, 2, and `process` with thread 3.
+
+use crate::types::{Backtrace, Frame};
+
+#[derive(Debug)]
+pub struct StackNode {
+    /// Frames shared by the threads, outermost frame first.
+    pub frames: Vec<Frame>,
+    /// Threads whose stacks start with frames of this node and its ancestors. Sorted.
+    pub threads: Vec<i32>,
+    /// Diverging continuations of the stacks, groups with more threads first.
+    pub children: Vec<StackNode>,
+}
+
+/// Group stacks of threads. Backtraces are as returned by GDB, i.e. innermost frame first.
+pub fn group_stacks(stacks: &[(i32, &Backtrace)]) -> Vec<StackNode> {
+    let stacks = stacks
+        .iter()
+        .map(|(thread_id, bt)| (*thread_id, bt.0.iter().rev().collect::<Vec<_>>()))
+        .collect::<Vec<_>>();
+    let stacks = stacks
+        .iter()
+        .map(|(thread_id, frames)| (*thread_id, frames.as_slice()))
+        .collect::<Vec<_>>();
+    group(&stacks, 0)
+}
+
+/// Two frames are considered the same when they're in the same function and the pc is the same.
+fn same_frame(f1: &Frame, f2: &Frame) -> bool {
+    f1.addr == f2.addr && f1.func == f2.func
+}
+
+/// Group stacks (outermost frame first) by their frames starting at `depth`. Frames before
+/// `depth` are the same in all stacks.
+fn group(stacks: &[(i32, &[&Frame])], depth: usize) -> Vec<StackNode> {
+    // Group by the frame at `depth`, in order of first appearance. Stacks that end at `depth` are
+    // not in any group.
+    let mut groups: Vec<Vec<(i32, &[&Frame])>> = vec![];
+    for &(thread_id, frames) in stacks {
+        let frame = match frames.get(depth) {
+            None => {
+                continue;
+            }
+            Some(frame) => frame,
+        };
+        match groups
+            .iter_mut()
+            .find(|group| same_frame(group[0].1[depth], frame))
+        {
+            None => groups.push(vec![(thread_id, frames)]),
+            Some(group) => group.push((thread_id, frames)),
+        }
+    }
+
+    let mut nodes = groups
+        .into_iter()
+        .map(|members| {
+            // Extend the node while all stacks in the group continue with the same frame
+            let mut end = depth + 1;
+            loop {
+                let first = match members[0].1.get(end) {
+                    None => break,
+                    Some(frame) => frame,
+                };
+                if members
+                    .iter()
+                    .all(|(_, frames)| frames.get(end).map_or(false, |f| same_frame(f, first)))
+                {
+                    end += 1;
+                } else {
+                    break;
+                }
+            }
+            let frames = members[0].1[depth..end]
+                .iter()
+                .map(|frame| (*frame).clone())
+                .collect();
+            let mut threads = members
+                .iter()
+                .map(|(thread_id, _)| *thread_id)
+                .collect::<Vec<_>>();
+            threads.sort();
+            StackNode {
+                frames,
+                threads,
+                children: group(&members, end),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    // Stable sort, so groups with same number of threads stay in order of first appearance
+    nodes.sort_by(|n1, n2| n2.threads.len().cmp(&n1.threads.len()));
+    nodes
+}
+
+#[cfg(test)]
+fn frame(func: &str, addr: &str) -> Frame {
+    Frame {
+        level: 0,
+        addr: addr.to_string(),
+        func: func.to_string(),
+        file: None,
+        fullname: None,
+        line: None,
+        from: None,
+    }
+}
+
+#[test]
+fn group_stacks_tests() {
+    // Innermost frame first, as in GDB backtraces
+    let bt = |frames: &[(&str, &str)]| {
+        Backtrace(
+            frames
+                .iter()
+                .map(|(func, addr)| frame(func, addr))
+                .collect(),
+        )
+    };
+    let waiting = || {
+        bt(&[
+            ("pthread_cond_wait", "0x10"),
+            ("worker", "0x20"),
+            ("start_thread", "0x30"),
+            ("clone", "0x40"),
+        ])
+    };
+    let stacks = vec![
+        (
+            1,
+            bt(&[
+                ("process", "0x50"),
+                ("worker", "0x21"),
+                ("start_thread", "0x30"),
+                ("clone", "0x40"),
+            ]),
+        ),
+        (2, waiting()),
+        (3, waiting()),
+        (4, bt(&[("main", "0x60")])),
+    ];
+
+    let stacks = stacks
+        .iter()
+        .map(|(thread_id, bt)| (*thread_id, bt))
+        .collect::<Vec<_>>();
+    let nodes = group_stacks(&stacks);
+    assert_eq!(nodes.len(), 2);
+
+    // Worker threads: common prefix is clone, start_thread
+    let workers = &nodes[0];
+    assert_eq!(workers.threads, vec![1, 2, 3]);
+    assert_eq!(
+        workers
+            .frames
+            .iter()
+            .map(|f| f.func.as_str())
+            .collect::<Vec<_>>(),
+        vec!["clone", "start_thread"]
+    );
+    // `worker` frames are different as the pcs are different
+    assert_eq!(workers.children.len(), 2);
+    assert_eq!(workers.children[0].threads, vec![2, 3]);
+    assert_eq!(
+        workers.children[0]
+            .frames
+            .iter()
+            .map(|f| f.func.as_str())
+            .collect::<Vec<_>>(),
+        vec!["worker", "pthread_cond_wait"]
+    );
+    assert!(workers.children[0].children.is_empty());
+    assert_eq!(workers.children[1].threads, vec![1]);
+
+    let main = &nodes[1];
+    assert_eq!(main.threads, vec![4]);
+    assert_eq!(main.frames.len(), 1);
+    assert!(main.children.is_empty());
+}
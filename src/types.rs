@@ -16,9 +16,14 @@ pub struct Breakpoint {
 
     pub enabled: bool,
 
-    /// Memory location at which the breakpoint is set.
+    /// Memory location at which the breakpoint is set. Empty for catchpoints, "<PENDING>" for
+    /// pending breakpoints.
     pub address: String,
 
+    /// Whether the location couldn't be resolved yet, e.g. because it's in a library that's not
+    /// loaded yet. GDB resolves it when a library is loaded.
+    pub pending: bool,
+
     /*
         Sigh ... gdb-mi documentation is out of date
         /// Logical location of the breakpoint, expressed by function name, file name, line number.
@@ -28,6 +33,8 @@ pub struct Breakpoint {
     /// Function name
     pub func: String,
     */
+    /// Location as given when the breakpoint was created. For catchpoints this is a description
+    /// of what's caught, e.g. "exception throw".
     pub original_location: String,
 
     /// File name
@@ -42,9 +49,59 @@ pub struct Breakpoint {
     /// Condition
     pub cond: Option<String>,
 
+    /// Global id of the thread the breakpoint is specific to.
+    pub thread: Option<i32>,
+
+    /// Number of hits to ignore before stopping
+    pub ignore_count: u32,
+
     // TODO thread-groups?
     /// Number of times the breakpoint has been hit
     pub hits: u32,
+
+    /// Commands to run when the breakpoint is hit. For dprintf breakpoints this is the `printf`
+    /// command.
+    pub script: Vec<String>,
+}
+
+impl Breakpoint {
+    /// Format string and arguments of a dprintf breakpoint, e.g. `"x = %d\n",x`.
+    pub fn dprintf_format(&self) -> Option<&str> {
+        if self.type_ != BreakpointType::Dprintf {
+            return None;
+        }
+        let command = self.script.first()?;
+        // Depending on `dprintf-style` the command is `printf ...`, `call (void) printf (...)` or
+        // `agent-printf ...`. Only strip the default one.
+        if command.starts_with("printf ") {
+            Some(command["printf ".len()..].trim_start())
+        } else {
+            Some(command)
+        }
+    }
+}
+
+/// A breakpoint to insert, as entered in the "add breakpoint" form or read from a script.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct BreakpointSpec {
+    pub location: String,
+    /// Empty when the breakpoint is unconditional
+    pub condition: String,
+    /// Format string (contents of a C string literal, e.g. `x = %d\n`) and comma-separated
+    /// arguments of a dynamic printf. `None` for normal breakpoints.
+    pub dprintf: Option<(String, String)>,
+    /// Delete the breakpoint when hit
+    pub temporary: bool,
+    /// Use a hardware breakpoint. Not supported for dprintfs.
+    pub hardware: bool,
+    /// Create a pending breakpoint if the location can't be resolved
+    pub pending: bool,
+    /// Create the breakpoint disabled
+    pub disabled: bool,
+    /// Only stop in this thread (global thread id)
+    pub thread: Option<i32>,
+    /// Number of hits to ignore
+    pub ignore_count: Option<u32>,
 }
 
 // NOTE: GDB has more details like whether the watchpoint is hardware or not. We ignore those for
@@ -52,14 +109,46 @@ pub struct Breakpoint {
 #[derive(Debug, PartialEq, Eq)]
 pub enum BreakpointType {
     Breakpoint,
+    /// Breakpoint using a hardware debug register, e.g. for code in ROM.
+    HardwareBreakpoint,
     #[allow(dead_code)]
     Watchpoint,
+    /// Dynamic printf: prints and continues, see `-dprintf-insert`.
+    Dprintf,
+    /// Stops on an event rather than at a location, see "Setting Catchpoints" in gdb manual.
+    Catchpoint(CatchpointType),
+}
+
+/// What a catchpoint catches. Values of `catch-type` field of catchpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchpointType {
+    /// C++ (or Rust panic) exception thrown.
+    Throw,
+    /// C++ exception caught.
+    Catch,
+    /// C++ exception rethrown.
+    Rethrow,
+    Syscall,
+    Fork,
+    Vfork,
+    Exec,
+    /// Shared library loaded.
+    Load,
+    /// Shared library unloaded.
+    Unload,
+    Signal,
+    /// A catchpoint created in the console that we don't know about, e.g. Ada exceptions.
+    Other,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum BreakpointDisposition {
     Keep,
     NoKeep,
+    /// Deleted when hit (temporary breakpoints)
+    Delete,
+    /// Disabled when hit (`enable once`)
+    Disable,
 }
 
 //
@@ -74,7 +163,7 @@ pub struct Watchpoint {
     pub type_: WatchpointType,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum WatchpointType {
     ReadWrite,
     Read,
@@ -87,7 +176,7 @@ pub enum WatchpointType {
 
 pub struct Backtrace(pub Vec<Frame>);
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Frame {
     pub level: usize,
 
@@ -111,6 +200,192 @@ pub struct Frame {
     pub from: Option<String>,
 }
 
+//
+// Thread stuff
+//
+
+#[derive(Debug, Clone)]
+pub struct Thread {
+    /// Thread id assigned by GDB.
+    pub id: i32,
+
+    /// Target-specific thread id, e.g. "Thread 0x7ffff7fbdb80 (LWP 19785)".
+    pub target_id: String,
+
+    /// Name of the thread (e.g. set with `pthread_setname_np`), if available.
+    pub name: Option<String>,
+
+    pub state: ThreadState,
+
+    /// The processor core the thread was last seen on, if available.
+    pub core: Option<u32>,
+
+    /// Top frame of the thread. Not available for running threads.
+    pub frame: Option<Frame>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadState {
+    Stopped,
+    Running,
+}
+
+/// Execution commands that can be sent for a single thread in non-stop mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThreadAction {
+    Continue,
+    Interrupt,
+    Step,
+    Next,
+}
+
+//
+// Inferior stuff
+//
+
+/// An inferior (a "thread group" in gdb-mi), i.e. a process being debugged or to be debugged.
+/// Forked processes are added as new inferiors when GDB follows or keeps them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inferior {
+    /// Thread group id assigned by GDB, e.g. "i1".
+    pub id: String,
+
+    /// Process id. Only available while the process is running.
+    pub pid: Option<u32>,
+
+    /// Path of the executable, if loaded.
+    pub executable: Option<String>,
+
+    /// Exit code of the process, if it exited.
+    pub exit_code: Option<u32>,
+}
+
+impl Inferior {
+    pub fn new(id: String) -> Inferior {
+        Inferior {
+            id,
+            pid: None,
+            executable: None,
+            exit_code: None,
+        }
+    }
+}
+
+//
+// Library stuff
+//
+
+/// A shared library loaded by an inferior.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Library {
+    /// Identifier of the library, usually the same as `target_name`.
+    pub id: String,
+
+    /// Name of the library on the target, e.g. "/lib/x86_64-linux-gnu/libc.so.6".
+    pub target_name: String,
+
+    /// Name of the library on the host. Different from `target_name` when debugging remotely.
+    pub host_name: String,
+
+    /// Whether debug symbols of the library are loaded.
+    pub symbols_loaded: bool,
+
+    /// Thread group (inferior) the library is loaded in. Not available when the library is loaded
+    /// in all inferiors.
+    pub thread_group: Option<String>,
+
+    /// Address ranges of the library's sections, as (from, to) pairs.
+    pub ranges: Vec<(String, String)>,
+}
+
+//
+// Signal stuff
+//
+
+/// How GDB handles a signal, as shown by `info signals` and changed with `handle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signal {
+    /// e.g. "SIGSEGV"
+    pub name: String,
+
+    /// Whether GDB stops the program when the signal happens.
+    pub stop: bool,
+
+    /// Whether GDB prints a message when the signal happens.
+    pub print: bool,
+
+    /// Whether GDB passes the signal to the program.
+    pub pass: bool,
+
+    /// e.g. "Segmentation fault"
+    pub description: String,
+}
+
+//
+// Symbol stuff
+//
+
+/// What to search with `-symbol-info-*` commands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    Variable,
+    Type,
+}
+
+/// Result of a `-symbol-info-functions`, `-symbol-info-variables` or `-symbol-info-types`
+/// command.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SymbolInfo {
+    /// Symbols with debug info, grouped by the file they're defined in.
+    pub debug: Vec<SymbolFile>,
+
+    /// Symbols without debug info, e.g. functions of libraries without debug symbols. Only
+    /// returned when asked with `--include-nondebug`.
+    pub nondebug: Vec<NondebugSymbol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolFile {
+    /// File name as given to the compiler, e.g. "test.c".
+    pub filename: String,
+
+    /// Absolute path of the file. Not available when GDB can't find the file.
+    pub fullname: Option<String>,
+
+    pub symbols: Vec<Symbol>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+
+    /// Line of the definition. Not available for some types, e.g. "int".
+    pub line: Option<u32>,
+
+    /// Type of functions and variables, e.g. "int (int, char **)".
+    pub type_: Option<String>,
+
+    /// Declaration of functions and variables, e.g. "static int counter;".
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NondebugSymbol {
+    pub name: String,
+    pub address: String,
+}
+
+/// A source file of the program, as listed by `-file-list-exec-source-files`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFile {
+    /// File name as given to the compiler, e.g. "src/test.c".
+    pub file: String,
+
+    /// Absolute path of the file. Not available when GDB can't find the file.
+    pub fullname: Option<String>,
+}
+
 //
 // Value/expression stuff
 //
@@ -121,7 +396,74 @@ pub struct Value {
     pub value: String,
     pub name: String,
     pub type_: String,
+    /// Number of children. For dynamic varobjs this is the number of children fetched so far,
+    /// check `has_more` to see if there are more.
     pub n_children: usize,
+    /// Whether the varobj is implemented by a pretty-printer.
+    pub dynamic: bool,
+    /// For dynamic varobjs: whether there are more children available.
+    pub has_more: bool,
+    /// Display hint of the pretty-printer.
+    pub display_hint: Option<DisplayHint>,
+}
+
+/// Display hint of a pretty-printer, see "Pretty Printing API" in gdb manual.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayHint {
+    /// Children should be rendered as an array.
+    Array,
+    /// Children are key/value pairs: first child is a key, second is the value, and so on.
+    Map,
+    /// Value should be rendered as a string.
+    String,
+}
+
+/// An entry in the `changelist` of a `-var-update` result.
+#[derive(Debug)]
+pub struct VarChange {
+    /// Name of the varobj, e.g. "var1.x".
+    pub name: String,
+
+    /// New value. Not available when the varobj is out of scope.
+    pub value: Option<String>,
+
+    pub in_scope: VarScope,
+
+    /// Whether the type of the varobj changed. When this is the case children of the varobj
+    /// should be fetched again.
+    pub type_changed: bool,
+
+    /// Only available when `type_changed` is true.
+    pub new_type: Option<String>,
+
+    /// Only available when number of children changed.
+    pub new_num_children: Option<usize>,
+
+    /// Whether the varobj is implemented by a pretty-printer. Children of dynamic varobjs may
+    /// change without a type change.
+    pub dynamic: bool,
+
+    /// For dynamic varobjs: whether there are more children available.
+    pub has_more: bool,
+}
+
+/// Display format of a varobj, set with `-var-set-format`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarFormat {
+    Natural,
+    Hexadecimal,
+    Binary,
+    Octal,
+    Decimal,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarScope {
+    InScope,
+    OutOfScope,
+    /// The varobj is no longer valid (e.g. the program was re-compiled and loaded), it should be
+    /// deleted.
+    Invalid,
 }
 
 //
@@ -138,3 +480,185 @@ pub struct AsmInst {
     pub inst: String,
     pub address: String,
 }
+
+//
+// Event stuff
+//
+
+/// Asynchronous notifications from GDB (`*` and `=` records), see "GDB/MI Async Records" in gdb
+/// manual.
+#[derive(Debug)]
+pub enum Event {
+    /// Threads stopped.
+    Stopped(StopInfo),
+
+    /// Threads resumed. `None` means all threads.
+    Running {
+        thread_ids: Option<Vec<i32>>,
+    },
+
+    ThreadCreated {
+        id: i32,
+        group_id: String,
+    },
+
+    ThreadExited {
+        id: i32,
+        group_id: String,
+    },
+
+    /// Thread selected, e.g. with "thread N" in the console.
+    ThreadSelected {
+        id: i32,
+    },
+
+    ThreadGroupAdded {
+        id: String,
+    },
+
+    ThreadGroupRemoved {
+        id: String,
+    },
+
+    /// A process was started or attached to.
+    ThreadGroupStarted {
+        id: String,
+        pid: Option<u32>,
+    },
+
+    ThreadGroupExited {
+        id: String,
+        exit_code: Option<u32>,
+    },
+
+    LibraryLoaded(Library),
+
+    /// The library as it was loaded. Only `id`, `target_name`, `host_name` and `thread_group` are
+    /// available.
+    LibraryUnloaded(Library),
+
+    BreakpointCreated(Breakpoint),
+
+    BreakpointModified(Breakpoint),
+
+    BreakpointDeleted {
+        id: u32,
+    },
+
+    /// A setting was changed with "set", e.g. "set follow-fork-mode child".
+    CmdParamChanged {
+        param: String,
+        value: String,
+    },
+
+    /// Memory of an inferior was changed by the user, e.g. with "set var".
+    MemoryChanged {
+        thread_group: String,
+        addr: String,
+        len: String,
+        /// Whether the changed memory is in a code section.
+        code: bool,
+    },
+}
+
+/// Fields of a `*stopped` record.
+#[derive(Debug)]
+pub struct StopInfo {
+    /// Not available e.g. after attaching to a process.
+    pub reason: Option<StopReason>,
+
+    /// The thread that caused the stop.
+    pub thread_id: Option<i32>,
+
+    /// Threads that stopped. `None` means all threads.
+    pub stopped_threads: Option<Vec<i32>>,
+
+    /// Frame the thread stopped at. Not available when the process exited.
+    pub frame: Option<Frame>,
+
+    /// The processor core the thread stopped on, if available.
+    pub core: Option<u32>,
+}
+
+/// Why execution stopped. Values of `reason` field in `*stopped` records.
+#[derive(Debug, PartialEq, Eq)]
+pub enum StopReason {
+    BreakpointHit {
+        number: u32,
+    },
+
+    /// A watchpoint was triggered. For read watchpoints only `value` is available, for write
+    /// watchpoints both `old_value` and `value` are available. Access watchpoints have either
+    /// both or only `value` depending on whether the value changed.
+    WatchpointTrigger {
+        number: u32,
+        type_: WatchpointType,
+        expr: String,
+        old_value: Option<String>,
+        value: Option<String>,
+    },
+
+    /// The frame of a watchpoint's expression went out of scope, the watchpoint is deleted.
+    WatchpointScope {
+        number: u32,
+    },
+
+    /// "finish" returned. Return value is not available for void functions.
+    FunctionFinished {
+        return_value: Option<String>,
+    },
+
+    /// "until" or "advance" reached the location.
+    LocationReached,
+
+    /// Stepping finished.
+    EndSteppingRange,
+
+    SignalReceived {
+        name: String,
+        meaning: String,
+    },
+
+    /// The process exited with non-zero exit code.
+    Exited {
+        exit_code: u32,
+    },
+
+    ExitedNormally,
+
+    /// The process was killed by a signal.
+    ExitedSignalled {
+        name: String,
+        meaning: String,
+    },
+
+    Fork {
+        new_pid: u32,
+    },
+
+    Vfork {
+        new_pid: u32,
+    },
+
+    /// The process called exec.
+    Exec {
+        new_exec: String,
+    },
+
+    SyscallEntry {
+        name: String,
+    },
+
+    SyscallReturn {
+        name: String,
+    },
+
+    /// A shared library was loaded or unloaded (with "stop-on-solib-events").
+    SolibEvent,
+
+    /// Reverse execution reached the end of the recorded history.
+    NoHistory,
+
+    /// A reason we don't know about.
+    Other(String),
+}
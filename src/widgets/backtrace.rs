@@ -2,7 +2,7 @@
 
 use gtk::prelude::*;
 
-use crate::types::Backtrace;
+use crate::types::{Backtrace, Frame};
 
 pub struct BacktraceW {
     model: gtk::ListStore,
@@ -19,18 +19,21 @@ enum Cols {
     Func,
     // e.g. "rts/RtsMessages.c:186"
     Loc,
+    // Whether the frame changed since the last update. Changed frames are highlighted.
+    Changed,
 }
 
 /// Column types for the list store
-static COL_TYPES: [gtk::Type; 4] = [
+static COL_TYPES: [gtk::Type; 5] = [
     gtk::Type::String, // level
     gtk::Type::String, // address
     gtk::Type::String, // function
     gtk::Type::String, // location
+    gtk::Type::Bool,   // changed
 ];
 
 /// Column indices for when inserting rows into the list store
-static COL_INDICES: [u32; 4] = [0, 1, 2, 3];
+static COL_INDICES: [u32; 5] = [0, 1, 2, 3, 4];
 
 impl BacktraceW {
     pub fn new(bt: &Backtrace) -> BacktraceW {
@@ -55,6 +58,8 @@ impl BacktraceW {
             column.pack_start(&renderer, true);
             column.set_title(title);
             column.add_attribute(&renderer, "text", col_ty as i32);
+            renderer.set_property_foreground(Some("red"));
+            column.add_attribute(&renderer, "foreground-set", Cols::Changed as i32);
             if selectable {
                 // We don't want to allow editing but we want to allow copying the contents, so we
                 // enable editing, but we don't update the text in "edited" callback.
@@ -115,17 +120,92 @@ impl BacktraceW {
         self.clear();
 
         for frame in &bt.0 {
-            let file_line = match (&frame.file, &frame.line) {
-                (Some(file), Some(line)) => format!("{}:{}", file, line),
-                _ => "".to_string(),
+            set_frame(&self.model, &self.model.append(), frame, false);
+        }
+    }
+
+    /// Render the given backtrace, updating the existing rows in place (so that selection is
+    /// preserved). Frames that are different than the ones in the previous backtrace are
+    /// highlighted. Frames are compared starting from the outermost frame, so when a function
+    /// returns only the frames above it are highlighted.
+    ///
+    /// `depth_change` is the difference between the new and old stack depths. When only the top
+    /// frames of the stacks are rendered this is needed to find corresponding frames in the old
+    /// backtrace. When not available the backtraces are assumed to be complete.
+    pub fn update_bt(&self, bt: &Backtrace, depth_change: Option<isize>) {
+        // Collect (address, function) of the old frames
+        let mut old_frames: Vec<(String, String)> = vec![];
+        if let Some(iter) = self.model.get_iter_first() {
+            loop {
+                let addr = self
+                    .model
+                    .get_value(&iter, Cols::Addr as i32)
+                    .get::<String>()
+                    .unwrap();
+                let func = self
+                    .model
+                    .get_value(&iter, Cols::Func as i32)
+                    .get::<String>()
+                    .unwrap();
+                old_frames.push((addr, func));
+                if !self.model.iter_next(&iter) {
+                    break;
+                }
+            }
+        }
+
+        let depth_change = depth_change.unwrap_or(bt.0.len() as isize - old_frames.len() as isize);
+
+        let mut iter = self.model.get_iter_first();
+        for (frame_idx, frame) in bt.0.iter().enumerate() {
+            // Index of the corresponding frame in the old backtrace, counting from the bottom
+            let old_idx = frame_idx as isize - depth_change;
+            let changed = if old_idx < 0 || old_idx as usize >= old_frames.len() {
+                true
+            } else {
+                let (ref old_addr, ref old_func) = old_frames[old_idx as usize];
+                *old_addr != frame.addr || *old_func != frame.func
             };
-            let values: [&dyn gtk::ToValue; 4] = [
-                &format!("#{}", frame.level),
-                &frame.addr,
-                &frame.func,
-                &file_line,
-            ];
-            self.model.set(&self.model.append(), &COL_INDICES, &values);
+            match iter {
+                Some(ref iter_) => {
+                    set_frame(&self.model, iter_, frame, changed);
+                    if !self.model.iter_next(iter_) {
+                        iter = None;
+                    }
+                }
+                None => {
+                    set_frame(&self.model, &self.model.append(), frame, changed);
+                }
+            }
+        }
+
+        // Remove the rows of old frames that don't exist anymore
+        if let Some(iter) = iter {
+            while self.model.remove(&iter) {}
         }
     }
+
+    /// Add more frames to the end of the backtrace.
+    pub fn append_bt(&self, bt: &Backtrace) {
+        for frame in &bt.0 {
+            set_frame(&self.model, &self.model.append(), frame, false);
+        }
+    }
+}
+
+fn set_frame(model: &gtk::ListStore, iter: &gtk::TreeIter, frame: &Frame, changed: bool) {
+    let file_line = match (&frame.file, &frame.line, &frame.from) {
+        (Some(file), Some(line), _) => format!("{}:{}", file, line),
+        // Shared library of the function, when there's no debug info
+        (_, _, Some(from)) => from.clone(),
+        _ => "".to_string(),
+    };
+    let values: [&dyn gtk::ToValue; 5] = [
+        &format!("#{}", frame.level),
+        &frame.addr,
+        &frame.func,
+        &file_line,
+        &changed,
+    ];
+    model.set(iter, &COL_INDICES, &values);
 }
@@ -1,17 +1,38 @@
-//! A button for adding new breakpoints. When clicked it turns into two entries for location and
-//! condition of the breakpoint. When submitted it turns back into a "add breakpoint" button.
+//! A button for adding new breakpoints. When clicked it turns into entries for location and
+//! condition of the breakpoint, and format and arguments for dynamic printfs (breakpoints that
+//! print instead of stopping), and options like temporary/hardware/thread-specific breakpoints.
+//! When submitted the form stays open until the breakpoint is created (`reset`), errors (e.g. an
+//! unknown function or a condition that doesn't parse) are shown next to the entries
+//! (`show_error`) so that the input can be fixed.
 
 use gtk::prelude::*;
 
 use std::cell::RefCell;
 use std::rc::Rc;
 
+use crate::types::BreakpointSpec;
+
 /// Type of a "breakpoint added" callback. Rc<RefCell<...>> becuase it's shared by entry "activate"
 /// signal callbacks and the widget (to be able to set it after initializing all widgets).
-type BreakpointAddCb = Rc<RefCell<Option<Box<Fn(String, String)>>>>;
+type BreakpointAddCb = Rc<RefCell<Option<Box<Fn(BreakpointSpec)>>>>;
+
+/// Entries of the form, in order of the rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakpointField {
+    Location = 0,
+    Condition,
+    Format,
+    Arguments,
+    Thread,
+    Ignore,
+}
 
 pub struct BreakpointAddW {
     button: gtk::Button,
+    grid: gtk::Grid,
+    entries: [gtk::Entry; 6],
+    checks: [gtk::CheckButton; 4],
+    error_label: gtk::Label,
     cb: BreakpointAddCb,
 }
 
@@ -31,18 +52,69 @@ impl BreakpointAddW {
         //
 
         // grid -> [ [ location label, location entry],
-        //           [ condition label, condition entry ] ]
+        //           [ condition label, condition entry ],
+        //           [ format label, format entry ],
+        //           [ arguments label, arguments entry ],
+        //           [ thread label, thread entry ],
+        //           [ ignore label, ignore entry ],
+        //           [ box -> [ check buttons ] ],
+        //           [ error label ] ]
         let grid = gtk::Grid::new();
         let location_label = gtk::Label::new("Location");
         let condition_label = gtk::Label::new("Condition");
+        let format_label = gtk::Label::new("Format");
+        let args_label = gtk::Label::new("Arguments");
         let location_entry = gtk::Entry::new();
         location_entry.set_hexpand(true);
         let condition_entry = gtk::Entry::new();
         condition_entry.set_hexpand(true);
+        let format_entry = gtk::Entry::new();
+        format_entry.set_hexpand(true);
+        format_entry.set_placeholder_text("Print instead of stopping, e.g. x = %d\\n");
+        let args_entry = gtk::Entry::new();
+        args_entry.set_hexpand(true);
+        args_entry.set_placeholder_text("e.g. x");
+        let thread_label = gtk::Label::new("Thread");
+        let thread_entry = gtk::Entry::new();
+        thread_entry.set_hexpand(true);
+        thread_entry.set_placeholder_text("All threads");
+        let ignore_label = gtk::Label::new("Ignore");
+        let ignore_entry = gtk::Entry::new();
+        ignore_entry.set_hexpand(true);
+        ignore_entry.set_placeholder_text("Number of hits to ignore");
+        let options_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let temporary_check = gtk::CheckButton::new_with_label("Temporary");
+        temporary_check.set_tooltip_text("Delete the breakpoint when it's hit");
+        let hardware_check = gtk::CheckButton::new_with_label("Hardware");
+        hardware_check.set_tooltip_text("Use a hardware breakpoint");
+        let pending_check = gtk::CheckButton::new_with_label("Pending");
+        pending_check.set_tooltip_text(
+            "Create the breakpoint even if the location is not found, e.g. because it's in a \
+             library that's not loaded yet",
+        );
+        let disabled_check = gtk::CheckButton::new_with_label("Disabled");
+        options_box.pack_start(&temporary_check, false, false, 0);
+        options_box.pack_start(&hardware_check, false, false, 0);
+        options_box.pack_start(&pending_check, false, false, 0);
+        options_box.pack_start(&disabled_check, false, false, 0);
         grid.attach(&location_label, 0, 0, 1, 1);
         grid.attach(&location_entry, 1, 0, 1, 1);
         grid.attach(&condition_label, 0, 1, 1, 1);
         grid.attach(&condition_entry, 1, 1, 1, 1);
+        grid.attach(&format_label, 0, 2, 1, 1);
+        grid.attach(&format_entry, 1, 2, 1, 1);
+        grid.attach(&args_label, 0, 3, 1, 1);
+        grid.attach(&args_entry, 1, 3, 1, 1);
+        grid.attach(&thread_label, 0, 4, 1, 1);
+        grid.attach(&thread_entry, 1, 4, 1, 1);
+        grid.attach(&ignore_label, 0, 5, 1, 1);
+        grid.attach(&ignore_entry, 1, 5, 1, 1);
+        grid.attach(&options_box, 0, 6, 2, 1);
+        let error_label = gtk::Label::new(None);
+        error_label.set_halign(gtk::Align::Start);
+        error_label.set_line_wrap(true);
+        error_label.set_selectable(true);
+        grid.attach(&error_label, 0, 7, 2, 1);
 
         //
         // The callback cell
@@ -69,68 +141,99 @@ impl BreakpointAddW {
             location_entry_clone.grab_focus();
         });
 
-        // TODO: Remove duplication
-        // Entry submitted -> call the callback, remove the grid, add button
-        let condition_entry_clone = condition_entry.clone();
-        let cb_clone = cb.clone();
-        let grid_clone = grid.clone();
-        let button_clone = button.clone();
-        location_entry.connect_activate(move |w| {
-            if let Some(location_str) = w.get_text() {
-                if location_str.as_str().is_empty() {
+        // Any entry submitted -> call the callback, remove the grid, add button
+        let entries = [
+            location_entry,
+            condition_entry,
+            format_entry,
+            args_entry,
+            thread_entry,
+            ignore_entry,
+        ];
+        let checks = [
+            temporary_check,
+            hardware_check,
+            pending_check,
+            disabled_check,
+        ];
+        let submit = {
+            let entries = entries.clone();
+            let checks = checks.clone();
+            let error_label = error_label.clone();
+            let cb = cb.clone();
+            move || {
+                let texts = entries
+                    .iter()
+                    .map(|entry| {
+                        entry
+                            .get_text()
+                            .map(|s| s.as_str().trim().to_string())
+                            .unwrap_or_else(|| "".to_string())
+                    })
+                    .collect::<Vec<_>>();
+                if texts[0].is_empty() {
                     return;
                 }
-                let location_str = location_str.as_str().to_string();
-                let condition_str = condition_entry_clone
-                    .get_text()
-                    .map(|s| s.as_str().to_string())
-                    .unwrap_or_else(|| "".to_string());
-                w.set_text("");
-                condition_entry_clone.set_text("");
-                if let Some(ref cb) = *cb_clone.borrow() {
-                    cb(location_str, condition_str);
+                clear_errors(&entries, &error_label);
+                let thread = match parse_opt_number::<i32>(&texts[4]) {
+                    Ok(thread) => thread,
+                    Err(()) => {
+                        show_error(
+                            &entries,
+                            &error_label,
+                            BreakpointField::Thread,
+                            "Not a thread number",
+                        );
+                        return;
+                    }
+                };
+                let ignore_count = match parse_opt_number::<u32>(&texts[5]) {
+                    Ok(ignore_count) => ignore_count,
+                    Err(()) => {
+                        show_error(
+                            &entries,
+                            &error_label,
+                            BreakpointField::Ignore,
+                            "Not a number of hits",
+                        );
+                        return;
+                    }
+                };
+                let spec = BreakpointSpec {
+                    location: texts[0].clone(),
+                    condition: texts[1].clone(),
+                    dprintf: if texts[2].is_empty() {
+                        None
+                    } else {
+                        Some((texts[2].clone(), texts[3].clone()))
+                    },
+                    temporary: checks[0].get_active(),
+                    hardware: checks[1].get_active(),
+                    pending: checks[2].get_active(),
+                    disabled: checks[3].get_active(),
+                    thread,
+                    ignore_count,
+                };
+                // The form is reset when the breakpoint is created
+                if let Some(ref cb) = *cb.borrow() {
+                    cb(spec);
                 }
-                // Remove the grid from the parent
-                let parent = w.get_ancestor(gtk::Box::static_type()).unwrap();
-                let box_ = parent.downcast_ref::<gtk::Box>().unwrap();
-                box_.remove(&grid_clone);
-                // Add button
-                box_.pack_end(&button_clone, false, false, 0);
-                box_.show_all();
             }
-        });
-
-        // Same stuff for condition entry
-        let location_entry_clone = location_entry.clone();
-        let cb_clone = cb.clone();
-        let grid_clone = grid.clone();
-        let button_clone = button.clone();
-        condition_entry.connect_activate(move |w| {
-            if let Some(location_str) = location_entry_clone.get_text() {
-                if location_str.as_str().is_empty() {
-                    return;
-                }
-                let location_str = location_str.as_str().to_string();
-                let condition_str = w
-                    .get_text()
-                    .map(|s| s.as_str().to_string())
-                    .unwrap_or_else(|| "".to_string());
-                location_entry_clone.set_text("");
-                w.set_text("");
-                if let Some(ref cb) = *cb_clone.borrow() {
-                    cb(location_str, condition_str);
-                }
-                // Remove the grid from the box
-                let parent = w.get_ancestor(gtk::Box::static_type()).unwrap();
-                let box_ = parent.downcast_ref::<gtk::Box>().unwrap();
-                box_.remove(&grid_clone);
-                // Add button
-                box_.pack_end(&button_clone, false, false, 0);
-                box_.show_all();
-            }
-        });
+        };
+        let submit = Rc::new(submit);
+        for entry in entries.iter() {
+            let submit = submit.clone();
+            entry.connect_activate(move |_| submit());
+        }
 
-        BreakpointAddW { button, cb }
+        BreakpointAddW {
+            button,
+            grid,
+            entries,
+            checks,
+            error_label,
+            cb,
+        }
     }
 
     pub fn get_widget(&self) -> &gtk::Widget {
@@ -138,8 +241,72 @@ impl BreakpointAddW {
         self.button.upcast_ref()
     }
 
-    /// Set "breakpoint added" callback. Arguments are: location, condition.
-    pub fn connect_breakpoint_added(&self, cb: Box<Fn(String, String)>) {
+    /// Set "breakpoint added" callback. The callback should call `reset` when the breakpoint is
+    /// created, or `show_error` when it can't be created.
+    pub fn connect_breakpoint_added(&self, cb: Box<Fn(BreakpointSpec)>) {
         *self.cb.borrow_mut() = Some(cb);
     }
+
+    /// Show an error next to an entry of the form and move the focus to the entry. The input is
+    /// kept.
+    pub fn show_error(&self, field: BreakpointField, msg: &str) {
+        show_error(&self.entries, &self.error_label, field, msg);
+    }
+
+    /// Clear the form and turn it back into the "add breakpoint" button.
+    pub fn reset(&self) {
+        clear_errors(&self.entries, &self.error_label);
+        for entry in self.entries.iter() {
+            entry.set_text("");
+        }
+        for check in self.checks.iter() {
+            check.set_active(false);
+        }
+        // Remove the grid from the parent. The form may be already closed if we're called for an
+        // old submission.
+        let parent = match self.grid.get_parent() {
+            None => {
+                return;
+            }
+            Some(parent) => parent,
+        };
+        let box_ = parent.downcast_ref::<gtk::Box>().unwrap();
+        box_.remove(&self.grid);
+        // Add button
+        box_.pack_end(&self.button, false, false, 0);
+        box_.show_all();
+    }
+}
+
+fn show_error(
+    entries: &[gtk::Entry; 6],
+    error_label: &gtk::Label,
+    field: BreakpointField,
+    msg: &str,
+) {
+    let entry = &entries[field as usize];
+    entry.set_icon_from_icon_name(gtk::EntryIconPosition::Secondary, Some("dialog-error"));
+    entry.set_icon_tooltip_text(gtk::EntryIconPosition::Secondary, Some(msg));
+    error_label.set_markup(&format!(
+        "<span foreground=\"red\">{}</span>",
+        glib::markup_escape_text(msg)
+    ));
+    entry.grab_focus();
+}
+
+fn clear_errors(entries: &[gtk::Entry; 6], error_label: &gtk::Label) {
+    for entry in entries.iter() {
+        entry.set_icon_from_icon_name(gtk::EntryIconPosition::Secondary, None);
+        entry.set_icon_tooltip_text(gtk::EntryIconPosition::Secondary, None);
+    }
+    error_label.set_text("");
+}
+
+/// Parse an optional number entry. Empty entry is `Ok(None)`.
+fn parse_opt_number<N: std::str::FromStr>(text: &str) -> Result<Option<N>, ()> {
+    if text.is_empty() {
+        Ok(None)
+    } else {
+        text.parse::<N>().map(Some).map_err(|_| ())
+    }
 }
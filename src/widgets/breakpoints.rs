@@ -1,23 +1,66 @@
 //! A `TreeView` for rendering breakpoints.
+//!
+//! The context menu of a breakpoint opens an editor for the commands GDB runs when the breakpoint
+//! is hit (e.g. "print x", "bt 3", "continue"). Breakpoints can be exported to and imported from
+//! GDB scripts (see `breakpoint_script`).
+//!
+//! Multiple breakpoints can be selected to enable, disable or delete them at once. Breakpoints can
+//! be put in named groups (see `breakpoint_groups`) to enable or disable them together.
+
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 
 use gtk::prelude::*;
 
-use crate::types::Breakpoint;
+use crate::breakpoint_groups::{breakpoint_key, BreakpointGroups};
+use crate::types::{
+    Breakpoint, BreakpointDisposition, BreakpointSpec, BreakpointType, CatchpointType, Event,
+};
 use crate::widgets::breakpoint_add::BreakpointAddW;
+pub use crate::widgets::breakpoint_add::BreakpointField;
+use crate::widgets::catchpoint_add::CatchpointAddW;
+
+/// Type of the reference for the "breakpoint commands" callback.
+type CommandsCb = Rc<RefCell<Option<Box<Fn(u32, Vec<String>)>>>>;
+
+/// Type of the reference for the "import" and "export" callbacks.
+type FileCb = Rc<RefCell<Option<Box<Fn(&Path)>>>>;
+
+/// Type of the reference for the "breakpoints enabled" callback.
+type BulkEnableCb = Rc<RefCell<Option<Box<Fn(&[u32], bool)>>>>;
+
+/// Type of the reference for the "breakpoints deleted" callback.
+type BulkDeleteCb = Rc<RefCell<Option<Box<Fn(&[u32])>>>>;
+
+/// Type of the reference for the "groups changed" callback.
+type GroupsCb = Rc<RefCell<Option<Box<Fn(&BreakpointGroups)>>>>;
 
 pub struct BreakpointsW {
-    // scrolled -> box -> [tree view, button ("Add breakpoints")]
+    // scrolled -> box -> [tree view,
+    //                    box -> [combo box (group), button ("Enable"), button ("Disable"),
+    //                            button ("Select")],
+    //                    box -> [button ("Import"), button ("Export")],
+    //                    box -> [button ("Add catchpoint")], button ("Add breakpoints")]
     widget: gtk::ScrolledWindow,
     model: gtk::ListStore,
+    group_combo: gtk::ComboBoxText,
+    groups: Rc<RefCell<BreakpointGroups>>,
     bp_enabled_renderer: gtk::CellRendererToggle,
     // "Add breakpoint" widget
     add_bp: BreakpointAddW,
+    // "Add catchpoint" widget
+    add_cp: CatchpointAddW,
+    commands: CommandsCb,
+    import: FileCb,
+    export: FileCb,
+    bulk_enable: BulkEnableCb,
+    bulk_delete: BulkDeleteCb,
+    groups_changed: GroupsCb,
 }
 
-// TODO: How to best show disposition?
-
 /// Number of columns
-const NUM_COLS: usize = 7;
+const NUM_COLS: usize = 13;
 
 /// Column indices for cell renderers
 #[repr(i32)]
@@ -25,6 +68,8 @@ enum Cols {
     Enabled = 0,
     // Unique
     Number,
+    // "breakpoint", or what a catchpoint catches, e.g. "catch throw"
+    Type,
     // Usually just a function name
     Location,
     // E.g. foo.c:123
@@ -35,21 +80,38 @@ enum Cols {
     Cond,
     // Number of hits so far
     Hits,
+    // Disposition and other options, e.g. "pending, delete on hit, thread 2". Empty for plain
+    // breakpoints.
+    State,
+    // Format and arguments of dprintf breakpoints, e.g. "x = %d\n",x
+    Format,
+    // Summary of the commands, e.g. "print x; bt 3; continue"
+    Commands,
+    // Name of the breakpoint's group, empty if not in a group
+    Group,
+    // Commands, one per line. Not rendered, used to initialize the editor.
+    Script,
 }
 
 /// Column types for the list store
 static COL_TYPES: [gtk::Type; NUM_COLS] = [
     gtk::Type::Bool,   // enabled
     gtk::Type::String, // number
+    gtk::Type::String, // type
     gtk::Type::String, // location
     gtk::Type::String, // file
     gtk::Type::String, // address
     gtk::Type::String, // condition
     gtk::Type::String, // hits
+    gtk::Type::String, // state
+    gtk::Type::String, // dprintf format
+    gtk::Type::String, // commands summary
+    gtk::Type::String, // group
+    gtk::Type::String, // commands
 ];
 
 /// Column indices for when inserting rows into the list store
-static COL_INDICES: [u32; NUM_COLS] = [0, 1, 2, 3, 4, 5, 6];
+static COL_INDICES: [u32; NUM_COLS] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
 
 impl BreakpointsW {
     pub fn new() -> BreakpointsW {
@@ -77,6 +139,49 @@ impl BreakpointsW {
         let add_bp = BreakpointAddW::new();
         box_.pack_end(add_bp.get_widget(), false, false, 0);
 
+        //
+        // Create the "Add catchpoint" widget. In its own box as the form swaps the button with
+        // the grid in its parent box.
+        //
+
+        let add_cp = CatchpointAddW::new();
+        let add_cp_box = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        add_cp_box.pack_end(add_cp.get_widget(), false, false, 0);
+        box_.pack_end(&add_cp_box, false, false, 0);
+
+        //
+        // Create import/export buttons
+        //
+
+        let import: FileCb = Rc::new(RefCell::new(None));
+        let export: FileCb = Rc::new(RefCell::new(None));
+
+        let file_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        let import_button = gtk::Button::new_with_label("Import…");
+        import_button.set_tooltip_text("Create breakpoints from a GDB script");
+        {
+            let import = import.clone();
+            import_button.connect_clicked(move |w| {
+                if let Some(path) = ask_file(w.upcast_ref(), gtk::FileChooserAction::Open) {
+                    call_file_cb(&import, "import breakpoints from", &path);
+                }
+            });
+        }
+        file_box.pack_start(&import_button, false, false, 0);
+        let export_button = gtk::Button::new_with_label("Export…");
+        export_button
+            .set_tooltip_text("Save breakpoints as a GDB script, to be loaded with \"source\"");
+        {
+            let export = export.clone();
+            export_button.connect_clicked(move |w| {
+                if let Some(path) = ask_file(w.upcast_ref(), gtk::FileChooserAction::Save) {
+                    call_file_cb(&export, "export breakpoints to", &path);
+                }
+            });
+        }
+        file_box.pack_start(&export_button, false, false, 0);
+        box_.pack_end(&file_box, false, false, 0);
+
         //
         // Create the view
         //
@@ -85,6 +190,7 @@ impl BreakpointsW {
         view.set_vexpand(false);
         view.set_hexpand(false);
         view.set_headers_visible(true);
+        view.get_selection().set_mode(gtk::SelectionMode::Multiple);
         box_.pack_start(&view, true, true, 0);
 
         // Enabled column, render as a toggle
@@ -111,17 +217,239 @@ impl BreakpointsW {
         };
 
         add_col("Number", Cols::Number, false);
+        add_col("Type", Cols::Type, false);
         add_col("Location", Cols::Location, true);
         add_col("File", Cols::File, true);
         add_col("Address", Cols::Address, false);
         add_col("Condition", Cols::Cond, true);
         add_col("Hits", Cols::Hits, false);
+        add_col("State", Cols::State, false);
+        add_col("Format", Cols::Format, false);
+        add_col("Commands", Cols::Commands, false);
+        add_col("Group", Cols::Group, false);
+
+        let commands: CommandsCb = Rc::new(RefCell::new(None));
+        let bulk_enable: BulkEnableCb = Rc::new(RefCell::new(None));
+        let bulk_delete: BulkDeleteCb = Rc::new(RefCell::new(None));
+        let groups_changed: GroupsCb = Rc::new(RefCell::new(None));
+        let groups = Rc::new(RefCell::new(BreakpointGroups::new()));
+
+        //
+        // Create the group bar
+        //
+
+        let group_box = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        let group_label = gtk::Label::new("Group");
+        group_box.pack_start(&group_label, false, false, 0);
+        let group_combo = gtk::ComboBoxText::new();
+        group_box.pack_start(&group_combo, true, true, 0);
+        for &(label, enable) in &[("Enable", true), ("Disable", false)] {
+            let button = gtk::Button::new_with_label(label);
+            let model = model.clone();
+            let group_combo = group_combo.clone();
+            let groups = groups.clone();
+            let bulk_enable = bulk_enable.clone();
+            button.connect_clicked(move |_| {
+                if let Some(name) = group_combo.get_active_text() {
+                    let ids = group_rows(&model, &groups.borrow(), &name)
+                        .iter()
+                        .map(|iter| row_number(&model, iter))
+                        .collect::<Vec<_>>();
+                    call_enable_cb(&bulk_enable, &ids, enable);
+                }
+            });
+            group_box.pack_start(&button, false, false, 0);
+        }
+        let select_button = gtk::Button::new_with_label("Select");
+        {
+            let model = model.clone();
+            let view = view.clone();
+            let group_combo = group_combo.clone();
+            let groups = groups.clone();
+            select_button.connect_clicked(move |_| {
+                if let Some(name) = group_combo.get_active_text() {
+                    let selection = view.get_selection();
+                    selection.unselect_all();
+                    for iter in group_rows(&model, &groups.borrow(), &name) {
+                        selection.select_iter(&iter);
+                    }
+                }
+            });
+        }
+        group_box.pack_start(&select_button, false, false, 0);
+        box_.pack_end(&group_box, false, false, 0);
+
+        //
+        // Create the context menu
+        //
+
+        // Number and commands of the breakpoint that the menu was opened for
+        let menu_row: Rc<RefCell<Option<(u32, String)>>> = Rc::new(RefCell::new(None));
+
+        let menu = gtk::Menu::new();
+        for &(label, enable) in &[("Enable selected", true), ("Disable selected", false)] {
+            let item = gtk::MenuItem::new_with_label(label);
+            let view = view.clone();
+            let bulk_enable = bulk_enable.clone();
+            item.connect_activate(move |_| {
+                let ids = selected_rows(&view)
+                    .into_iter()
+                    .map(|(bp_id, _)| bp_id)
+                    .collect::<Vec<_>>();
+                call_enable_cb(&bulk_enable, &ids, enable);
+            });
+            menu.append(&item);
+        }
+        let delete_item = gtk::MenuItem::new_with_label("Delete selected");
+        {
+            let view = view.clone();
+            let bulk_delete = bulk_delete.clone();
+            delete_item.connect_activate(move |_| {
+                let ids = selected_rows(&view)
+                    .into_iter()
+                    .map(|(bp_id, _)| bp_id)
+                    .collect::<Vec<_>>();
+                match *bulk_delete.borrow() {
+                    None => {
+                        println!("Can't delete breakpoints {:?}: callback not set", ids);
+                    }
+                    Some(ref cb) => {
+                        cb(&ids);
+                    }
+                }
+            });
+        }
+        menu.append(&delete_item);
+        menu.append(&gtk::SeparatorMenuItem::new());
+        let add_group_item = gtk::MenuItem::new_with_label("Add to group…");
+        {
+            let view = view.clone();
+            let model = model.clone();
+            let group_combo = group_combo.clone();
+            let groups = groups.clone();
+            let groups_changed = groups_changed.clone();
+            add_group_item.connect_activate(move |_| {
+                let names = groups
+                    .borrow()
+                    .names()
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                let name = match ask_group(view.upcast_ref(), &names) {
+                    None => {
+                        return;
+                    }
+                    Some(name) => name,
+                };
+                for (_, key) in selected_rows(&view) {
+                    groups.borrow_mut().add(&name, &key);
+                }
+                update_groups(&model, &group_combo, &groups.borrow());
+                group_combo.set_active_id(Some(name.as_str()));
+                call_groups_cb(&groups_changed, &groups.borrow());
+            });
+        }
+        menu.append(&add_group_item);
+        let remove_group_item = gtk::MenuItem::new_with_label("Remove from group");
+        {
+            let view = view.clone();
+            let model = model.clone();
+            let group_combo = group_combo.clone();
+            let groups = groups.clone();
+            let groups_changed = groups_changed.clone();
+            remove_group_item.connect_activate(move |_| {
+                for (_, key) in selected_rows(&view) {
+                    groups.borrow_mut().remove(&key);
+                }
+                update_groups(&model, &group_combo, &groups.borrow());
+                call_groups_cb(&groups_changed, &groups.borrow());
+            });
+        }
+        menu.append(&remove_group_item);
+        menu.append(&gtk::SeparatorMenuItem::new());
+        let commands_item = gtk::MenuItem::new_with_label("Edit commands…");
+        {
+            let menu_row = menu_row.clone();
+            let commands = commands.clone();
+            let view = view.clone();
+            commands_item.connect_activate(move |_| {
+                let (bp_id, script) = match *menu_row.borrow() {
+                    None => {
+                        return;
+                    }
+                    Some((bp_id, ref script)) => (bp_id, script.clone()),
+                };
+                if let Some(new_commands) = ask_commands(view.upcast_ref(), bp_id, &script) {
+                    match *commands.borrow() {
+                        None => {
+                            println!("Can't set commands of {}: callback not set", bp_id);
+                        }
+                        Some(ref cb) => {
+                            cb(bp_id, new_commands);
+                        }
+                    }
+                }
+            });
+        }
+        menu.append(&commands_item);
+        menu.show_all();
+        menu.set_attach_widget(Some(&view));
+
+        view.connect_button_press_event(move |view, ev| {
+            if ev.get_event_type() != gdk::EventType::ButtonPress || ev.get_button() != 3 {
+                return gtk::Inhibit(false);
+            }
+            let (x, y) = ev.get_position();
+            let path = match view.get_path_at_pos(x as i32, y as i32) {
+                Some((Some(path), _, _, _)) => path,
+                _ => {
+                    return gtk::Inhibit(false);
+                }
+            };
+            let model = view.get_model().unwrap();
+            let iter = model.get_iter(&path).unwrap();
+            let bp_id = model
+                .get_value(&iter, Cols::Number as i32)
+                .get::<String>()
+                .unwrap()
+                .parse::<u32>()
+                .unwrap();
+            let script = model
+                .get_value(&iter, Cols::Script as i32)
+                .get::<String>()
+                .unwrap();
+            let type_ = model
+                .get_value(&iter, Cols::Type as i32)
+                .get::<String>()
+                .unwrap();
+            // Keep the selection when clicking on a selected row, to act on all selected rows
+            let selection = view.get_selection();
+            if !selection.path_is_selected(&path) {
+                selection.unselect_all();
+                selection.select_path(&path);
+            }
+            // Commands of a dprintf are the printf
+            let single = selection.count_selected_rows() == 1;
+            commands_item.set_sensitive(single && type_ != "dprintf");
+            *menu_row.borrow_mut() = Some((bp_id, script));
+            menu.popup_at_pointer(Some(&**ev));
+            gtk::Inhibit(true)
+        });
 
         BreakpointsW {
             widget: scrolled,
             model,
+            group_combo,
+            groups,
             bp_enabled_renderer,
             add_bp,
+            add_cp,
+            commands,
+            import,
+            export,
+            bulk_enable,
+            bulk_delete,
+            groups_changed,
         }
     }
 
@@ -146,11 +474,69 @@ impl BreakpointsW {
         });
     }
 
-    /// Set "breakpoint added" callback. Arguments are: location, condition.
-    pub fn connect_breakpoint_added(&self, cb: Box<Fn(String, String)>) {
+    /// Set "breakpoint added" callback. The callback should call `breakpoint_add_done` or
+    /// `breakpoint_add_failed`.
+    pub fn connect_breakpoint_added(&self, cb: Box<Fn(BreakpointSpec)>) {
         self.add_bp.connect_breakpoint_added(cb);
     }
 
+    /// Close the "add breakpoint" form after creating the breakpoint.
+    pub fn breakpoint_add_done(&self) {
+        self.add_bp.reset();
+    }
+
+    /// Show why the breakpoint of the "add breakpoint" form can't be created. `field` is the
+    /// entry with the invalid input.
+    pub fn breakpoint_add_failed(&self, field: BreakpointField, msg: &str) {
+        self.add_bp.show_error(field, msg);
+    }
+
+    /// Set "catchpoint added" callback. Arguments are: what to catch, argument of the catchpoint
+    /// (exception regex, syscall names or library regex, empty when not given).
+    pub fn connect_catchpoint_added(&self, cb: Box<Fn(CatchpointType, String)>) {
+        self.add_cp.connect_catchpoint_added(cb);
+    }
+
+    /// Set "breakpoint commands" callback. Arguments are: breakpoint number, new commands (empty
+    /// to remove the commands). The breakpoint should be updated with `add_or_update_breakpoint`.
+    pub fn connect_breakpoint_commands(&mut self, cb: Box<Fn(u32, Vec<String>)>) {
+        *self.commands.borrow_mut() = Some(cb);
+    }
+
+    /// Set "import" callback. Argument is path of the script to import.
+    pub fn connect_import(&mut self, cb: Box<Fn(&Path)>) {
+        *self.import.borrow_mut() = Some(cb);
+    }
+
+    /// Set "export" callback. Argument is path of the script to write.
+    pub fn connect_export(&mut self, cb: Box<Fn(&Path)>) {
+        *self.export.borrow_mut() = Some(cb);
+    }
+
+    /// Set "breakpoints enabled" callback, for enabling or disabling selected breakpoints or a
+    /// group. Arguments are: breakpoint numbers, whether to enable or disable.
+    pub fn connect_breakpoints_enabled(&mut self, cb: Box<Fn(&[u32], bool)>) {
+        *self.bulk_enable.borrow_mut() = Some(cb);
+    }
+
+    /// Set "breakpoints deleted" callback. Argument is the breakpoint numbers. Rows should be
+    /// removed with `remove_breakpoint`.
+    pub fn connect_breakpoints_deleted(&mut self, cb: Box<Fn(&[u32])>) {
+        *self.bulk_delete.borrow_mut() = Some(cb);
+    }
+
+    /// Set "groups changed" callback, called after adding breakpoints to or removing breakpoints
+    /// from groups. Groups should be saved.
+    pub fn connect_groups_changed(&mut self, cb: Box<Fn(&BreakpointGroups)>) {
+        *self.groups_changed.borrow_mut() = Some(cb);
+    }
+
+    /// Replace groups, e.g. with groups loaded for a new program.
+    pub fn set_groups(&self, groups: BreakpointGroups) {
+        update_groups(&self.model, &self.group_combo, &groups);
+        *self.groups.borrow_mut() = groups;
+    }
+
     pub fn toggle_breakpoint(&self, bp_id: u32, enable: bool) {
         // find the row for the row with given breakpoint id
         if let Some(iter) = self.model.get_iter_first() {
@@ -179,6 +565,40 @@ impl BreakpointsW {
         self.widget.upcast_ref()
     }
 
+    /// Update breakpoints created, modified or deleted by GDB (e.g. in the console) or by us.
+    pub fn handle_event(&self, event: &Event) {
+        match event {
+            Event::BreakpointCreated(bp) | Event::BreakpointModified(bp) => {
+                self.add_or_update_breakpoint(bp);
+            }
+            Event::BreakpointDeleted { id } => {
+                self.remove_breakpoint(*id);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn remove_breakpoint(&self, bp_id: u32) {
+        if let Some(iter) = self.model.get_iter_first() {
+            loop {
+                let bp_id_ = self
+                    .model
+                    .get_value(&iter, Cols::Number as i32)
+                    .get::<String>()
+                    .unwrap()
+                    .parse::<u32>()
+                    .unwrap();
+                if bp_id_ == bp_id {
+                    self.model.remove(&iter);
+                    return;
+                }
+                if !self.model.iter_next(&iter) {
+                    break;
+                }
+            }
+        }
+    }
+
     /// Update the breakpoint if it exists, otherwise add a new one.
     pub fn add_or_update_breakpoint(&self, bp: &Breakpoint) {
         println!("add_or_update_breakpoint({:?})", bp);
@@ -196,19 +616,31 @@ impl BreakpointsW {
                         &iter,
                         &[
                             Cols::Enabled as u32,
+                            Cols::Type as u32,
                             Cols::Location as u32,
                             Cols::File as u32,
                             Cols::Address as u32,
                             Cols::Cond as u32,
                             Cols::Hits as u32,
+                            Cols::State as u32,
+                            Cols::Format as u32,
+                            Cols::Commands as u32,
+                            Cols::Group as u32,
+                            Cols::Script as u32,
                         ],
                         &[
                             &mk_enabled_col(bp),
+                            &mk_type_col(bp),
                             &mk_location_col(bp),
                             &mk_file_col(bp),
                             &mk_address_col(bp),
                             &mk_cond_col(bp),
                             &mk_hits_col(bp),
+                            &mk_state_col(bp),
+                            &mk_format_col(bp),
+                            &mk_commands_col(bp),
+                            &mk_group_col(bp, &self.groups.borrow()),
+                            &mk_script_col(bp),
                         ],
                     );
                     return;
@@ -224,11 +656,17 @@ impl BreakpointsW {
         let values: [&dyn gtk::ToValue; NUM_COLS] = [
             &mk_enabled_col(bp),
             &mk_number_col(bp),
+            &mk_type_col(bp),
             &mk_location_col(bp),
             &mk_file_col(bp),
             &mk_address_col(bp),
             &mk_cond_col(bp),
             &mk_hits_col(bp),
+            &mk_state_col(bp),
+            &mk_format_col(bp),
+            &mk_commands_col(bp),
+            &mk_group_col(bp, &self.groups.borrow()),
+            &mk_script_col(bp),
         ];
         self.model.set(&self.model.append(), &COL_INDICES, &values);
     }
@@ -242,6 +680,29 @@ fn mk_number_col(bp: &Breakpoint) -> gtk::Value {
     format!("{}", bp.number).to_value()
 }
 
+fn mk_type_col(bp: &Breakpoint) -> gtk::Value {
+    match bp.type_ {
+        BreakpointType::Breakpoint => "breakpoint",
+        BreakpointType::HardwareBreakpoint => "hw breakpoint",
+        BreakpointType::Watchpoint => "watchpoint",
+        BreakpointType::Dprintf => "dprintf",
+        BreakpointType::Catchpoint(type_) => match type_ {
+            CatchpointType::Throw => "catch throw",
+            CatchpointType::Catch => "catch catch",
+            CatchpointType::Rethrow => "catch rethrow",
+            CatchpointType::Syscall => "catch syscall",
+            CatchpointType::Fork => "catch fork",
+            CatchpointType::Vfork => "catch vfork",
+            CatchpointType::Exec => "catch exec",
+            CatchpointType::Load => "catch load",
+            CatchpointType::Unload => "catch unload",
+            CatchpointType::Signal => "catch signal",
+            CatchpointType::Other => "catchpoint",
+        },
+    }
+    .to_value()
+}
+
 fn mk_location_col(bp: &Breakpoint) -> gtk::Value {
     bp.original_location.to_value()
 }
@@ -267,3 +728,316 @@ fn mk_cond_col(bp: &Breakpoint) -> gtk::Value {
 fn mk_hits_col(bp: &Breakpoint) -> gtk::Value {
     format!("{}", bp.hits).to_value()
 }
+
+fn mk_state_col(bp: &Breakpoint) -> gtk::Value {
+    let mut state = vec![];
+    if bp.pending {
+        state.push("pending".to_string());
+    }
+    match bp.disposition {
+        BreakpointDisposition::Keep | BreakpointDisposition::NoKeep => {}
+        BreakpointDisposition::Delete => {
+            state.push("delete on hit".to_string());
+        }
+        BreakpointDisposition::Disable => {
+            state.push("disable on hit".to_string());
+        }
+    }
+    if let Some(thread) = bp.thread {
+        state.push(format!("thread #{}", thread));
+    }
+    if bp.ignore_count != 0 {
+        state.push(format!("ignore next {} hits", bp.ignore_count));
+    }
+    state.join(", ").to_value()
+}
+
+fn mk_format_col(bp: &Breakpoint) -> gtk::Value {
+    bp.dprintf_format().unwrap_or("").to_value()
+}
+
+/// Max. number of commands to show in the commands column.
+const MAX_SUMMARY_COMMANDS: usize = 3;
+
+fn mk_commands_col(bp: &Breakpoint) -> gtk::Value {
+    if bp.type_ == BreakpointType::Dprintf {
+        // Shown in the format column
+        return "".to_value();
+    }
+    let mut summary = bp
+        .script
+        .iter()
+        .take(MAX_SUMMARY_COMMANDS)
+        .map(|command| command.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    if bp.script.len() > MAX_SUMMARY_COMMANDS {
+        summary.push_str(&format!(
+            "; … (+{})",
+            bp.script.len() - MAX_SUMMARY_COMMANDS
+        ));
+    }
+    summary.to_value()
+}
+
+fn mk_group_col(bp: &Breakpoint, groups: &BreakpointGroups) -> gtk::Value {
+    groups
+        .group_of(&breakpoint_key(
+            &bp.original_location,
+            bp.cond.as_ref().map(String::as_str),
+        ))
+        .unwrap_or("")
+        .to_value()
+}
+
+fn mk_script_col(bp: &Breakpoint) -> gtk::Value {
+    bp.script.join("\n").to_value()
+}
+
+/// Ask the user for commands of breakpoint `bp_id`. `script` is the current commands, one per
+/// line. Returns `None` when cancelled.
+fn ask_commands(parent: &gtk::Widget, bp_id: u32, script: &str) -> Option<Vec<String>> {
+    let dialog = gtk::Dialog::new();
+    dialog.set_title(&format!("Commands of breakpoint {}", bp_id));
+    dialog.set_modal(true);
+    dialog.set_default_size(400, 250);
+    if let Some(window) = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok())
+    {
+        dialog.set_transient_for(Some(&window));
+    }
+
+    let label = gtk::Label::new("GDB commands to run when the breakpoint is hit, one per line");
+    label.set_halign(gtk::Align::Start);
+    dialog
+        .get_content_area()
+        .pack_start(&label, false, false, 0);
+
+    let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+    scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+    let text_view = gtk::TextView::new();
+    text_view.set_monospace(true);
+    text_view.get_buffer().unwrap().set_text(script);
+    scrolled.add(&text_view);
+    dialog
+        .get_content_area()
+        .pack_start(&scrolled, true, true, 0);
+
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel.into());
+    dialog.add_button("Save", gtk::ResponseType::Ok.into());
+    dialog.show_all();
+
+    let response = gtk::ResponseType::from(dialog.run());
+    let buffer = text_view.get_buffer().unwrap();
+    let text = buffer
+        .get_text(&buffer.get_start_iter(), &buffer.get_end_iter(), false)
+        .map(|s| s.to_string())
+        .unwrap_or_default();
+    dialog.destroy();
+
+    if response != gtk::ResponseType::Ok {
+        return None;
+    }
+    Some(
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
+}
+
+/// Ask the user for a script to import or a file to export to.
+fn ask_file(parent: &gtk::Widget, action: gtk::FileChooserAction) -> Option<PathBuf> {
+    let window = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok());
+    let (title, accept) = match action {
+        gtk::FileChooserAction::Save => ("Export breakpoints", "Export"),
+        _ => ("Import breakpoints", "Import"),
+    };
+    let dialog = gtk::FileChooserDialog::new(Some(title), window.as_ref(), action);
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel.into());
+    dialog.add_button(accept, gtk::ResponseType::Accept.into());
+    if action == gtk::FileChooserAction::Save {
+        dialog.set_do_overwrite_confirmation(true);
+        dialog.set_current_name("breakpoints.gdb");
+    }
+
+    let response = gtk::ResponseType::from(dialog.run());
+    let path = dialog.get_filename();
+    dialog.destroy();
+
+    if response != gtk::ResponseType::Accept {
+        return None;
+    }
+    path
+}
+
+fn call_file_cb(cb: &FileCb, action: &str, path: &Path) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't {} {}: callback not set", action, path.display());
+        }
+        Some(ref cb) => {
+            cb(path);
+        }
+    }
+}
+
+fn call_enable_cb(cb: &BulkEnableCb, ids: &[u32], enable: bool) {
+    if ids.is_empty() {
+        return;
+    }
+    match *cb.borrow() {
+        None => {
+            println!(
+                "Can't enable/disable breakpoints {:?}: callback not set",
+                ids
+            );
+        }
+        Some(ref cb) => {
+            cb(ids, enable);
+        }
+    }
+}
+
+fn call_groups_cb(cb: &GroupsCb, groups: &BreakpointGroups) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't save breakpoint groups: callback not set");
+        }
+        Some(ref cb) => {
+            cb(groups);
+        }
+    }
+}
+
+fn row_number(model: &gtk::ListStore, iter: &gtk::TreeIter) -> u32 {
+    model
+        .get_value(iter, Cols::Number as i32)
+        .get::<String>()
+        .unwrap()
+        .parse::<u32>()
+        .unwrap()
+}
+
+/// Key of the breakpoint in groups, see `breakpoint_groups::breakpoint_key`.
+fn row_key(model: &gtk::TreeModel, iter: &gtk::TreeIter) -> String {
+    let location = model
+        .get_value(iter, Cols::Location as i32)
+        .get::<String>()
+        .unwrap();
+    let cond = model.get_value(iter, Cols::Cond as i32).get::<String>();
+    breakpoint_key(&location, cond.as_ref().map(String::as_str))
+}
+
+/// Numbers and group keys of the selected breakpoints.
+fn selected_rows(view: &gtk::TreeView) -> Vec<(u32, String)> {
+    let (paths, model) = view.get_selection().get_selected_rows();
+    paths
+        .iter()
+        .filter_map(|path| model.get_iter(path))
+        .map(|iter| {
+            let bp_id = model
+                .get_value(&iter, Cols::Number as i32)
+                .get::<String>()
+                .unwrap()
+                .parse::<u32>()
+                .unwrap();
+            (bp_id, row_key(&model, &iter))
+        })
+        .collect()
+}
+
+/// Rows of the breakpoints in group `name`.
+fn group_rows(model: &gtk::ListStore, groups: &BreakpointGroups, name: &str) -> Vec<gtk::TreeIter> {
+    let mut rows = vec![];
+    if let Some(iter) = model.get_iter_first() {
+        loop {
+            let key = row_key(model.upcast_ref(), &iter);
+            if groups.group_of(&key) == Some(name) {
+                rows.push(iter.clone());
+            }
+            if !model.iter_next(&iter) {
+                break;
+            }
+        }
+    }
+    rows
+}
+
+/// Update the group column of all rows and the group names in the combo box. Keeps the active
+/// group if it still exists.
+fn update_groups(model: &gtk::ListStore, combo: &gtk::ComboBoxText, groups: &BreakpointGroups) {
+    if let Some(iter) = model.get_iter_first() {
+        loop {
+            let key = row_key(model.upcast_ref(), &iter);
+            let group = groups.group_of(&key).unwrap_or("");
+            model.set_value(&iter, Cols::Group as u32, &group.to_value());
+            if !model.iter_next(&iter) {
+                break;
+            }
+        }
+    }
+
+    let active = combo.get_active_text();
+    combo.remove_all();
+    for name in groups.names() {
+        combo.append(Some(name), name);
+    }
+    let active_kept = match active {
+        None => false,
+        Some(name) => combo.set_active_id(Some(name.as_str())),
+    };
+    if !active_kept && !groups.names().is_empty() {
+        combo.set_active(Some(0));
+    }
+}
+
+/// Ask the user for a group to add the selected breakpoints to. `names` is the existing groups, a
+/// new group is created when the name is not one of them. Returns `None` when cancelled.
+fn ask_group(parent: &gtk::Widget, names: &[String]) -> Option<String> {
+    let dialog = gtk::Dialog::new();
+    dialog.set_title("Add to group");
+    dialog.set_modal(true);
+    if let Some(window) = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok())
+    {
+        dialog.set_transient_for(Some(&window));
+    }
+
+    let combo = gtk::ComboBoxText::new_with_entry();
+    for name in names {
+        combo.append_text(name);
+    }
+    if let Some(entry) = combo
+        .get_child()
+        .and_then(|w| w.downcast::<gtk::Entry>().ok())
+    {
+        entry.set_placeholder_text("Group name");
+        entry.set_activates_default(true);
+    }
+    dialog
+        .get_content_area()
+        .pack_start(&combo, false, false, 0);
+
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel.into());
+    dialog.add_button("Add", gtk::ResponseType::Ok.into());
+    dialog.set_default_response(gtk::ResponseType::Ok.into());
+    dialog.show_all();
+
+    let response = gtk::ResponseType::from(dialog.run());
+    let name = combo
+        .get_active_text()
+        .map(|s| s.as_str().trim().to_string())
+        .unwrap_or_default();
+    dialog.destroy();
+
+    if response != gtk::ResponseType::Ok || name.is_empty() {
+        return None;
+    }
+    Some(name)
+}
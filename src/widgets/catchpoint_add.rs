@@ -0,0 +1,159 @@
+//! Similar to `BreakPointAddW`, for catchpoints
+
+use gtk::prelude::*;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::types::CatchpointType;
+
+/// Type of a "catchpoint added" callback. Arguments are: what to catch, argument of the
+/// catchpoint (see `KINDS`). Argument is empty when not given.
+type CatchpointAddCb = Rc<RefCell<Option<Box<Fn(CatchpointType, String)>>>>;
+
+/// Catchpoints that can be created with the form, in order of the combo box entries: type, combo
+/// box text, placeholder text for the argument entry.
+static KINDS: [(CatchpointType, &str, &str); 8] = [
+    (
+        CatchpointType::Throw,
+        "C++ throw",
+        "Exception type regex (optional)",
+    ),
+    (
+        CatchpointType::Catch,
+        "C++ catch",
+        "Exception type regex (optional)",
+    ),
+    (
+        CatchpointType::Rethrow,
+        "C++ rethrow",
+        "Exception type regex (optional)",
+    ),
+    (
+        CatchpointType::Syscall,
+        "Syscall",
+        "Syscall names or numbers (optional)",
+    ),
+    (CatchpointType::Fork, "Fork", "No argument"),
+    (CatchpointType::Vfork, "Vfork", "No argument"),
+    (CatchpointType::Exec, "Exec", "No argument"),
+    (
+        CatchpointType::Load,
+        "Library load",
+        "Library path regex (optional)",
+    ),
+];
+
+pub struct CatchpointAddW {
+    button: gtk::Button,
+    cb: CatchpointAddCb,
+}
+
+impl CatchpointAddW {
+    pub fn new() -> CatchpointAddW {
+        //
+        // Initialize the button
+        //
+
+        let button = gtk::Button::new_from_icon_name("gtk-add", gtk::IconSize::SmallToolbar);
+        button.set_use_underline(true);
+        button.set_label("New _catchpoint");
+        button.set_halign(gtk::Align::Start);
+
+        //
+        // Initialize the entries
+        //
+
+        // grid -> [ [ kind label, combo box ],
+        //           [ argument label, argument entry ] ]
+        let grid = gtk::Grid::new();
+        let kind_label = gtk::Label::new("Catch");
+        let kind_combo = gtk::ComboBoxText::new();
+        for (_, text, _) in KINDS.iter() {
+            kind_combo.append_text(text);
+        }
+        let arg_label = gtk::Label::new("Argument");
+        let arg_entry = gtk::Entry::new();
+        arg_entry.set_hexpand(true);
+
+        grid.attach(&kind_label, 0, 0, 1, 1);
+        grid.attach(&kind_combo, 1, 0, 1, 1);
+        grid.attach(&arg_label, 0, 1, 1, 1);
+        grid.attach(&arg_entry, 1, 1, 1, 1);
+
+        //
+        // The callback cell
+        //
+
+        let cb: CatchpointAddCb = Rc::new(RefCell::new(None));
+
+        //
+        // Connect signals
+        //
+
+        // Kind changed -> update the argument hint. The entry stays sensitive even for kinds
+        // without an argument, as activating it submits the form.
+        let arg_entry_clone = arg_entry.clone();
+        kind_combo.connect_changed(move |w| {
+            if let Some(idx) = w.get_active() {
+                arg_entry_clone.set_placeholder_text(KINDS[idx as usize].2);
+            }
+        });
+        kind_combo.set_active(0);
+
+        // Button clicked -> remove the button and add the grid
+        let grid_clone = grid.clone();
+        let arg_entry_clone = arg_entry.clone();
+        button.connect_clicked(move |w| {
+            // Remove it from the parent
+            let parent = w.get_parent().unwrap();
+            let box_ = parent.downcast_ref::<gtk::Box>().unwrap();
+            box_.remove(w);
+            // Add the grid
+            box_.pack_end(&grid_clone, false, false, 0);
+            box_.show_all();
+            // Move the focus to the argument entry
+            arg_entry_clone.grab_focus();
+        });
+
+        // Entry submitted -> call the callback, remove the grid, add button
+        let cb_clone = cb.clone();
+        let grid_clone = grid.clone();
+        let kind_combo_clone = kind_combo.clone();
+        let button_clone = button.clone();
+        arg_entry.connect_activate(move |w| {
+            let kind = match kind_combo_clone.get_active() {
+                Some(idx) => KINDS[idx as usize].0,
+                None => {
+                    return;
+                }
+            };
+            let arg = match w.get_text() {
+                None => "".to_string(),
+                Some(arg) => arg.as_str().trim().to_string(),
+            };
+            if let Some(ref cb) = *cb_clone.borrow() {
+                cb(kind, arg)
+            }
+            w.set_text("");
+            // Remove the grid from the box
+            let parent = w.get_ancestor(gtk::Box::static_type()).unwrap();
+            let box_ = parent.downcast_ref::<gtk::Box>().unwrap();
+            box_.remove(&grid_clone);
+            // Add button
+            box_.pack_end(&button_clone, false, false, 0);
+            box_.show_all();
+        });
+
+        CatchpointAddW { button, cb }
+    }
+
+    pub fn get_widget(&self) -> &gtk::Widget {
+        // Return the button. When clicked it'll be removed and the grid will be added.
+        self.button.upcast_ref()
+    }
+
+    pub fn connect_catchpoint_added(&self, cb: Box<Fn(CatchpointType, String)>) {
+        *self.cb.borrow_mut() = Some(cb);
+    }
+}
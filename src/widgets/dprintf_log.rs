@@ -0,0 +1,97 @@
+//! A log of dynamic printf (dprintf breakpoint) output, with a timestamp and the breakpoint number
+//! of every line, e.g. "12:03:41.527 #2  x = 10".
+
+use std::cell::Cell;
+
+use gtk::prelude::*;
+
+pub struct DprintfLogW {
+    // box -> [ scrolled -> text view, "clear" button ]
+    widget: gtk::Box,
+    text_view: gtk::TextView,
+    /// Whether the last output ended with a newline. Output of a single printf may arrive in
+    /// multiple console records, lines are only prefixed when they start.
+    at_line_start: Cell<bool>,
+}
+
+impl DprintfLogW {
+    pub fn new() -> DprintfLogW {
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+
+        let text_view = gtk::TextView::new();
+        text_view.set_monospace(true);
+        text_view.set_vexpand(true);
+        text_view.set_editable(false);
+        scrolled.add(&text_view);
+
+        let clear = gtk::Button::new_with_label("Clear");
+        clear.set_halign(gtk::Align::Start);
+        {
+            let text_view = text_view.clone();
+            clear.connect_clicked(move |_| {
+                text_view.get_buffer().unwrap().set_text("");
+            });
+        }
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        widget.pack_start(&scrolled, true, true, 0);
+        widget.pack_start(&clear, false, false, 0);
+
+        DprintfLogW {
+            widget,
+            text_view,
+            at_line_start: Cell::new(true),
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Add output of dprintf breakpoint `bp_id`.
+    pub fn add_output(&self, bp_id: u32, output: &str) {
+        let mut text = String::with_capacity(output.len());
+        for line in output.split_terminator('\n') {
+            if self.at_line_start.get() {
+                text.push_str(&format!("{} #{}  ", timestamp(), bp_id));
+            }
+            text.push_str(line);
+            text.push('\n');
+            self.at_line_start.set(true);
+        }
+        if !output.ends_with('\n') && !text.is_empty() {
+            // Last line is not finished, continue it with the next output
+            text.pop();
+            self.at_line_start.set(false);
+        }
+
+        let text_buffer = self.text_view.get_buffer().unwrap();
+        let mut end_iter = text_buffer.get_end_iter();
+        text_buffer.insert(&mut end_iter, &text);
+
+        // Scroll to the bottom
+        let end_mark = text_buffer
+            .create_mark(
+                None,
+                &text_buffer.get_end_iter(),
+                false, /* left gravity */
+            )
+            .unwrap();
+        self.text_view.scroll_mark_onscreen(&end_mark);
+        text_buffer.delete_mark(&end_mark);
+    }
+}
+
+/// Local time, e.g. "12:03:41.527".
+fn timestamp() -> String {
+    let now = glib::DateTime::new_now_local();
+    format!(
+        "{}.{:03}",
+        now.format("%H:%M:%S")
+            .map(|s| s.to_string())
+            .unwrap_or_default(),
+        now.get_microsecond() / 1000
+    )
+}
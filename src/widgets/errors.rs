@@ -0,0 +1,72 @@
+//! An info bar that shows errors from GDB (e.g. `^error` results of commands sent by the UI),
+//! which would otherwise only be visible in the GDB log.
+
+use gtk::prelude::*;
+
+/// Max. number of errors to keep in the tooltip.
+const MAX_ERRORS: usize = 20;
+
+pub struct ErrorsW {
+    // info bar -> content area -> label
+    widget: gtk::InfoBar,
+    label: gtk::Label,
+    /// Recent errors, shown in the tooltip. Newest error is the last one.
+    errors: Vec<String>,
+}
+
+impl ErrorsW {
+    pub fn new() -> ErrorsW {
+        let info_bar = gtk::InfoBar::new();
+        info_bar.set_message_type(gtk::MessageType::Error);
+        info_bar.set_show_close_button(true);
+        // Only shown when there's an error
+        info_bar.set_no_show_all(true);
+
+        let label = gtk::Label::new(None);
+        label.set_halign(gtk::Align::Start);
+        label.set_line_wrap(true);
+        label.set_selectable(true);
+        label.show();
+        info_bar.get_content_area().unwrap().add(&label);
+
+        info_bar.connect_response(|info_bar, _| {
+            info_bar.hide();
+        });
+
+        ErrorsW {
+            widget: info_bar,
+            label,
+            errors: vec![],
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Show an error. Errors stay visible until closed or a new error is shown. Previous errors
+    /// are shown in the tooltip.
+    pub fn show_error(&mut self, error: &str) {
+        self.errors.push(error.to_string());
+        if self.errors.len() > MAX_ERRORS {
+            self.errors.remove(0);
+        }
+
+        if self.errors.len() > 1 {
+            self.label
+                .set_text(&format!("{} ({} errors)", error, self.errors.len()));
+        } else {
+            self.label.set_text(error);
+        }
+        self.label
+            .set_tooltip_text(Some(self.errors.join("\n").as_str()));
+        self.widget.show();
+    }
+
+    /// Hide the bar and forget the errors.
+    pub fn clear(&mut self) {
+        self.errors.clear();
+        self.widget.hide();
+    }
+}
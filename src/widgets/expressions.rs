@@ -6,6 +6,20 @@ use std::rc::Rc;
 
 use gtk::prelude::*;
 
+use crate::types::{DisplayHint, Value, VarFormat};
+
+// TODO: copied from app
+macro_rules! some {
+    ( $x:expr ) => {
+        match $x {
+            Some(ret) => ret,
+            None => {
+                return;
+            }
+        }
+    };
+}
+
 pub struct ExpressionsW {
     store: gtk::TreeStore,
     view: gtk::TreeView,
@@ -19,14 +33,56 @@ pub struct ExpressionsW {
     get_children: ExprGetChildrenCb,
     // Callback for adding new expressions
     add_expr: AddExprCb,
+    // Callback for deleting top-level expressions
+    delete_expr: DeleteExprCb,
+    // Callback for changing display format of expressions
+    set_format: SetFormatCb,
 }
 
-/// Type of the reference for the callback for asking for children of an expression.
-type ExprGetChildrenCb = Rc<RefCell<Option<Box<Fn(&str /* full name of the expression */)>>>>;
+/// Type of the reference for the callback for asking for children of an expression. Arguments
+/// are: full name of the expression, index of the first child, index of the last child + 1.
+type ExprGetChildrenCb = Rc<RefCell<Option<Box<Fn(&str, usize, usize)>>>>;
 
 /// Type of the reference for the callback for adding new expressions.
 type AddExprCb = Rc<RefCell<Option<Box<Fn(String /* expression */)>>>>;
 
+/// Type of the reference for the callback for deleting expressions.
+type DeleteExprCb = Rc<RefCell<Option<Box<Fn(&str /* full name of the expression */)>>>>;
+
+/// Type of the reference for the callback for changing display format of expressions.
+type SetFormatCb = Rc<RefCell<Option<Box<Fn(&str /* full name of the expression */, VarFormat)>>>>;
+
+/// Number of children to ask for at once. Arrays can have millions of elements, we don't want to
+/// fetch all of them when a node is expanded.
+pub const CHILDREN_PAGE_SIZE: usize = 100;
+
+/// Max. number of children to show in a node. When more children are loaded the old ones are
+/// dropped.
+const MAX_CHILDREN: usize = 1000;
+
+/// Formats shown in the context menu.
+static FORMATS: [VarFormat; 5] = [
+    VarFormat::Natural,
+    VarFormat::Hexadecimal,
+    VarFormat::Binary,
+    VarFormat::Octal,
+    VarFormat::Decimal,
+];
+
+/// Column indices of the tree store.
+#[repr(i32)]
+enum Cols {
+    // Full name of the expression, not rendered
+    FullName = 0,
+    Expr,
+    Value,
+    Type,
+    // Whether the expression is in scope. Out-of-scope expressions are greyed out.
+    InScope,
+    // Whether the value changed in the last `-var-update`. Changed values are highlighted.
+    Changed,
+}
+
 // TODO: Rename this
 struct ExpressionChild {
     /// Location of this node in the tree.
@@ -44,8 +100,34 @@ struct ExpressionChild {
     value: Option<String>,
     type_: Option<String>,
 
-    /// Children of this node.
+    /// Display format of the value. Children inherit format of the parent.
+    format: VarFormat,
+
+    /// Display hint of the pretty-printer, if the expression is pretty-printed.
+    display_hint: Option<DisplayHint>,
+
+    /// Number of children. Not known for dynamic varobjs, see `has_more`.
+    n_children: usize,
+
+    /// Whether the expression is a dynamic (pretty-printed) varobj.
+    dynamic: bool,
+
+    /// Children of this node. Only a range of the children are loaded, see `fetched_to`.
     children: Vec<ExpressionChild>,
+
+    /// Index of the next child to load (end of the loaded range).
+    fetched_to: usize,
+
+    /// The "load more" row, added after the children when there are more children to load.
+    load_more: Option<gtk::TreeIter>,
+
+    /// Whether we asked for the children. Expressions without children have this set too, to
+    /// avoid asking for children on every expand.
+    children_fetched: bool,
+
+    /// Expand the node when children are added. Used to restore expanded nodes after dropping
+    /// their children.
+    expand_when_fetched: bool,
 }
 
 // TODO: Put the tree in a scrolled window
@@ -57,6 +139,8 @@ impl ExpressionsW {
             String::static_type(), // expression
             String::static_type(), // value
             String::static_type(), // type
+            bool::static_type(),   // in scope
+            bool::static_type(),   // changed
         ]);
 
         // Without this we can't store and reuse TreeIters
@@ -85,20 +169,28 @@ impl ExpressionsW {
         // Create columns
         //
 
-        let add_text_col = |name: &str, idx: i32| {
+        let add_text_col = |name: &str, col_ty: Cols| {
             let cell_renderer = gtk::CellRendererText::new();
             let col = gtk::TreeViewColumn::new();
             col.set_title(name);
             col.pack_start(&cell_renderer, true);
-            col.add_attribute(&cell_renderer, "text", idx);
+            col.add_attribute(&cell_renderer, "text", col_ty as i32);
+            col.add_attribute(&cell_renderer, "sensitive", Cols::InScope as i32);
             view.append_column(&col);
+            (col, cell_renderer)
         };
 
-        add_text_col("Expression", 1);
-        add_text_col("Value", 2);
-        add_text_col("Type", 3);
+        add_text_col("Expression", Cols::Expr);
+        let (value_col, value_renderer) = add_text_col("Value", Cols::Value);
+        add_text_col("Type", Cols::Type);
+
+        // Highlight values changed in the last update
+        value_renderer.set_property_foreground(Some("red"));
+        value_col.add_attribute(&value_renderer, "foreground-set", Cols::Changed as i32);
 
         let add_expr: AddExprCb = Rc::new(RefCell::new(None));
+        let delete_expr: DeleteExprCb = Rc::new(RefCell::new(None));
+        let set_format: SetFormatCb = Rc::new(RefCell::new(None));
 
         //
         // Connect row expanded signal
@@ -107,33 +199,210 @@ impl ExpressionsW {
         let tree = exprs.clone();
         let get_children: ExprGetChildrenCb = Rc::new(RefCell::new(None));
         let get_children_clone = get_children.clone();
-        view.connect_row_expanded(move |view: &gtk::TreeView, iter, path| {
-            let model = view.get_model().unwrap();
-            let store = model.downcast_ref::<gtk::TreeStore>().unwrap();
-            let name = store.get_value(&iter, 0).get::<String>().unwrap();
-            let expr = store.get_value(&iter, 1).get::<String>().unwrap();
-
+        view.connect_row_expanded(move |_view: &gtk::TreeView, _iter, path| {
             // The TreeStore path of the node should be the same as its path in `ExpressionsW.exprs`
+            // ("load more" rows are always the last row so they don't break this)
             // TODO somehow check this
             let path = path.get_indices();
             assert!(!path.is_empty());
-            let mut node: &ExpressionChild = &tree.borrow()[path[0] as usize];
+            let mut tree = tree.borrow_mut();
+            let mut node: &mut ExpressionChild = &mut tree[path[0] as usize];
             for p in &path[1..] {
-                node = &node.children[*p as usize];
+                node = &mut node.children[*p as usize];
             }
 
-            // If the node doesn't have any children yet ask for it
-            // FIXME: What if it doesn't have children? we keep asking for it every time we expand!
-            if node.children.is_empty() {
-                match *get_children_clone.borrow() {
+            // If we haven't asked for the children yet ask for the first page
+            if !node.children_fetched {
+                node.children_fetched = true;
+                call_get_children(&get_children_clone, &node.full_name, 0, CHILDREN_PAGE_SIZE);
+            }
+        });
+
+        //
+        // Connect row activated signal: load more children when a "load more" row is activated
+        //
+
+        let tree = exprs.clone();
+        let get_children_clone = get_children.clone();
+        let store_clone = store.clone();
+        view.connect_row_activated(move |view, path, _col| {
+            let iter = some!(store_clone.get_iter(path));
+            let name = store_clone
+                .get_value(&iter, Cols::FullName as i32)
+                .get::<String>()
+                .unwrap();
+            if name != "__LOAD_MORE__" {
+                return;
+            }
+            let parent = some!(store_clone.iter_parent(&iter));
+            let parent_name = store_clone
+                .get_value(&parent, Cols::FullName as i32)
+                .get::<String>()
+                .unwrap();
+
+            let from = {
+                let mut tree = tree.borrow_mut();
+                let node = some!(find_node(&mut tree, &parent_name));
+                let from = node.fetched_to;
+                if node.children.len() + CHILDREN_PAGE_SIZE > MAX_CHILDREN {
+                    // Drop the loaded children to keep the tree small
+                    reset_node_children(&store_clone, view, node, true);
+                    node.expand_when_fetched = true;
+                }
+                node.children_fetched = true;
+                from
+            };
+
+            call_get_children(
+                &get_children_clone,
+                &parent_name,
+                from,
+                from + CHILDREN_PAGE_SIZE,
+            );
+        });
+
+        //
+        // Connect "delete" key: delete the selected top-level expression
+        //
+
+        let delete_expr_clone = delete_expr.clone();
+        view.connect_key_press_event(move |view, ev| {
+            if ev.get_keyval() != gdk::enums::key::Delete {
+                return gtk::Inhibit(false);
+            }
+            let (model, iter) = match view.get_selection().get_selected() {
+                None => {
+                    return gtk::Inhibit(false);
+                }
+                Some(selected) => selected,
+            };
+            // Only top-level expressions can be deleted, children are deleted with their parents
+            if model.iter_parent(&iter).is_some() {
+                return gtk::Inhibit(false);
+            }
+            let name = model
+                .get_value(&iter, Cols::FullName as i32)
+                .get::<String>()
+                .unwrap();
+            call_delete_expr(&delete_expr_clone, &name);
+            gtk::Inhibit(true)
+        });
+
+        //
+        // Create the context menu: display formats + delete
+        //
+
+        // Full name of the expression that the menu was opened for
+        let menu_row: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+        let menu = gtk::Menu::new();
+        for format in FORMATS.iter().cloned() {
+            let item = gtk::MenuItem::new_with_label(format_label(format));
+            let menu_row_clone = menu_row.clone();
+            let tree_clone = exprs.clone();
+            let set_format_clone = set_format.clone();
+            item.connect_activate(move |_| {
+                let name = some!(menu_row_clone.borrow().clone());
+                // Update format of the node and its children, then ask for the values in the new
+                // format.
+                let mut names = vec![];
+                {
+                    let mut tree = tree_clone.borrow_mut();
+                    let node = some!(find_node(&mut tree, &name));
+                    set_format_rec(node, format, &mut names);
+                }
+                match *set_format_clone.borrow() {
                     None => {
-                        println!("Can't get children of {}: callback not set", node.full_name);
+                        println!("Can't set format of {}: callback not set", name);
                     }
                     Some(ref cb) => {
-                        cb(&node.full_name);
+                        for name in names {
+                            cb(&name, format);
+                        }
                     }
                 }
+            });
+            menu.append(&item);
+        }
+        menu.append(&gtk::SeparatorMenuItem::new());
+        let range_item = gtk::MenuItem::new_with_label("Show range…");
+        {
+            let menu_row_clone = menu_row.clone();
+            let tree_clone = exprs.clone();
+            let get_children_clone = get_children.clone();
+            let store_clone = store.clone();
+            let view_clone = view.clone();
+            range_item.connect_activate(move |_| {
+                let name = some!(menu_row_clone.borrow().clone());
+                let (n_children, is_map) = {
+                    let mut tree = tree_clone.borrow_mut();
+                    let node = some!(find_node(&mut tree, &name));
+                    (
+                        if node.dynamic {
+                            None
+                        } else {
+                            Some(node.n_children)
+                        },
+                        node.display_hint == Some(DisplayHint::Map),
+                    )
+                };
+                let (mut from, mut to) = some!(ask_range(view_clone.upcast_ref(), n_children));
+                if is_map {
+                    // Every element of a map is two children: a key and a value
+                    from *= 2;
+                    to *= 2;
+                }
+                {
+                    let mut tree = tree_clone.borrow_mut();
+                    let node = some!(find_node(&mut tree, &name));
+                    reset_node_children(&store_clone, &view_clone, node, true);
+                    node.children_fetched = true;
+                    node.expand_when_fetched = true;
+                }
+                call_get_children(&get_children_clone, &name, from, to);
+            });
+        }
+        menu.append(&range_item);
+        let delete_item = gtk::MenuItem::new_with_label("Delete");
+        {
+            let menu_row_clone = menu_row.clone();
+            let delete_expr_clone = delete_expr.clone();
+            delete_item.connect_activate(move |_| {
+                let name = some!(menu_row_clone.borrow().clone());
+                call_delete_expr(&delete_expr_clone, &name);
+            });
+        }
+        menu.append(&delete_item);
+        menu.show_all();
+        menu.set_attach_widget(Some(&view));
+
+        view.connect_button_press_event(move |view, ev| {
+            if ev.get_event_type() != gdk::EventType::ButtonPress || ev.get_button() != 3 {
+                return gtk::Inhibit(false);
+            }
+            let (x, y) = ev.get_position();
+            let path = match view.get_path_at_pos(x as i32, y as i32) {
+                Some((Some(path), _, _, _)) => path,
+                _ => {
+                    return gtk::Inhibit(false);
+                }
+            };
+            let model = view.get_model().unwrap();
+            let iter = model.get_iter(&path).unwrap();
+            let name = model
+                .get_value(&iter, Cols::FullName as i32)
+                .get::<String>()
+                .unwrap();
+            if name == "__PLACEHOLDER__" || name == "__LOAD_MORE__" {
+                return gtk::Inhibit(false);
             }
+            view.get_selection().select_path(&path);
+            // Only top-level expressions can be deleted
+            delete_item.set_sensitive(model.iter_parent(&iter).is_none());
+            range_item.set_sensitive(model.iter_has_child(&iter));
+            *menu_row.borrow_mut() = Some(name);
+            menu.popup_at_pointer(Some(&**ev));
+            gtk::Inhibit(true)
         });
 
         //
@@ -173,6 +442,8 @@ impl ExpressionsW {
             exprs,
             get_children,
             add_expr,
+            delete_expr,
+            set_format,
         }
     }
 
@@ -180,9 +451,10 @@ impl ExpressionsW {
         self.box_.upcast_ref()
     }
 
-    /// Set "get children" callback. Argument to the callback is the full name of the expression,
-    /// e.g. "var1.x.y".
-    pub fn connect_get_children(&mut self, cb: Box<Fn(&str)>) {
+    /// Set "get children" callback. Arguments to the callback are the full name of the
+    /// expression, e.g. "var1.x.y", and the range of the children to get (end exclusive).
+    /// Children should be added with `add_children`.
+    pub fn connect_get_children(&mut self, cb: Box<Fn(&str, usize, usize)>) {
         *self.get_children.borrow_mut() = Some(cb);
     }
 
@@ -190,25 +462,42 @@ impl ExpressionsW {
         *self.add_expr.borrow_mut() = Some(cb);
     }
 
-    /// Add a top-level expression.
-    fn add_top(
-        &mut self,
-        full_name: String,
-        expr: String,
-        value: String,
-        type_: String,
-        has_children: bool,
-    ) {
+    /// Set "delete expression" callback. Argument to the callback is the full name of a top-level
+    /// expression. The expression should be removed from the widget with `remove` after deleting
+    /// the varobj.
+    pub fn connect_delete_expr(&mut self, cb: Box<Fn(&str)>) {
+        *self.delete_expr.borrow_mut() = Some(cb);
+    }
+
+    /// Set "set format" callback. Called for the selected expression and all of its children when
+    /// the user changes the display format. Values in the new format should be set with
+    /// `set_formatted_value`.
+    pub fn connect_set_format(&mut self, cb: Box<Fn(&str, VarFormat)>) {
+        *self.set_format.borrow_mut() = Some(cb);
+    }
+
+    /// Get display format of an expression.
+    pub fn get_format(&self, name: &str) -> Option<VarFormat> {
+        let mut exprs = self.exprs.borrow_mut();
+        find_node(&mut exprs, name).map(|node| node.format)
+    }
+
+    /// Add a top-level expression at the given position, or at the end.
+    fn add_top(&mut self, idx: Option<usize>, expr: String, v: Value) {
+        let has_children = v.n_children != 0 || v.has_more;
+
         // Insert a row
-        let iter = self.store.insert(None /* parent */, -1 /* last */);
+        let position = idx.map_or(-1 /* last */, |idx| idx as i32);
+        let iter = self.store.insert(None /* parent */, position);
         self.store.set(
             &iter,
-            &[0, 1, 2, 3],
+            &[0, 1, 2, 3, 4],
             &[
-                &full_name.to_value(),
+                &v.name.to_value(),
                 &expr.to_value(),
-                &value.to_value(),
-                &type_.to_value(),
+                &v.value.to_value(),
+                &v.type_.to_value(),
+                &true.to_value(),
             ],
         );
 
@@ -221,109 +510,408 @@ impl ExpressionsW {
 
         let node = ExpressionChild {
             iter,
-            full_name: full_name.clone(),
-            name: full_name,
+            full_name: v.name.clone(),
+            name: v.name,
             expr,
-            value: Some(value),
-            type_: Some(type_),
+            value: Some(v.value),
+            type_: Some(v.type_),
+            format: VarFormat::Natural,
+            display_hint: v.display_hint,
+            n_children: v.n_children,
+            dynamic: v.dynamic,
             children: vec![],
+            fetched_to: 0,
+            load_more: None,
+            children_fetched: false,
+            expand_when_fetched: false,
         };
 
-        self.exprs.borrow_mut().push(node);
+        let mut exprs = self.exprs.borrow_mut();
+        match idx {
+            None => exprs.push(node),
+            Some(idx) => exprs.insert(idx, node),
+        }
     }
 
-    pub fn add(
-        &mut self,
-        name: String,
-        expr: String,
-        value: String,
-        type_: String,
-        has_children: bool,
-    ) {
+    /// Add an expression. `expr` is the string shown in the "Expression" column. The parent of
+    /// the expression needs to exist.
+    pub fn add(&mut self, expr: String, v: Value) {
+        let name = v.name.clone();
         let path = name.split('.').collect::<Vec<_>>();
         if path.len() == 1 {
             // Add a top-level expression
-            self.add_top(name, expr, value, type_, has_children);
+            self.add_top(None, expr, v);
         } else {
             // Otherwise start recursing down to find/create the node we're looking for
             let top_level_name = path[0];
             for node in self.exprs.borrow_mut().iter_mut() {
                 if node.name == top_level_name {
-                    return add_child(
-                        &self.store,
-                        node,
-                        &path[1..],
-                        name.clone(), // sigh
-                        expr,
-                        value,
-                        type_,
-                        has_children,
-                    );
+                    return add_child(&self.store, node, &path[1..], expr, v);
                 }
             }
         }
     }
 
-    pub fn update_value(&mut self, name: String, value: String) {
-        let path = name.split('.').collect::<Vec<_>>();
-        if path.len() == 1 {
-            // Add a top-level expression
-            for expr in self.exprs.borrow_mut().iter_mut() {
-                if expr.name == path[0] {
-                    self.store.set(&expr.iter, &[2], &[&value.to_value()]);
-                    expr.value = Some(value);
-                    break;
-                }
+    /// Add children of an expression, e.g. results of a `-var-list-children`. Children of a
+    /// pretty-printed map are rendered as `key: value` rows.
+    ///
+    /// `to` is the end of the requested range and `has_more` is the `has_more` field of the
+    /// `-var-list-children` result, used to decide whether to show a "load more" row.
+    pub fn add_children(&mut self, parent: &str, to: usize, children: Vec<Value>, has_more: bool) {
+        let display_hint = {
+            let mut exprs = self.exprs.borrow_mut();
+            let node = some!(find_node(&mut exprs, parent));
+            // Remove the "load more" row, it's added back to the end below if there are still
+            // more children to load
+            if let Some(iter) = node.load_more.take() {
+                self.store.remove(&iter);
+            }
+            node.display_hint
+        };
+
+        if display_hint == Some(DisplayHint::Map) {
+            // Keys and values alternate. Only add the values, show keys in the expression column.
+            let mut children = children.into_iter();
+            while let (Some(key), Some(value)) = (children.next(), children.next()) {
+                self.add(key.value, value);
             }
         } else {
-            let top_level_name = path[0];
-            for node in self.exprs.borrow_mut().iter_mut() {
-                if node.name == top_level_name {
-                    return update_value(&self.store, node, &path[1..], value);
+            for child in children {
+                let expr = child.expr.clone().unwrap_or_else(|| child.name.clone());
+                self.add(expr, child);
+            }
+        }
+
+        // Remember that we've fetched the children so that we don't ask again on the next
+        // expand, even if the expression doesn't have any children.
+        let expand_path = {
+            let mut exprs = self.exprs.borrow_mut();
+            let node = some!(find_node(&mut exprs, parent));
+            node.children_fetched = true;
+            node.fetched_to = to;
+            if node.children.is_empty() {
+                // Remove the placeholder
+                if let Some(child) = self.store.iter_children(Some(&node.iter)) {
+                    self.store.remove(&child);
                 }
             }
+            let more = if node.dynamic {
+                has_more
+            } else {
+                to < node.n_children
+            };
+            if more {
+                let iter = self.store.insert(&node.iter, -1);
+                self.store.set(
+                    &iter,
+                    &[0, 1, 4],
+                    &[&"__LOAD_MORE__", &"load more…", &true.to_value()],
+                );
+                node.load_more = Some(iter);
+            }
+            if node.expand_when_fetched {
+                node.expand_when_fetched = false;
+                self.store.get_path(&node.iter)
+            } else {
+                None
+            }
+        };
+
+        // Borrow of `exprs` needs to be released here as this triggers "row expanded"
+        if let Some(path) = expand_path {
+            self.view.expand_row(&path, false);
         }
     }
-}
 
-fn update_value(
-    store: &gtk::TreeStore,
-    mut node: &mut ExpressionChild,
-    path: &[&str],
-    value: String,
-) {
-    // Find index of this node in the parent (`node`)
-    let mut node_idx: Option<usize> = None;
-    let p = path[0];
-    for i in 0..node.children.len() {
-        if node.children[i].name == p {
-            if path.len() == 1 {
-                let mut node = &mut node.children[i];
-                store.set(&node.iter, &[2], &[&value.to_value()]);
+    /// Update value of an expression. The value will be highlighted until the next
+    /// `clear_changed` call.
+    pub fn update_value(&mut self, name: String, value: String) {
+        self.set_value(name, value, true);
+    }
+
+    /// Update value of an expression after changing its format. Unlike `update_value` the value
+    /// is not highlighted.
+    pub fn set_formatted_value(&mut self, name: String, value: String) {
+        self.set_value(name, value, false);
+    }
+
+    fn set_value(&mut self, name: String, value: String, changed: bool) {
+        let mut exprs = self.exprs.borrow_mut();
+        match find_node(&mut exprs, &name) {
+            None => {
+                println!("set_value can't find node for name: {:?}", name);
+            }
+            Some(node) => {
+                self.store.set(
+                    &node.iter,
+                    &[Cols::Value as u32, Cols::Changed as u32],
+                    &[&value.to_value(), &changed.to_value()],
+                );
                 node.value = Some(value);
-                return;
-            } else {
-                return update_value(store, &mut node.children[i], &path[1..], value);
             }
         }
     }
 
-    println!("update_value can't find node for path: {:?}", path);
+    /// Grey out (`in_scope == false`) or un-grey an expression and its children.
+    pub fn set_in_scope(&mut self, name: &str, in_scope: bool) {
+        let mut exprs = self.exprs.borrow_mut();
+        if let Some(node) = find_node(&mut exprs, name) {
+            let store = &self.store;
+            store.set_value(&node.iter, Cols::InScope as u32, &in_scope.to_value());
+            for_each_node(&node.children, &mut |child| {
+                store.set_value(&child.iter, Cols::InScope as u32, &in_scope.to_value());
+            });
+        }
+    }
+
+    /// Update type of an expression. Children of the expression are dropped. Returns whether the
+    /// children should be requested again, see `reset_children`.
+    pub fn update_type(
+        &mut self,
+        name: &str,
+        type_: String,
+        n_children: usize,
+        has_more: bool,
+    ) -> bool {
+        {
+            let mut exprs = self.exprs.borrow_mut();
+            if let Some(node) = find_node(&mut exprs, name) {
+                self.store
+                    .set_value(&node.iter, Cols::Type as u32, &type_.to_value());
+                node.type_ = Some(type_);
+            }
+        }
+        self.reset_children(name, n_children, has_more)
+    }
+
+    /// Drop children of an expression. Children are requested again when the node is expanded.
+    /// If the node is currently expanded this returns `true`, in which case the caller should
+    /// request the children and add them with `add_children`. The node is expanded again when
+    /// the children are added.
+    pub fn reset_children(&mut self, name: &str, n_children: usize, has_more: bool) -> bool {
+        let mut exprs = self.exprs.borrow_mut();
+        let node = match find_node(&mut exprs, name) {
+            None => {
+                return false;
+            }
+            Some(node) => node,
+        };
+        node.n_children = n_children;
+        let has_children = n_children != 0 || has_more;
+        let expanded = reset_node_children(&self.store, &self.view, node, has_children);
+        node.expand_when_fetched = expanded && has_children;
+        node.children_fetched = node.expand_when_fetched;
+        node.expand_when_fetched
+    }
+
+    /// Remove highlighting of values changed in the last update.
+    pub fn clear_changed(&mut self) {
+        let store = &self.store;
+        for_each_node(&self.exprs.borrow(), &mut |node| {
+            store.set_value(&node.iter, Cols::Changed as u32, &false.to_value());
+        });
+    }
+
+    /// The expression (as entered by the user) of a top-level expression.
+    pub fn get_expr(&self, name: &str) -> Option<String> {
+        let exprs = self.exprs.borrow();
+        exprs
+            .iter()
+            .find(|node| node.name == name)
+            .map(|node| node.expr.clone())
+    }
+
+    /// Replace a top-level expression with a new varobj of the same expression, keeping its row
+    /// and display format. Used when the old varobj becomes invalid. Returns the display format,
+    /// which should be set for the new varobj (`-var-set-format`) if it's not the default. Returns
+    /// `None` if the expression doesn't exist (e.g. it was removed).
+    pub fn replace(&mut self, name: &str, v: Value) -> Option<VarFormat> {
+        let new_name = v.name.clone();
+        let (idx, expr, format) = {
+            let mut exprs = self.exprs.borrow_mut();
+            let idx = exprs.iter().position(|node| node.name == name)?;
+            let node = exprs.remove(idx);
+            self.store.remove(&node.iter);
+            (idx, node.expr, node.format)
+        };
+        self.add_top(Some(idx), expr, v);
+        let mut exprs = self.exprs.borrow_mut();
+        find_node(&mut exprs, &new_name)?.format = format;
+        Some(format)
+    }
+
+    /// Remove a top-level expression and its children.
+    pub fn remove(&mut self, name: &str) {
+        let mut exprs = self.exprs.borrow_mut();
+        if let Some(idx) = exprs.iter().position(|node| node.name == name) {
+            let node = exprs.remove(idx);
+            self.store.remove(&node.iter);
+        }
+    }
+}
+
+/// Find the node with the given full name, e.g. "var1.x.y".
+fn find_node<'a>(
+    nodes: &'a mut Vec<ExpressionChild>,
+    full_name: &str,
+) -> Option<&'a mut ExpressionChild> {
+    let mut path = full_name.split('.');
+    let top_level_name = path.next()?;
+    let mut node = nodes.iter_mut().find(|node| node.name == top_level_name)?;
+    for p in path {
+        node = node.children.iter_mut().find(|node| node.name == p)?;
+    }
+    Some(node)
+}
+
+/// Drop children of a node. A placeholder is added if `has_children` is true. Returns whether the
+/// node was expanded.
+fn reset_node_children(
+    store: &gtk::TreeStore,
+    view: &gtk::TreeView,
+    node: &mut ExpressionChild,
+    has_children: bool,
+) -> bool {
+    let expanded = store
+        .get_path(&node.iter)
+        .map(|path| view.row_expanded(&path))
+        .unwrap_or(false);
+
+    while let Some(child) = store.iter_children(Some(&node.iter)) {
+        store.remove(&child);
+    }
+    node.children.clear();
+    node.children_fetched = false;
+    node.fetched_to = 0;
+    node.load_more = None;
+
+    // Create a placeholder iter for children if the node still has children
+    if has_children {
+        let iter = store.insert(&node.iter, -1);
+        store.set(&iter, &[0, 1], &[&"__PLACEHOLDER__", &"__PLACEHOLDER__"]);
+    }
+
+    expanded
+}
+
+/// Ask the user for a range of children to show. `n_children` is the number of children, when
+/// known.
+fn ask_range(parent: &gtk::Widget, n_children: Option<usize>) -> Option<(usize, usize)> {
+    let dialog = gtk::Dialog::new();
+    dialog.set_title("Show range");
+    dialog.set_modal(true);
+    if let Some(window) = parent
+        .get_toplevel()
+        .and_then(|w| w.downcast::<gtk::Window>().ok())
+    {
+        dialog.set_transient_for(Some(&window));
+    }
+
+    // grid -> [ [ from label, from spin button ],
+    //           [ to label, to spin button ] ]
+    let max = n_children
+        .map(|n| n as f64)
+        .unwrap_or(f64::from(std::i32::MAX));
+    let grid = gtk::Grid::new();
+    let from_label = gtk::Label::new("From");
+    let from_spin = gtk::SpinButton::new_with_range(0.0, max, 1.0);
+    let to_label = gtk::Label::new("To");
+    let to_spin = gtk::SpinButton::new_with_range(0.0, max, 1.0);
+    to_spin.set_value((CHILDREN_PAGE_SIZE as f64).min(max));
+    grid.attach(&from_label, 0, 0, 1, 1);
+    grid.attach(&from_spin, 1, 0, 1, 1);
+    grid.attach(&to_label, 0, 1, 1, 1);
+    grid.attach(&to_spin, 1, 1, 1, 1);
+    dialog.get_content_area().pack_start(&grid, true, true, 0);
+
+    dialog.add_button("Cancel", gtk::ResponseType::Cancel.into());
+    dialog.add_button("Show", gtk::ResponseType::Ok.into());
+    dialog.show_all();
+
+    let response = gtk::ResponseType::from(dialog.run());
+    let from = from_spin.get_value_as_int() as usize;
+    let to = to_spin.get_value_as_int() as usize;
+    dialog.destroy();
+
+    if response != gtk::ResponseType::Ok || to <= from {
+        return None;
+    }
+    // Keep the tree small
+    Some((from, to.min(from + MAX_CHILDREN)))
+}
+
+fn call_get_children(cb: &ExprGetChildrenCb, name: &str, from: usize, to: usize) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't get children of {}: callback not set", name);
+        }
+        Some(ref cb) => {
+            cb(name, from, to);
+        }
+    }
+}
+
+/// Set format of a node and its children. Full names of updated nodes with values affected by the
+/// format are pushed to `names`.
+fn set_format_rec(node: &mut ExpressionChild, format: VarFormat, names: &mut Vec<String>) {
+    node.format = format;
+    if node
+        .value
+        .as_ref()
+        .map_or(false, |value| format_affects(value))
+    {
+        names.push(node.full_name.clone());
+    }
+    for child in node.children.iter_mut() {
+        set_format_rec(child, format, names);
+    }
+}
+
+fn call_delete_expr(cb: &DeleteExprCb, name: &str) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't delete {}: callback not set", name);
+        }
+        Some(ref cb) => {
+            cb(name);
+        }
+    }
+}
+
+/// Whether display formats affect a value. Values of structs, unions (`{...}`) and arrays (`[3]`)
+/// are not formatted, only their children are.
+pub fn format_affects(value: &str) -> bool {
+    !(value == "{...}" || (value.starts_with('[') && value.ends_with(']')))
+}
+
+fn format_label(format: VarFormat) -> &'static str {
+    match format {
+        VarFormat::Natural => "Natural",
+        VarFormat::Hexadecimal => "Hexadecimal",
+        VarFormat::Binary => "Binary",
+        VarFormat::Octal => "Octal",
+        VarFormat::Decimal => "Decimal",
+    }
+}
+
+/// Call the function on all nodes, recursively.
+fn for_each_node<F: FnMut(&ExpressionChild)>(nodes: &[ExpressionChild], f: &mut F) {
+    for node in nodes {
+        f(node);
+        for_each_node(&node.children, f);
+    }
 }
 
 /// Add a child expression.
 fn add_child(
     store: &gtk::TreeStore,
     // Searching for the parent in this node.
-    mut node: &mut ExpressionChild,
+    node: &mut ExpressionChild,
     // Path of the child node. When this has one entry `node` is the parent of the child. Can't be
     // empty.
-    path: &[&str],     // ["y", "z"]
-    full_name: String, // "x.y.z"
+    path: &[&str], // ["y", "z"]
     expr: String,
-    value: String,
-    type_: String,
-    has_children: bool,
+    v: Value, // v.name: "x.y.z"
 ) {
     // Find index of this node in the parent (`node`)
     let mut node_idx: Option<usize> = None;
@@ -335,6 +923,8 @@ fn add_child(
         }
     }
 
+    let has_children = v.n_children != 0 || v.has_more;
+
     match node_idx {
         None => {
             // We don't have a node for the child yet, create it.
@@ -342,34 +932,31 @@ fn add_child(
             // this node needs to exist.
             assert!(path.len() == 1);
 
-            // We should have at least one "placeholder" in as a child in this node, so this should
-            // work
-            let mut store_path = store.get_path(&node.iter).unwrap();
-            store_path.down();
-
             let insert = |iter| {
                 store.set(
                     iter,
-                    &[0, 1, 2, 3],
+                    &[0, 1, 2, 3, 4],
                     &[
-                        &full_name.to_value(),
+                        &v.name.to_value(),
                         &expr.to_value(),
-                        &value.to_value(),
-                        &type_.to_value(),
+                        &v.value.to_value(),
+                        &v.type_.to_value(),
+                        &true.to_value(),
                     ],
                 );
             };
 
-            // Update the placeholder if it exists
-            let mut iter = store.get_iter(&store_path).unwrap();
-            let iter_name = store.get_value(&iter, 0).get::<String>().unwrap();
-            if iter_name.as_str() == "__PLACEHOLDER__" {
-                insert(&iter);
-            } else {
-                // Otherwise insert new row to the end
-                iter = store.insert(&node.iter, -1);
-                insert(&iter);
-            }
+            // Update the placeholder if it exists, otherwise insert new row to the end
+            let iter = match store.iter_children(Some(&node.iter)) {
+                Some(ref iter)
+                    if store.get_value(iter, 0).get::<String>().unwrap().as_str()
+                        == "__PLACEHOLDER__" =>
+                {
+                    iter.clone()
+                }
+                _ => store.insert(&node.iter, -1),
+            };
+            insert(&iter);
 
             // Create a placeholder iter for children if the new node has children
             if has_children {
@@ -378,14 +965,23 @@ fn add_child(
             }
 
             // Insert a new node in the model
+            let format = node.format;
             node.children.push(ExpressionChild {
                 iter,
-                full_name,
+                full_name: v.name,
                 name: path[0].to_owned(),
                 expr,
-                value: Some(value),
-                type_: Some(type_),
+                value: Some(v.value),
+                type_: Some(v.type_),
+                format,
+                display_hint: v.display_hint,
+                n_children: v.n_children,
+                dynamic: v.dynamic,
                 children: vec![],
+                fetched_to: 0,
+                load_more: None,
+                children_fetched: false,
+                expand_when_fetched: false,
             });
         }
         Some(node_idx) => {
@@ -395,30 +991,24 @@ fn add_child(
                     &node.children[node_idx].iter,
                     &[0, 1, 2, 3],
                     &[
-                        &full_name.to_value(),
+                        &v.name.to_value(),
                         &expr.to_value(),
-                        &value.to_value(),
-                        &type_.to_value(),
+                        &v.value.to_value(),
+                        &v.type_.to_value(),
                     ],
                 );
                 // Update the node at node_idx
-                let mut node = &mut node.children[node_idx];
-                node.full_name = full_name;
+                let node = &mut node.children[node_idx];
+                node.full_name = v.name;
                 node.name = path[0].to_owned();
                 node.expr = expr;
-                node.value = Some(value);
-                node.type_ = Some(type_);
+                node.value = Some(v.value);
+                node.type_ = Some(v.type_);
+                node.display_hint = v.display_hint;
+                node.n_children = v.n_children;
+                node.dynamic = v.dynamic;
             } else {
-                add_child(
-                    store,
-                    &mut node.children[node_idx],
-                    &path[1..],
-                    full_name,
-                    expr,
-                    value,
-                    type_,
-                    has_children,
-                )
+                add_child(store, &mut node.children[node_idx], &path[1..], expr, v)
             }
         }
     }
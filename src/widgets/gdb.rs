@@ -119,6 +119,10 @@ impl GdbW {
         self.entry.set_sensitive(true);
     }
 
+    pub fn enter_disconnected_state(&self) {
+        self.entry.set_sensitive(false);
+    }
+
     fn should_scroll_to_bottom(&self) -> bool {
         // If the user adjusted the scroll bar then we don't scroll to the end. Otherwise we do.
         // Code taken from https://mail.gnome.org/archives/gtk-list/2011-August/msg00034.html and I
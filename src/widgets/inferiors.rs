@@ -0,0 +1,224 @@
+//! A `TreeView` for rendering inferiors (processes), with settings for following forks.
+//!
+//! By default GDB only debugs the parent after a `fork()` and detaches from the child. With
+//! "follow fork mode" set to "child" GDB switches to the child instead, and with "detach on fork"
+//! disabled both processes stay under the debugger as separate inferiors.
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+
+use crate::types::{Event, Inferior};
+
+pub struct InferiorsW {
+    // box -> [ scrolled -> tree view, box -> [ label, combo box, check button ] ]
+    widget: gtk::Box,
+    model: gtk::ListStore,
+    follow_fork_mode: gtk::ComboBoxText,
+    detach_on_fork: gtk::CheckButton,
+    /// Set while updating settings with values from GDB, to avoid sending them back.
+    updating: Rc<Cell<bool>>,
+}
+
+/// Number of columns
+const NUM_COLS: usize = 4;
+
+/// Column indices for cell renderers
+#[repr(i32)]
+enum Cols {
+    // Thread group id, e.g. "i1"
+    Id = 0,
+    Pid,
+    Executable,
+    // "live", "exited with code 1" etc.
+    Status,
+}
+
+/// Column types for the list store
+static COL_TYPES: [gtk::Type; NUM_COLS] = [
+    gtk::Type::String, // id
+    gtk::Type::String, // pid
+    gtk::Type::String, // executable
+    gtk::Type::String, // status
+];
+
+/// Column indices for when inserting rows into the list store
+static COL_INDICES: [u32; NUM_COLS] = [0, 1, 2, 3];
+
+/// Values of GDB's `follow-fork-mode` setting.
+static FOLLOW_FORK_MODES: [&str; 2] = ["parent", "child"];
+
+impl InferiorsW {
+    pub fn new() -> InferiorsW {
+        let model = gtk::ListStore::new(&COL_TYPES);
+
+        let view = gtk::TreeView::new_with_model(&model);
+        view.set_vexpand(true);
+        view.set_hexpand(true);
+        view.set_headers_visible(true);
+
+        let add_col = |title: &'static str, col_ty: Cols| {
+            let renderer = gtk::CellRendererText::new();
+            let column = gtk::TreeViewColumn::new();
+            column.pack_start(&renderer, true);
+            column.set_title(title);
+            column.set_resizable(true);
+            column.add_attribute(&renderer, "text", col_ty as i32);
+            view.append_column(&column);
+        };
+
+        add_col("Inferior", Cols::Id);
+        add_col("Pid", Cols::Pid);
+        add_col("Executable", Cols::Executable);
+        add_col("Status", Cols::Status);
+
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+        scrolled.add(&view);
+
+        //
+        // Fork settings
+        //
+
+        let follow_fork_mode = gtk::ComboBoxText::new();
+        for mode in FOLLOW_FORK_MODES.iter() {
+            follow_fork_mode.append(Some(mode), mode);
+        }
+        follow_fork_mode.set_active_id(Some(FOLLOW_FORK_MODES[0]));
+        follow_fork_mode.set_tooltip_text("Which process to debug after a fork");
+
+        let detach_on_fork = gtk::CheckButton::new_with_label("Detach on fork");
+        detach_on_fork.set_active(true);
+        detach_on_fork.set_tooltip_text(
+            "Detach from the process that is not followed after a fork. When disabled both \
+             processes are debugged.",
+        );
+
+        let settings = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        settings.pack_start(&gtk::Label::new(Some("Follow fork:")), false, false, 0);
+        settings.pack_start(&follow_fork_mode, false, false, 0);
+        settings.pack_start(&detach_on_fork, false, false, 0);
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        widget.pack_start(&scrolled, true, true, 0);
+        widget.pack_start(&settings, false, false, 0);
+
+        InferiorsW {
+            widget,
+            model,
+            follow_fork_mode,
+            detach_on_fork,
+            updating: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Set "follow fork mode changed" callback. Argument is the new value of GDB's
+    /// `follow-fork-mode` setting: "parent" or "child".
+    pub fn connect_follow_fork_mode_changed(&self, cb: Box<Fn(&str)>) {
+        let updating = self.updating.clone();
+        self.follow_fork_mode.connect_changed(move |combo| {
+            if updating.get() {
+                return;
+            }
+            if let Some(mode) = combo.get_active_id() {
+                cb(mode.as_str());
+            }
+        });
+    }
+
+    /// Set "detach on fork toggled" callback. Argument is the new value of GDB's `detach-on-fork`
+    /// setting.
+    pub fn connect_detach_on_fork_toggled(&self, cb: Box<Fn(bool)>) {
+        let updating = self.updating.clone();
+        self.detach_on_fork.connect_toggled(move |button| {
+            if !updating.get() {
+                cb(button.get_active());
+            }
+        });
+    }
+
+    /// Show the current value of `follow-fork-mode` (e.g. when set in .gdbinit). Does not call
+    /// the "follow fork mode changed" callback.
+    pub fn set_follow_fork_mode(&self, mode: &str) {
+        self.updating.set(true);
+        self.follow_fork_mode.set_active_id(Some(mode));
+        self.updating.set(false);
+    }
+
+    /// Show the current value of `detach-on-fork`. Does not call the "detach on fork toggled"
+    /// callback.
+    pub fn set_detach_on_fork(&self, detach: bool) {
+        self.updating.set(true);
+        self.detach_on_fork.set_active(detach);
+        self.updating.set(false);
+    }
+
+    /// Update fork settings when they're changed in the console.
+    pub fn handle_event(&self, event: &Event) {
+        if let Event::CmdParamChanged { param, value } = event {
+            match param.as_str() {
+                "follow-fork-mode" => self.set_follow_fork_mode(value),
+                "detach-on-fork" => self.set_detach_on_fork(value == "on"),
+                _ => {}
+            }
+        }
+    }
+
+    /// Update the inferior if it exists, otherwise add a new one.
+    pub fn add_or_update_inferior(&self, inferior: &Inferior) {
+        let iter = match self.find_inferior(&inferior.id) {
+            Some(iter) => iter,
+            None => self.model.append(),
+        };
+        let values: [&dyn gtk::ToValue; NUM_COLS] = [
+            &inferior.id,
+            &inferior.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+            &inferior.executable.clone().unwrap_or_default(),
+            &inferior_status(inferior),
+        ];
+        self.model.set(&iter, &COL_INDICES, &values);
+    }
+
+    /// Remove an inferior, e.g. after `remove-inferiors`.
+    pub fn remove_inferior(&self, id: &str) {
+        if let Some(iter) = self.find_inferior(id) {
+            self.model.remove(&iter);
+        }
+    }
+
+    pub fn clear(&self) {
+        self.model.clear();
+    }
+
+    fn find_inferior(&self, id: &str) -> Option<gtk::TreeIter> {
+        let iter = self.model.get_iter_first()?;
+        loop {
+            let id_ = self
+                .model
+                .get_value(&iter, Cols::Id as i32)
+                .get::<String>()
+                .unwrap();
+            if id_ == id {
+                return Some(iter);
+            }
+            if !self.model.iter_next(&iter) {
+                return None;
+            }
+        }
+    }
+}
+
+fn inferior_status(inferior: &Inferior) -> String {
+    match (inferior.pid, inferior.exit_code) {
+        // Process exists, may be running or stopped
+        (Some(_), _) => "live".to_string(),
+        (None, Some(exit_code)) => format!("exited with code {}", exit_code),
+        (None, None) => "not started".to_string(),
+    }
+}
@@ -0,0 +1,297 @@
+//! A `TreeView` for rendering shared libraries loaded by inferiors.
+//!
+//! The context menu of a library has actions for loading symbols of the library (e.g. when
+//! `auto-solib-add` is off) and for stopping when the library is loaded (a `catch load`
+//! catchpoint), which is useful for setting breakpoints in `dlopen`ed libraries.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+
+use crate::types::{Event, Library};
+
+/// Type of the reference for callbacks that take a library path.
+type LibraryCb = Rc<RefCell<Option<Box<Fn(&str)>>>>;
+
+pub struct LibrariesW {
+    // scrolled -> tree view
+    widget: gtk::ScrolledWindow,
+    model: gtk::ListStore,
+    load_symbols: LibraryCb,
+    break_on_load: LibraryCb,
+}
+
+/// Number of columns
+const NUM_COLS: usize = 6;
+
+/// Column indices for cell renderers
+#[repr(i32)]
+enum Cols {
+    // Library id, not rendered
+    Id = 0,
+    // Thread group, e.g. "i1". Empty when the library is loaded in all inferiors.
+    Inferior,
+    // Path on the target
+    Path,
+    // e.g. "0x00007ffff7dc5630-0x00007ffff7f3a27d"
+    Range,
+    Symbols,
+    // Host path and all address ranges
+    Tooltip,
+}
+
+/// Column types for the list store
+static COL_TYPES: [gtk::Type; NUM_COLS] = [
+    gtk::Type::String, // id
+    gtk::Type::String, // inferior
+    gtk::Type::String, // path
+    gtk::Type::String, // address range
+    gtk::Type::Bool,   // symbols loaded
+    gtk::Type::String, // tooltip
+];
+
+/// Column indices for when inserting rows into the list store
+static COL_INDICES: [u32; NUM_COLS] = [0, 1, 2, 3, 4, 5];
+
+impl LibrariesW {
+    pub fn new() -> LibrariesW {
+        let model = gtk::ListStore::new(&COL_TYPES);
+
+        let view = gtk::TreeView::new_with_model(&model);
+        view.set_vexpand(true);
+        view.set_hexpand(true);
+        view.set_headers_visible(true);
+        view.set_tooltip_column(Cols::Tooltip as i32);
+
+        let add_col = |title: &'static str, col_ty: Cols| {
+            let renderer = gtk::CellRendererText::new();
+            let column = gtk::TreeViewColumn::new();
+            column.pack_start(&renderer, true);
+            column.set_title(title);
+            column.set_resizable(true);
+            column.add_attribute(&renderer, "text", col_ty as i32);
+            view.append_column(&column);
+        };
+
+        add_col("Inferior", Cols::Inferior);
+        add_col("Library", Cols::Path);
+        add_col("Address", Cols::Range);
+
+        // Symbols column, render as a (read-only) toggle
+        let renderer = gtk::CellRendererToggle::new();
+        renderer.set_activatable(false);
+        let column = gtk::TreeViewColumn::new();
+        column.pack_start(&renderer, true);
+        column.set_title("Symbols");
+        column.add_attribute(&renderer, "active", Cols::Symbols as i32);
+        view.append_column(&column);
+
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+        scrolled.add(&view);
+
+        let load_symbols: LibraryCb = Rc::new(RefCell::new(None));
+        let break_on_load: LibraryCb = Rc::new(RefCell::new(None));
+
+        //
+        // Create the context menu
+        //
+
+        // Path of the library that the menu was opened for
+        let menu_row: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+        let menu = gtk::Menu::new();
+        let load_symbols_item = gtk::MenuItem::new_with_label("Load symbols");
+        {
+            let menu_row = menu_row.clone();
+            let load_symbols = load_symbols.clone();
+            load_symbols_item.connect_activate(move |_| {
+                if let Some(ref path) = *menu_row.borrow() {
+                    call_library_cb(&load_symbols, "load symbols of", path);
+                }
+            });
+        }
+        menu.append(&load_symbols_item);
+        let break_on_load_item = gtk::MenuItem::new_with_label("Break when this library loads");
+        {
+            let menu_row = menu_row.clone();
+            let break_on_load = break_on_load.clone();
+            break_on_load_item.connect_activate(move |_| {
+                if let Some(ref path) = *menu_row.borrow() {
+                    call_library_cb(&break_on_load, "break on load of", path);
+                }
+            });
+        }
+        menu.append(&break_on_load_item);
+        menu.show_all();
+        menu.set_attach_widget(Some(&view));
+
+        view.connect_button_press_event(move |view, ev| {
+            if ev.get_event_type() != gdk::EventType::ButtonPress || ev.get_button() != 3 {
+                return gtk::Inhibit(false);
+            }
+            let (x, y) = ev.get_position();
+            let path = match view.get_path_at_pos(x as i32, y as i32) {
+                Some((Some(path), _, _, _)) => path,
+                _ => {
+                    return gtk::Inhibit(false);
+                }
+            };
+            let model = view.get_model().unwrap();
+            let iter = model.get_iter(&path).unwrap();
+            let library_path = model
+                .get_value(&iter, Cols::Path as i32)
+                .get::<String>()
+                .unwrap();
+            let symbols_loaded = model
+                .get_value(&iter, Cols::Symbols as i32)
+                .get::<bool>()
+                .unwrap();
+            view.get_selection().select_path(&path);
+            load_symbols_item.set_sensitive(!symbols_loaded);
+            *menu_row.borrow_mut() = Some(library_path);
+            menu.popup_at_pointer(Some(&**ev));
+            gtk::Inhibit(true)
+        });
+
+        LibrariesW {
+            widget: scrolled,
+            model,
+            load_symbols,
+            break_on_load,
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Set "load symbols" callback. Argument is path of the library on the target. Library should
+    /// be updated with `add_or_update_library` after loading the symbols.
+    pub fn connect_load_symbols(&mut self, cb: Box<Fn(&str)>) {
+        *self.load_symbols.borrow_mut() = Some(cb);
+    }
+
+    /// Set "break on load" callback. Argument is path of the library on the target.
+    pub fn connect_break_on_load(&mut self, cb: Box<Fn(&str)>) {
+        *self.break_on_load.borrow_mut() = Some(cb);
+    }
+
+    pub fn handle_event(&self, event: &Event) {
+        match event {
+            Event::LibraryLoaded(library) => {
+                self.add_or_update_library(library);
+            }
+            Event::LibraryUnloaded(library) => {
+                if let Some(iter) = self.find_library(library) {
+                    self.model.remove(&iter);
+                }
+            }
+            Event::ThreadGroupExited { id, .. } => {
+                // Libraries are unloaded without `library-unloaded` records when the process
+                // exits
+                self.remove_inferior_libraries(id);
+            }
+            _ => {}
+        }
+    }
+
+    /// Update the library if it exists, otherwise add a new one.
+    pub fn add_or_update_library(&self, library: &Library) {
+        let iter = match self.find_library(library) {
+            Some(iter) => iter,
+            None => self.model.append(),
+        };
+        let range = match library.ranges.len() {
+            0 => "".to_string(),
+            n => {
+                let (ref from, ref to) = library.ranges[0];
+                if n == 1 {
+                    format!("{}-{}", from, to)
+                } else {
+                    format!("{}-{} (+{} ranges)", from, to, n - 1)
+                }
+            }
+        };
+        // Tooltips are markup
+        let mut tooltip = glib::markup_escape_text(&library.host_name).to_string();
+        for (from, to) in &library.ranges {
+            tooltip.push_str(&format!("\n{}-{}", from, to));
+        }
+        let values: [&dyn gtk::ToValue; NUM_COLS] = [
+            &library.id,
+            &library.thread_group.clone().unwrap_or_default(),
+            &library.target_name,
+            &range,
+            &library.symbols_loaded,
+            &tooltip,
+        ];
+        self.model.set(&iter, &COL_INDICES, &values);
+    }
+
+    pub fn clear(&self) {
+        self.model.clear();
+    }
+
+    /// Libraries are identified by id and thread group, as inferiors load libraries separately.
+    fn find_library(&self, library: &Library) -> Option<gtk::TreeIter> {
+        let group = library.thread_group.clone().unwrap_or_default();
+        let iter = self.model.get_iter_first()?;
+        loop {
+            let id = self
+                .model
+                .get_value(&iter, Cols::Id as i32)
+                .get::<String>()
+                .unwrap();
+            let group_ = self
+                .model
+                .get_value(&iter, Cols::Inferior as i32)
+                .get::<String>()
+                .unwrap();
+            if id == library.id && group_ == group {
+                return Some(iter);
+            }
+            if !self.model.iter_next(&iter) {
+                return None;
+            }
+        }
+    }
+
+    fn remove_inferior_libraries(&self, group: &str) {
+        let iter = match self.model.get_iter_first() {
+            None => {
+                return;
+            }
+            Some(iter) => iter,
+        };
+        loop {
+            let group_ = self
+                .model
+                .get_value(&iter, Cols::Inferior as i32)
+                .get::<String>()
+                .unwrap();
+            // `remove` moves the iterator to the next row
+            let valid = if group_ == group {
+                self.model.remove(&iter)
+            } else {
+                self.model.iter_next(&iter)
+            };
+            if !valid {
+                return;
+            }
+        }
+    }
+}
+
+fn call_library_cb(cb: &LibraryCb, action: &str, path: &str) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't {} {}: callback not set", action, path);
+        }
+        Some(ref cb) => {
+            cb(path);
+        }
+    }
+}
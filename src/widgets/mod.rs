@@ -1,15 +1,36 @@
 pub mod backtrace;
 mod breakpoint_add;
 pub mod breakpoints;
+mod catchpoint_add;
+pub mod dprintf_log;
+pub mod errors;
 pub mod expressions;
 pub mod gdb;
+pub mod inferiors;
+pub mod libraries;
+pub mod parallel_stacks;
+pub mod signals;
+pub mod source;
+pub mod source_files;
+pub mod status;
+pub mod symbols;
 pub mod threads;
 mod watchpoint_add;
 pub mod watchpoints;
 
 pub use backtrace::BacktraceW;
 pub use breakpoints::BreakpointsW;
+pub use dprintf_log::DprintfLogW;
+pub use errors::ErrorsW;
 pub use expressions::ExpressionsW;
 pub use gdb::GdbW;
+pub use inferiors::InferiorsW;
+pub use libraries::LibrariesW;
+pub use parallel_stacks::ParallelStacksW;
+pub use signals::SignalsW;
+pub use source::SourceW;
+pub use source_files::SourceFilesW;
+pub use status::StatusW;
+pub use symbols::SymbolsW;
 pub use threads::ThreadsW;
 pub use watchpoints::WatchpointsW;
@@ -0,0 +1,147 @@
+//! A `TreeView` that shows threads grouped by common call stack prefixes ("parallel stacks").
+//!
+//! Each node is a sequence of frames shared by a group of threads, outermost frames at the top.
+//! Expanding a node shows the thread ids of the group, followed by the nodes where the stacks of
+//! the group diverge.
+
+use gtk::prelude::*;
+
+use crate::stack_groups::{group_stacks, StackNode};
+use crate::types::{Backtrace, Frame};
+
+pub struct ParallelStacksW {
+    // scrolled -> tree view
+    widget: gtk::ScrolledWindow,
+    model: gtk::TreeStore,
+    view: gtk::TreeView,
+}
+
+/// Column indices for cell renderers
+#[repr(i32)]
+enum Cols {
+    // Innermost function of the node, or thread ids
+    Func = 0,
+    // e.g. "rts/RtsMessages.c:186"
+    Loc,
+    // Number of threads, e.g. "200 threads"
+    Threads,
+    // All frames of the node
+    Tooltip,
+}
+
+/// Column types for the tree store
+static COL_TYPES: [gtk::Type; 4] = [
+    gtk::Type::String, // function
+    gtk::Type::String, // location
+    gtk::Type::String, // number of threads
+    gtk::Type::String, // tooltip
+];
+
+/// Column indices for when inserting rows into the tree store
+static COL_INDICES: [u32; 4] = [0, 1, 2, 3];
+
+impl ParallelStacksW {
+    pub fn new() -> ParallelStacksW {
+        let model = gtk::TreeStore::new(&COL_TYPES);
+
+        let view = gtk::TreeView::new_with_model(&model);
+        view.set_vexpand(true);
+        view.set_hexpand(true);
+        view.set_tooltip_column(Cols::Tooltip as i32);
+
+        let add_text_renderer_col = |title: &'static str, col_ty: Cols| {
+            let renderer = gtk::CellRendererText::new();
+            let column = gtk::TreeViewColumn::new();
+            column.pack_start(&renderer, true);
+            column.set_title(title);
+            column.set_resizable(true);
+            column.add_attribute(&renderer, "text", col_ty as i32);
+            view.append_column(&column);
+        };
+
+        add_text_renderer_col("Function", Cols::Func);
+        add_text_renderer_col("Location", Cols::Loc);
+        add_text_renderer_col("Threads", Cols::Threads);
+
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+        scrolled.add(&view);
+
+        ParallelStacksW {
+            widget: scrolled,
+            model,
+            view,
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Render the given stacks, dropping the old ones. Backtraces are innermost frame first, as
+    /// returned by GDB.
+    pub fn set_stacks(&self, stacks: &[(i32, &Backtrace)]) {
+        self.model.clear();
+        for node in group_stacks(stacks) {
+            add_node(&self.model, None, &node);
+        }
+        self.view.expand_all();
+    }
+
+    pub fn clear(&self) {
+        self.model.clear();
+    }
+}
+
+fn add_node(model: &gtk::TreeStore, parent: Option<&gtk::TreeIter>, node: &StackNode) {
+    // Show the innermost frame of the node, other frames are in the tooltip
+    let innermost = node.frames.last().unwrap();
+    let func = if node.frames.len() > 1 {
+        format!("{} (+{} frames)", innermost.func, node.frames.len() - 1)
+    } else {
+        innermost.func.clone()
+    };
+    let threads = if node.threads.len() == 1 {
+        "1 thread".to_string()
+    } else {
+        format!("{} threads", node.threads.len())
+    };
+    // Tooltips are markup, C++ and Rust function names have '<' and '&'
+    let tooltip = node
+        .frames
+        .iter()
+        .rev()
+        .map(|frame| {
+            glib::markup_escape_text(&format!("{} {}", frame.func, frame_loc(frame))).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let values: [&dyn gtk::ToValue; 4] = [&func, &frame_loc(innermost), &threads, &tooltip];
+    let iter = model.insert(parent, -1);
+    model.set(&iter, &COL_INDICES, &values);
+
+    // Thread ids of the group
+    let thread_ids = node
+        .threads
+        .iter()
+        .map(|thread_id| format!("#{}", thread_id))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let thread_ids = format!("Threads: {}", thread_ids);
+    let values: [&dyn gtk::ToValue; 4] = [&thread_ids, &"", &"", &thread_ids];
+    let thread_ids_iter = model.insert(&iter, -1);
+    model.set(&thread_ids_iter, &COL_INDICES, &values);
+
+    for child in &node.children {
+        add_node(model, Some(&iter), child);
+    }
+}
+
+fn frame_loc(frame: &Frame) -> String {
+    match (&frame.file, &frame.line, &frame.from) {
+        (Some(file), Some(line), _) => format!("{}:{}", file, line),
+        (_, _, Some(from)) => from.clone(),
+        _ => frame.addr.clone(),
+    }
+}
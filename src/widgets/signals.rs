@@ -0,0 +1,310 @@
+//! A `TreeView` for configuring how GDB handles signals (the `handle` command), with a banner for
+//! the last received signal.
+//!
+//! When the program stops because of a signal the banner shows the signal and offers continuing
+//! with the signal delivered to the program or discarded, regardless of the "pass" setting.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+
+use crate::types::{Event, Signal, StopReason};
+
+/// Type of the reference for the "handle signal" callback.
+type HandleSignalCb = Rc<RefCell<Option<Box<Fn(&str, &str)>>>>;
+
+/// Type of the reference for the "continue with signal" callback.
+type ContinueCb = Rc<RefCell<Option<Box<Fn(Option<&str>)>>>>;
+
+pub struct SignalsW {
+    // box -> [ info bar -> [ label, "deliver" button, "discard" button ],
+    //          scrolled -> tree view,
+    //          "refresh" button ]
+    widget: gtk::Box,
+    model: gtk::ListStore,
+    banner: gtk::InfoBar,
+    banner_label: gtk::Label,
+    refresh: gtk::Button,
+    /// Name of the signal the program stopped with, if it's currently stopped because of a
+    /// signal.
+    received: Rc<RefCell<Option<String>>>,
+    handle_signal: HandleSignalCb,
+    continue_with_signal: ContinueCb,
+}
+
+/// Number of columns
+const NUM_COLS: usize = 6;
+
+/// Column indices for cell renderers
+#[repr(i32)]
+enum Cols {
+    Name = 0,
+    Stop,
+    Print,
+    Pass,
+    Description,
+    // Whether the program stopped because of this signal. Rendered in bold.
+    Received,
+}
+
+/// Column types for the list store
+static COL_TYPES: [gtk::Type; NUM_COLS] = [
+    gtk::Type::String, // name
+    gtk::Type::Bool,   // stop
+    gtk::Type::Bool,   // print
+    gtk::Type::Bool,   // pass
+    gtk::Type::String, // description
+    gtk::Type::Bool,   // received
+];
+
+/// Column indices for when inserting rows into the list store
+static COL_INDICES: [u32; NUM_COLS] = [0, 1, 2, 3, 4, 5];
+
+impl SignalsW {
+    pub fn new() -> SignalsW {
+        let model = gtk::ListStore::new(&COL_TYPES);
+
+        let view = gtk::TreeView::new_with_model(&model);
+        view.set_vexpand(true);
+        view.set_hexpand(true);
+        view.set_headers_visible(true);
+        view.set_search_column(Cols::Name as i32);
+
+        let handle_signal: HandleSignalCb = Rc::new(RefCell::new(None));
+        let continue_with_signal: ContinueCb = Rc::new(RefCell::new(None));
+
+        let add_text_col = |title: &'static str, col_ty: Cols| {
+            let renderer = gtk::CellRendererText::new();
+            renderer.set_property_weight(700);
+            let column = gtk::TreeViewColumn::new();
+            column.pack_start(&renderer, true);
+            column.set_title(title);
+            column.set_resizable(true);
+            column.add_attribute(&renderer, "text", col_ty as i32);
+            column.add_attribute(&renderer, "weight-set", Cols::Received as i32);
+            view.append_column(&column);
+        };
+
+        // Toggles send `handle` commands, rows are updated with the output of the command
+        let add_toggle_col =
+            |title: &'static str, col_ty: Cols, on: &'static str, off: &'static str| {
+                let col = col_ty as i32;
+                let renderer = gtk::CellRendererToggle::new();
+                let column = gtk::TreeViewColumn::new();
+                column.pack_start(&renderer, true);
+                column.set_title(title);
+                column.add_attribute(&renderer, "active", col);
+                view.append_column(&column);
+
+                let model = model.clone();
+                let handle_signal = handle_signal.clone();
+                renderer.connect_toggled(move |_, path| {
+                    let iter = model.get_iter(&path).unwrap();
+                    let name = model
+                        .get_value(&iter, Cols::Name as i32)
+                        .get::<String>()
+                        .unwrap();
+                    let active = model.get_value(&iter, col).get::<bool>().unwrap();
+                    match *handle_signal.borrow() {
+                        None => {
+                            println!("Can't handle signal {}: callback not set", name);
+                        }
+                        Some(ref cb) => {
+                            cb(&name, if active { off } else { on });
+                        }
+                    }
+                });
+            };
+
+        add_text_col("Signal", Cols::Name);
+        add_toggle_col("Stop", Cols::Stop, "stop", "nostop");
+        add_toggle_col("Print", Cols::Print, "print", "noprint");
+        add_toggle_col("Pass", Cols::Pass, "pass", "nopass");
+        add_text_col("Description", Cols::Description);
+
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+        scrolled.add(&view);
+
+        //
+        // Create the banner for received signals
+        //
+
+        let banner = gtk::InfoBar::new();
+        banner.set_message_type(gtk::MessageType::Warning);
+        // Only shown when stopped because of a signal
+        banner.set_no_show_all(true);
+
+        let banner_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let banner_label = gtk::Label::new(None);
+        banner_label.set_halign(gtk::Align::Start);
+        banner_label.set_line_wrap(true);
+        banner_box.pack_start(&banner_label, true, true, 0);
+
+        let received: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+
+        let deliver = gtk::Button::new_with_label("Continue and deliver");
+        deliver.set_tooltip_text("Continue, passing the signal to the program");
+        {
+            let received = received.clone();
+            let continue_with_signal = continue_with_signal.clone();
+            deliver.connect_clicked(move |_| {
+                if let Some(ref name) = *received.borrow() {
+                    call_continue(&continue_with_signal, Some(name));
+                }
+            });
+        }
+        banner_box.pack_start(&deliver, false, false, 0);
+
+        let discard = gtk::Button::new_with_label("Continue and discard");
+        discard.set_tooltip_text("Continue without passing the signal to the program");
+        {
+            let continue_with_signal = continue_with_signal.clone();
+            discard.connect_clicked(move |_| {
+                call_continue(&continue_with_signal, None);
+            });
+        }
+        banner_box.pack_start(&discard, false, false, 0);
+
+        banner_box.show_all();
+        banner.get_content_area().unwrap().add(&banner_box);
+
+        let refresh = gtk::Button::new_with_label("Refresh");
+        refresh.set_halign(gtk::Align::Start);
+        refresh.set_tooltip_text("Show changes made with \"handle\" in the console");
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        widget.pack_start(&banner, false, false, 0);
+        widget.pack_start(&scrolled, true, true, 0);
+        widget.pack_start(&refresh, false, false, 0);
+
+        SignalsW {
+            widget,
+            model,
+            banner,
+            banner_label,
+            refresh,
+            received,
+            handle_signal,
+            continue_with_signal,
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Set "handle signal" callback. Arguments are: signal name, and a `handle` keyword ("stop",
+    /// "nostop", "print", "noprint", "pass" or "nopass"). The signal should be updated with
+    /// `update_signals`.
+    pub fn connect_handle_signal(&mut self, cb: Box<Fn(&str, &str)>) {
+        *self.handle_signal.borrow_mut() = Some(cb);
+    }
+
+    /// Set "continue with signal" callback. Argument is the signal to deliver, `None` means
+    /// continue without a signal.
+    pub fn connect_continue_with_signal(&mut self, cb: Box<Fn(Option<&str>)>) {
+        *self.continue_with_signal.borrow_mut() = Some(cb);
+    }
+
+    /// Set "refresh" callback. Signals should be updated with `update_signals`.
+    pub fn connect_refresh(&self, cb: Box<Fn()>) {
+        self.refresh.connect_clicked(move |_| cb());
+    }
+
+    /// Update signals in the list, adding signals that are not in the list.
+    pub fn update_signals(&self, signals: &[Signal]) {
+        for signal in signals {
+            let iter = match self.find_signal(&signal.name) {
+                Some(iter) => iter,
+                None => self.model.append(),
+            };
+            let received = self.received.borrow().as_ref() == Some(&signal.name);
+            let values: [&dyn gtk::ToValue; NUM_COLS] = [
+                &signal.name,
+                &signal.stop,
+                &signal.print,
+                &signal.pass,
+                &signal.description,
+                &received,
+            ];
+            self.model.set(&iter, &COL_INDICES, &values);
+        }
+    }
+
+    pub fn handle_event(&self, event: &Event) {
+        match event {
+            Event::Stopped(stop) => match stop.reason {
+                Some(StopReason::SignalReceived {
+                    ref name,
+                    ref meaning,
+                }) => {
+                    let thread = match stop.thread_id {
+                        None => "The program".to_string(),
+                        Some(thread_id) => format!("Thread #{}", thread_id),
+                    };
+                    self.banner_label.set_markup(&format!(
+                        "{} received <b>{}</b> ({})",
+                        thread,
+                        glib::markup_escape_text(name),
+                        glib::markup_escape_text(meaning)
+                    ));
+                    self.set_received(Some(name.clone()));
+                    self.banner.show();
+                }
+                _ => {
+                    self.set_received(None);
+                    self.banner.hide();
+                }
+            },
+            Event::Running { .. } => {
+                self.set_received(None);
+                self.banner.hide();
+            }
+            _ => {}
+        }
+    }
+
+    fn set_received(&self, signal: Option<String>) {
+        let old = self.received.replace(signal.clone());
+        for name in old.iter().chain(signal.iter()) {
+            if let Some(iter) = self.find_signal(name) {
+                self.model.set_value(
+                    &iter,
+                    Cols::Received as u32,
+                    &(Some(name) == signal.as_ref()).to_value(),
+                );
+            }
+        }
+    }
+
+    fn find_signal(&self, name: &str) -> Option<gtk::TreeIter> {
+        let iter = self.model.get_iter_first()?;
+        loop {
+            let name_ = self
+                .model
+                .get_value(&iter, Cols::Name as i32)
+                .get::<String>()
+                .unwrap();
+            if name_ == name {
+                return Some(iter);
+            }
+            if !self.model.iter_next(&iter) {
+                return None;
+            }
+        }
+    }
+}
+
+fn call_continue(cb: &ContinueCb, signal: Option<&str>) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't continue: callback not set");
+        }
+        Some(ref cb) => {
+            cb(signal);
+        }
+    }
+}
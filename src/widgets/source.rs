@@ -0,0 +1,227 @@
+//! A read-only view of a source file, with line numbers. Clicking a line number sets a breakpoint
+//! on the line, line numbers of lines with breakpoints are highlighted.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fs;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+
+use crate::types::Event;
+
+/// Type of the reference for the "break on line" callback.
+type BreakLineCb = Rc<RefCell<Option<Box<Fn(&str /* path */, u32 /* line */)>>>>;
+
+pub struct SourceW {
+    // box -> [ path label, scrolled -> text view ]
+    widget: gtk::Box,
+    path_label: gtk::Label,
+    text_view: gtk::TextView,
+    line_number_tag: gtk::TextTag,
+    // Path of the shown file
+    path: Rc<RefCell<Option<String>>>,
+    // Breakpoint numbers -> file paths and lines, for highlighting lines with breakpoints
+    breakpoints: RefCell<HashMap<u32, (String, u32)>>,
+    break_line: BreakLineCb,
+}
+
+impl SourceW {
+    pub fn new() -> SourceW {
+        let path_label = gtk::Label::new(None);
+        path_label.set_halign(gtk::Align::Start);
+        path_label.set_selectable(true);
+        path_label.set_tooltip_text("Click a line number to set a breakpoint");
+
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+
+        let text_view = gtk::TextView::new();
+        text_view.set_monospace(true);
+        text_view.set_vexpand(true);
+        text_view.set_editable(false);
+        text_view.set_cursor_visible(false);
+        scrolled.add(&text_view);
+
+        let text_buffer = text_view.get_buffer().unwrap();
+        let tag_table = text_buffer.get_tag_table().unwrap();
+        let line_number_tag = gtk::TextTag::new("line-number");
+        line_number_tag.set_property_foreground(Some("gray"));
+        tag_table.add(&line_number_tag);
+        let current_line_tag = gtk::TextTag::new("current-line");
+        current_line_tag.set_property_paragraph_background(Some("light yellow"));
+        tag_table.add(&current_line_tag);
+        // Added after "line-number" to take precedence
+        let breakpoint_tag = gtk::TextTag::new("breakpoint");
+        breakpoint_tag.set_property_foreground(Some("red"));
+        breakpoint_tag.set_property_weight(700); // bold
+        tag_table.add(&breakpoint_tag);
+
+        let path: Rc<RefCell<Option<String>>> = Rc::new(RefCell::new(None));
+        let break_line: BreakLineCb = Rc::new(RefCell::new(None));
+
+        // Line number clicked -> set a breakpoint
+        {
+            let line_number_tag = line_number_tag.clone();
+            let path = path.clone();
+            let break_line = break_line.clone();
+            text_view.connect_button_press_event(move |text_view, ev| {
+                if ev.get_event_type() != gdk::EventType::ButtonPress || ev.get_button() != 1 {
+                    return gtk::Inhibit(false);
+                }
+                let (x, y) = ev.get_position();
+                let (x, y) = text_view.window_to_buffer_coords(
+                    gtk::TextWindowType::Widget,
+                    x as i32,
+                    y as i32,
+                );
+                let iter = match text_view.get_iter_at_location(x, y) {
+                    None => {
+                        return gtk::Inhibit(false);
+                    }
+                    Some(iter) => iter,
+                };
+                if !iter.has_tag(&line_number_tag) {
+                    return gtk::Inhibit(false);
+                }
+                if let Some(ref path) = *path.borrow() {
+                    match *break_line.borrow() {
+                        None => {
+                            println!("Can't set breakpoint: callback not set");
+                        }
+                        Some(ref cb) => {
+                            cb(path, iter.get_line() as u32 + 1);
+                        }
+                    }
+                }
+                gtk::Inhibit(true)
+            });
+        }
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        widget.pack_start(&path_label, false, false, 0);
+        widget.pack_start(&scrolled, true, true, 0);
+
+        SourceW {
+            widget,
+            path_label,
+            text_view,
+            line_number_tag,
+            path,
+            breakpoints: RefCell::new(HashMap::new()),
+            break_line,
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Set "break on line" callback. Arguments are: path of the file, line (1-based).
+    pub fn connect_break_line(&mut self, cb: Box<Fn(&str, u32)>) {
+        *self.break_line.borrow_mut() = Some(cb);
+    }
+
+    /// Update highlighted breakpoint lines.
+    pub fn handle_event(&self, event: &Event) {
+        match event {
+            Event::BreakpointCreated(bp) | Event::BreakpointModified(bp) => {
+                let mut breakpoints = self.breakpoints.borrow_mut();
+                match (&bp.fullname, bp.line) {
+                    (Some(ref fullname), Some(line)) => {
+                        breakpoints.insert(bp.number, (fullname.clone(), line));
+                    }
+                    _ => {
+                        breakpoints.remove(&bp.number);
+                    }
+                }
+            }
+            Event::BreakpointDeleted { id } => {
+                self.breakpoints.borrow_mut().remove(id);
+            }
+            _ => {
+                return;
+            }
+        }
+        self.mark_breakpoints();
+    }
+
+    /// Show a file, scrolled to and highlighting `line` (1-based) when given. Switches to the
+    /// view when it's a notebook page.
+    pub fn show_file(&self, path: &str, line: Option<u32>) {
+        self.path_label.set_text(path);
+        let text_buffer = self.text_view.get_buffer().unwrap();
+        match fs::read_to_string(path) {
+            Err(err) => {
+                text_buffer.set_text(&format!("Can't read {}: {}", path, err));
+                *self.path.borrow_mut() = None;
+            }
+            Ok(source) => {
+                text_buffer.set_text("");
+                let n_lines = source.lines().count();
+                let width = n_lines.to_string().len();
+                let mut end_iter = text_buffer.get_end_iter();
+                for (line_idx, line) in source.lines().enumerate() {
+                    text_buffer.insert_with_tags_by_name(
+                        &mut end_iter,
+                        &format!("{:>width$}  ", line_idx + 1, width = width),
+                        &["line-number"],
+                    );
+                    text_buffer.insert(&mut end_iter, line);
+                    text_buffer.insert(&mut end_iter, "\n");
+                }
+                *self.path.borrow_mut() = Some(path.to_string());
+                self.mark_breakpoints();
+            }
+        }
+
+        if let Some(line) = line {
+            let start_iter = text_buffer.get_iter_at_line(line.saturating_sub(1) as i32);
+            let mut end_iter = start_iter.clone();
+            end_iter.forward_line();
+            text_buffer.apply_tag_by_name("current-line", &start_iter, &end_iter);
+            let mark = text_buffer
+                .create_mark(None, &start_iter, true /* left gravity */)
+                .unwrap();
+            self.text_view.scroll_to_mark(&mark, 0.0, true, 0.0, 0.3);
+            text_buffer.delete_mark(&mark);
+        }
+
+        if let Some(notebook) = self
+            .widget
+            .get_parent()
+            .and_then(|w| w.downcast::<gtk::Notebook>().ok())
+        {
+            if let Some(page) = notebook.page_num(&self.widget) {
+                notebook.set_current_page(Some(page));
+            }
+        }
+    }
+
+    /// Highlight line numbers of lines with breakpoints in the shown file.
+    fn mark_breakpoints(&self) {
+        let text_buffer = self.text_view.get_buffer().unwrap();
+        text_buffer.remove_tag_by_name(
+            "breakpoint",
+            &text_buffer.get_start_iter(),
+            &text_buffer.get_end_iter(),
+        );
+        let path = self.path.borrow();
+        let path = match *path {
+            None => {
+                return;
+            }
+            Some(ref path) => path,
+        };
+        for (bp_path, line) in self.breakpoints.borrow().values() {
+            if bp_path != path {
+                continue;
+            }
+            let start_iter = text_buffer.get_iter_at_line(line.saturating_sub(1) as i32);
+            let mut end_iter = start_iter.clone();
+            end_iter.forward_to_tag_toggle(Some(&self.line_number_tag));
+            text_buffer.apply_tag_by_name("breakpoint", &start_iter, &end_iter);
+        }
+    }
+}
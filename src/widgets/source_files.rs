@@ -0,0 +1,210 @@
+//! A filterable list of the program's source files, grouped by directory. Activating a file opens
+//! it in the source view.
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+
+use crate::types::SourceFile;
+
+/// Type of the reference for the "open" callback.
+type OpenCb = Rc<RefCell<Option<Box<Fn(&str /* path */)>>>>;
+
+/// Type of the reference for the "refresh" callback.
+type RefreshCb = Rc<RefCell<Option<Box<Fn()>>>>;
+
+pub struct SourceFilesW {
+    // box -> [ box -> [ search entry, button ("Refresh") ], scrolled -> tree view ]
+    widget: gtk::Box,
+    model: gtk::TreeStore,
+    view: gtk::TreeView,
+    filter_entry: gtk::SearchEntry,
+    // All files, the model only has the ones matching the filter
+    files: Rc<RefCell<Vec<SourceFile>>>,
+    open: OpenCb,
+    refresh: RefreshCb,
+}
+
+/// Column indices for cell renderers
+#[repr(i32)]
+enum Cols {
+    // Directory, or file name in the directory
+    Name = 0,
+    // Not rendered: path of the file, empty for directories
+    Path,
+}
+
+/// Column types for the tree store
+static COL_TYPES: [gtk::Type; 2] = [
+    gtk::Type::String, // name
+    gtk::Type::String, // path
+];
+
+impl SourceFilesW {
+    pub fn new() -> SourceFilesW {
+        let model = gtk::TreeStore::new(&COL_TYPES);
+        let files: Rc<RefCell<Vec<SourceFile>>> = Rc::new(RefCell::new(vec![]));
+        let open: OpenCb = Rc::new(RefCell::new(None));
+        let refresh: RefreshCb = Rc::new(RefCell::new(None));
+
+        let filter_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let filter_entry = gtk::SearchEntry::new();
+        filter_entry.set_placeholder_text("Filter, e.g. parser.c");
+        filter_entry.set_hexpand(true);
+        filter_box.pack_start(&filter_entry, true, true, 0);
+        let refresh_button = gtk::Button::new_with_label("Refresh");
+        refresh_button.set_tooltip_text("Update the list, e.g. after loading libraries");
+        {
+            let refresh = refresh.clone();
+            refresh_button.connect_clicked(move |_| match *refresh.borrow() {
+                None => {
+                    println!("Can't refresh source files: callback not set");
+                }
+                Some(ref cb) => {
+                    cb();
+                }
+            });
+        }
+        filter_box.pack_start(&refresh_button, false, false, 0);
+
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+
+        let view = gtk::TreeView::new_with_model(&model);
+        view.set_headers_visible(false);
+        view.set_vexpand(true);
+        scrolled.add(&view);
+
+        let renderer = gtk::CellRendererText::new();
+        let column = gtk::TreeViewColumn::new();
+        column.pack_start(&renderer, true);
+        column.add_attribute(&renderer, "text", Cols::Name as i32);
+        view.append_column(&column);
+
+        // Filter changed -> render matching files
+        {
+            let model = model.clone();
+            let view = view.clone();
+            let files = files.clone();
+            filter_entry.connect_search_changed(move |entry| {
+                let filter = entry.get_text().map(|s| s.to_string()).unwrap_or_default();
+                render_files(&model, &view, &files.borrow(), &filter);
+            });
+        }
+
+        // Row activated (double click, enter) -> open the file
+        {
+            let open = open.clone();
+            view.connect_row_activated(move |view, path, _| {
+                let model = view.get_model().unwrap();
+                let iter = model.get_iter(path).unwrap();
+                let file_path = model
+                    .get_value(&iter, Cols::Path as i32)
+                    .get::<String>()
+                    .unwrap_or_default();
+                if file_path.is_empty() {
+                    // A directory, expand or collapse
+                    if view.row_expanded(path) {
+                        view.collapse_row(path);
+                    } else {
+                        view.expand_row(path, false);
+                    }
+                    return;
+                }
+                match *open.borrow() {
+                    None => {
+                        println!("Can't open {}: callback not set", file_path);
+                    }
+                    Some(ref cb) => {
+                        cb(&file_path);
+                    }
+                }
+            });
+        }
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        widget.pack_start(&filter_box, false, false, 0);
+        widget.pack_start(&scrolled, true, true, 0);
+
+        SourceFilesW {
+            widget,
+            model,
+            view,
+            filter_entry,
+            files,
+            open,
+            refresh,
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Set "open" callback. Argument is path of the file.
+    pub fn connect_open(&mut self, cb: Box<Fn(&str)>) {
+        *self.open.borrow_mut() = Some(cb);
+    }
+
+    /// Set "refresh" callback. Files should be updated with `set_files`.
+    pub fn connect_refresh(&mut self, cb: Box<Fn()>) {
+        *self.refresh.borrow_mut() = Some(cb);
+    }
+
+    pub fn set_files(&self, files: Vec<SourceFile>) {
+        let filter = self
+            .filter_entry
+            .get_text()
+            .map(|s| s.to_string())
+            .unwrap_or_default();
+        render_files(&self.model, &self.view, &files, &filter);
+        *self.files.borrow_mut() = files;
+    }
+}
+
+/// Render files with paths containing `filter` (ignoring case), grouped by directory. Files that
+/// GDB can't find are shown with the names given to the compiler.
+fn render_files(model: &gtk::TreeStore, view: &gtk::TreeView, files: &[SourceFile], filter: &str) {
+    let filter = filter.trim().to_lowercase();
+    // Directory -> file names and paths. GDB lists headers once per compilation unit that
+    // includes them, so the same path may appear multiple times.
+    let mut dirs: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    for file in files {
+        let path = file.fullname.as_ref().unwrap_or(&file.file);
+        if !path.to_lowercase().contains(&filter) {
+            continue;
+        }
+        let path_ = Path::new(path);
+        let dir = path_
+            .parent()
+            .map(|dir| dir.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let name = path_
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        dirs.entry(dir)
+            .or_insert_with(BTreeMap::new)
+            .insert(name, path.clone());
+    }
+
+    let cols = [Cols::Name as u32, Cols::Path as u32];
+    model.clear();
+    for (dir, dir_files) in &dirs {
+        let dir_iter = model.append(None);
+        let dir_name = if dir.is_empty() { "." } else { dir.as_str() };
+        model.set(&dir_iter, &cols, &[&dir_name, &""]);
+        for (name, path) in dir_files {
+            model.set(&model.append(Some(&dir_iter)), &cols, &[name, path]);
+        }
+    }
+
+    // Show files when filtered, otherwise the directories can be browsed
+    if !filter.is_empty() {
+        view.expand_all();
+    }
+}
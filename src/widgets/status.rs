@@ -0,0 +1,220 @@
+//! A status bar that shows state of the inferior, its pid, the current thread and why it stopped,
+//! e.g. "Stopped  pid 19785  thread #1  Breakpoint 1 hit at test.c:3".
+//!
+//! Everything is updated from async events; the stop reason is decoded from fields of the
+//! `*stopped` record. Exception and library catchpoints stop with the "breakpoint-hit" reason, so
+//! catchpoints are tracked to tell them apart from breakpoints.
+
+use std::collections::HashMap;
+
+use gtk::prelude::*;
+
+use crate::types::{BreakpointType, Event, Frame, StopInfo, StopReason, WatchpointType};
+
+pub struct StatusW {
+    // box -> [ state label, pid label, thread label, reason label ]
+    widget: gtk::Box,
+    state_label: gtk::Label,
+    pid_label: gtk::Label,
+    thread_label: gtk::Label,
+    reason_label: gtk::Label,
+    state: State,
+    /// Catchpoint numbers mapped to what they catch, e.g. "exception throw".
+    catchpoints: HashMap<u32, String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    NotStarted,
+    Running,
+    Stopped,
+    Exited,
+}
+
+impl StatusW {
+    pub fn new() -> StatusW {
+        let widget = gtk::Box::new(gtk::Orientation::Horizontal, 12);
+        widget.set_margin_start(4);
+        widget.set_margin_end(4);
+
+        let state_label = gtk::Label::new(None);
+        widget.pack_start(&state_label, false, false, 0);
+
+        let pid_label = gtk::Label::new(None);
+        widget.pack_start(&pid_label, false, false, 0);
+
+        let thread_label = gtk::Label::new(None);
+        widget.pack_start(&thread_label, false, false, 0);
+
+        let reason_label = gtk::Label::new(None);
+        reason_label.set_halign(gtk::Align::Start);
+        reason_label.set_selectable(true);
+        widget.pack_start(&reason_label, true, true, 0);
+
+        let mut status_w = StatusW {
+            widget,
+            state_label,
+            pid_label,
+            thread_label,
+            reason_label,
+            state: State::NotStarted,
+            catchpoints: HashMap::new(),
+        };
+        status_w.set_state(State::NotStarted);
+        status_w
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::Stopped(stop) => {
+                match stop.reason {
+                    Some(StopReason::Exited { .. })
+                    | Some(StopReason::ExitedNormally)
+                    | Some(StopReason::ExitedSignalled { .. }) => {
+                        self.set_state(State::Exited);
+                        self.pid_label.set_text("");
+                        self.thread_label.set_text("");
+                    }
+                    _ => {
+                        self.set_state(State::Stopped);
+                        if let Some(thread_id) = stop.thread_id {
+                            self.set_current_thread(thread_id);
+                        }
+                    }
+                }
+                let reason = describe_stop(stop, &self.catchpoints);
+                self.reason_label
+                    .set_markup(&format!("<b>{}</b>", glib::markup_escape_text(&reason)));
+                self.reason_label.set_tooltip_text(Some(reason.as_str()));
+            }
+            Event::Running { .. } => {
+                // In non-stop mode some threads may still be stopped, but the stop reason is
+                // outdated either way
+                self.set_state(State::Running);
+                self.reason_label.set_text("");
+                self.reason_label.set_tooltip_text(None);
+            }
+            Event::BreakpointCreated(bp) | Event::BreakpointModified(bp) => {
+                if let BreakpointType::Catchpoint(_) = bp.type_ {
+                    self.catchpoints
+                        .insert(bp.number, bp.original_location.clone());
+                }
+            }
+            Event::BreakpointDeleted { id } => {
+                self.catchpoints.remove(id);
+            }
+            Event::ThreadSelected { id } => {
+                self.set_current_thread(*id);
+            }
+            Event::ThreadGroupStarted { pid, .. } => {
+                if let Some(pid) = pid {
+                    self.pid_label.set_text(&format!("pid {}", pid));
+                }
+            }
+            Event::ThreadGroupExited { exit_code, .. } => {
+                self.set_state(State::Exited);
+                self.pid_label.set_text("");
+                self.thread_label.set_text("");
+                if let Some(exit_code) = exit_code {
+                    self.reason_label
+                        .set_text(&format!("Exited with code {}", exit_code));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn set_state(&mut self, state: State) {
+        self.state = state;
+        self.state_label.set_text(match state {
+            State::NotStarted => "Not started",
+            State::Running => "Running",
+            State::Stopped => "Stopped",
+            State::Exited => "Exited",
+        });
+    }
+
+    fn set_current_thread(&self, thread_id: i32) {
+        if self.state != State::Exited {
+            self.thread_label
+                .set_text(&format!("thread #{}", thread_id));
+        }
+    }
+}
+
+/// Describe why execution stopped, e.g. "Breakpoint 1 hit at test.c:3".
+fn describe_stop(stop: &StopInfo, catchpoints: &HashMap<u32, String>) -> String {
+    let at = match stop.frame {
+        None => "".to_string(),
+        Some(ref frame) => format!(" at {}", frame_loc(frame)),
+    };
+    match stop.reason {
+        None => format!("Stopped{}", at),
+        Some(StopReason::BreakpointHit { number }) => match catchpoints.get(&number) {
+            None => format!("Breakpoint {} hit{}", number, at),
+            Some(what) => format!("Catchpoint {} ({}) hit{}", number, what, at),
+        },
+        Some(StopReason::WatchpointTrigger {
+            number,
+            type_,
+            ref expr,
+            ref old_value,
+            ref value,
+        }) => {
+            let kind = match type_ {
+                WatchpointType::Write => "Watchpoint",
+                WatchpointType::Read => "Read watchpoint",
+                WatchpointType::ReadWrite => "Access watchpoint",
+            };
+            let value = match (old_value, value) {
+                (Some(old), Some(new)) => format!("{} → {}", old, new),
+                (None, Some(value)) => format!("value = {}", value),
+                _ => "".to_string(),
+            };
+            format!("{} {} ({}): {}{}", kind, number, expr, value, at)
+        }
+        Some(StopReason::WatchpointScope { number }) => format!(
+            "Watchpoint {} deleted as its expression went out of scope{}",
+            number, at
+        ),
+        Some(StopReason::FunctionFinished { ref return_value }) => match return_value {
+            None => format!("Function finished{}", at),
+            Some(value) => format!("Function finished, returned {}{}", value, at),
+        },
+        Some(StopReason::LocationReached) => format!("Location reached{}", at),
+        Some(StopReason::EndSteppingRange) => format!("Step finished{}", at),
+        Some(StopReason::SignalReceived {
+            ref name,
+            ref meaning,
+        }) => format!("Received signal {} ({}){}", name, meaning, at),
+        Some(StopReason::Exited { exit_code }) => format!("Exited with code {}", exit_code),
+        Some(StopReason::ExitedNormally) => "Exited normally".to_string(),
+        Some(StopReason::ExitedSignalled {
+            ref name,
+            ref meaning,
+        }) => format!("Killed by signal {} ({})", name, meaning),
+        Some(StopReason::Fork { new_pid }) => format!("Forked process {}{}", new_pid, at),
+        Some(StopReason::Vfork { new_pid }) => format!("Vforked process {}{}", new_pid, at),
+        Some(StopReason::Exec { ref new_exec }) => format!("Executed {}{}", new_exec, at),
+        Some(StopReason::SyscallEntry { ref name }) => format!("Entered syscall {}{}", name, at),
+        Some(StopReason::SyscallReturn { ref name }) => {
+            format!("Returned from syscall {}{}", name, at)
+        }
+        Some(StopReason::SolibEvent) => format!("Shared library loaded or unloaded{}", at),
+        Some(StopReason::NoHistory) => format!("Reached end of recorded history{}", at),
+        Some(StopReason::Other(ref reason)) => format!("Stopped ({}){}", reason, at),
+    }
+}
+
+/// e.g. "test.c:3", or "__GI___poll (0x00007ffff5418bf9)" when the source is not known.
+fn frame_loc(frame: &Frame) -> String {
+    match (&frame.file, &frame.line) {
+        (Some(file), Some(line)) => format!("{}:{}", file, line),
+        _ => format!("{} ({})", frame.func, frame.addr),
+    }
+}
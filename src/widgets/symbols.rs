@@ -0,0 +1,439 @@
+//! A searchable list of the program's functions, variables and types, grouped by the file they're
+//! defined in. Functions can be used to set breakpoints, symbols with debug info can be opened in
+//! the source view, and variables can be added to the expressions.
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use gtk::prelude::*;
+
+use crate::types::{SymbolInfo, SymbolKind};
+
+/// Type of the reference for the "search" callback.
+type SearchCb = Rc<RefCell<Option<Box<Fn(SymbolKind, &str, bool)>>>>;
+
+/// Type of the reference for the "break on" callback.
+type BreakOnCb = Rc<RefCell<Option<Box<Fn(&str /* location */)>>>>;
+
+/// Type of the reference for the "open source" callback.
+type OpenSourceCb = Rc<RefCell<Option<Box<Fn(&str /* path */, Option<u32>)>>>>;
+
+/// Type of the reference for the "add expression" callback.
+type AddExprCb = Rc<RefCell<Option<Box<Fn(String /* expression */)>>>>;
+
+/// Max. number of symbols to ask GDB for. Searching all symbols of a large program can take a
+/// while, and rendering them even longer.
+pub const MAX_SYMBOLS: usize = 2000;
+
+/// Kinds in the kind combo box: kind, combo box id and text.
+static KINDS: [(SymbolKind, &str, &str); 3] = [
+    (SymbolKind::Function, "functions", "Functions"),
+    (SymbolKind::Variable, "variables", "Variables"),
+    (SymbolKind::Type, "types", "Types"),
+];
+
+pub struct SymbolsW {
+    // box -> [ box -> [ search entry, combo box (kind), check button (non-debugging) ],
+    //          status label,
+    //          scrolled -> tree view ]
+    widget: gtk::Box,
+    model: gtk::TreeStore,
+    view: gtk::TreeView,
+    status_label: gtk::Label,
+    // Kind of the rendered symbols
+    kind: Rc<Cell<SymbolKind>>,
+    search: SearchCb,
+    break_on: BreakOnCb,
+    open_source: OpenSourceCb,
+    add_expr: AddExprCb,
+}
+
+/// Number of columns
+const NUM_COLS: usize = 6;
+
+/// Column indices for cell renderers
+#[repr(i32)]
+enum Cols {
+    // Symbol name, or file name for file rows
+    Name = 0,
+    // Line of the definition, empty when not known
+    Line,
+    // Declaration of functions and variables, address of non-debugging symbols
+    Details,
+    // Not rendered: file name as given to the compiler, empty for non-debugging symbols
+    FileName,
+    // Not rendered: absolute path of the file, empty when not known
+    FullName,
+    // Not rendered: whether the row is a symbol (not a file)
+    IsSymbol,
+}
+
+/// Column types for the tree store
+static COL_TYPES: [gtk::Type; NUM_COLS] = [
+    gtk::Type::String, // name
+    gtk::Type::String, // line
+    gtk::Type::String, // details
+    gtk::Type::String, // file name
+    gtk::Type::String, // full name
+    gtk::Type::Bool,   // is symbol
+];
+
+/// Column indices for when inserting rows into the tree store
+static COL_INDICES: [u32; NUM_COLS] = [0, 1, 2, 3, 4, 5];
+
+/// A symbol row, as needed for the actions.
+struct SymbolRow {
+    name: String,
+    line: Option<u32>,
+    details: String,
+    file_name: String,
+    full_name: String,
+}
+
+impl SymbolsW {
+    pub fn new() -> SymbolsW {
+        let model = gtk::TreeStore::new(&COL_TYPES);
+
+        let search: SearchCb = Rc::new(RefCell::new(None));
+        let break_on: BreakOnCb = Rc::new(RefCell::new(None));
+        let open_source: OpenSourceCb = Rc::new(RefCell::new(None));
+        let add_expr: AddExprCb = Rc::new(RefCell::new(None));
+        let kind = Rc::new(Cell::new(SymbolKind::Function));
+
+        //
+        // Create the search bar
+        //
+
+        let search_box = gtk::Box::new(gtk::Orientation::Horizontal, 4);
+        let search_entry = gtk::SearchEntry::new();
+        search_entry.set_placeholder_text("Name regex, e.g. ^parse_");
+        search_entry.set_hexpand(true);
+        search_box.pack_start(&search_entry, true, true, 0);
+        let kind_combo = gtk::ComboBoxText::new();
+        for (_, id, text) in KINDS.iter() {
+            kind_combo.append(Some(*id), text);
+        }
+        kind_combo.set_active_id(Some(KINDS[0].1));
+        search_box.pack_start(&kind_combo, false, false, 0);
+        let nondebug_check = gtk::CheckButton::new_with_label("Non-debugging");
+        nondebug_check.set_tooltip_text("Include functions and variables without debug info");
+        search_box.pack_start(&nondebug_check, false, false, 0);
+
+        let status_label = gtk::Label::new(None);
+        status_label.set_halign(gtk::Align::Start);
+
+        {
+            let search = search.clone();
+            let kind_combo = kind_combo.clone();
+            let nondebug_check = nondebug_check.clone();
+            let status_label = status_label.clone();
+            let submit = move |entry: &gtk::SearchEntry| {
+                let regex = entry
+                    .get_text()
+                    .map(|s| s.as_str().trim().to_string())
+                    .unwrap_or_default();
+                let kind = kind_combo
+                    .get_active()
+                    .map(|idx| KINDS[idx as usize].0)
+                    .unwrap_or(SymbolKind::Function);
+                match *search.borrow() {
+                    None => {
+                        println!("Can't search symbols: callback not set");
+                    }
+                    Some(ref cb) => {
+                        status_label.set_text("Searching…");
+                        cb(kind, &regex, nondebug_check.get_active());
+                    }
+                }
+            };
+            let submit = Rc::new(submit);
+            {
+                let submit = submit.clone();
+                search_entry.connect_activate(move |entry| submit(entry));
+            }
+            // Search again with the new kind
+            let search_entry_ = search_entry.clone();
+            kind_combo.connect_changed(move |_| submit(&search_entry_));
+        }
+
+        //
+        // Create the view
+        //
+
+        let scrolled = gtk::ScrolledWindow::new(gtk::NONE_ADJUSTMENT, gtk::NONE_ADJUSTMENT);
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+
+        let view = gtk::TreeView::new_with_model(&model);
+        view.set_headers_visible(true);
+        view.set_vexpand(true);
+        scrolled.add(&view);
+
+        let add_col = |title: &'static str, col_ty: Cols| {
+            let renderer = gtk::CellRendererText::new();
+            let column = gtk::TreeViewColumn::new();
+            column.pack_start(&renderer, true);
+            column.set_title(title);
+            column.set_resizable(true);
+            column.add_attribute(&renderer, "text", col_ty as i32);
+            view.append_column(&column);
+        };
+        add_col("Name", Cols::Name);
+        add_col("Line", Cols::Line);
+        add_col("Details", Cols::Details);
+
+        //
+        // Create the context menu
+        //
+
+        let menu_row: Rc<RefCell<Option<SymbolRow>>> = Rc::new(RefCell::new(None));
+
+        let menu = gtk::Menu::new();
+        let break_item = gtk::MenuItem::new_with_label("Set breakpoint");
+        {
+            let menu_row = menu_row.clone();
+            let break_on = break_on.clone();
+            break_item.connect_activate(move |_| {
+                if let Some(ref row) = *menu_row.borrow() {
+                    if let Some(ref cb) = *break_on.borrow() {
+                        cb(&break_location(row));
+                    }
+                }
+            });
+        }
+        menu.append(&break_item);
+        let source_item = gtk::MenuItem::new_with_label("Open source");
+        {
+            let menu_row = menu_row.clone();
+            let open_source = open_source.clone();
+            source_item.connect_activate(move |_| {
+                if let Some(ref row) = *menu_row.borrow() {
+                    call_open_source_cb(&open_source, row);
+                }
+            });
+        }
+        menu.append(&source_item);
+        let expr_item = gtk::MenuItem::new_with_label("Add to expressions");
+        {
+            let menu_row = menu_row.clone();
+            let add_expr = add_expr.clone();
+            expr_item.connect_activate(move |_| {
+                if let Some(ref row) = *menu_row.borrow() {
+                    if let Some(ref cb) = *add_expr.borrow() {
+                        cb(variable_expr(row));
+                    }
+                }
+            });
+        }
+        menu.append(&expr_item);
+        menu.show_all();
+        menu.set_attach_widget(Some(&view));
+
+        {
+            let kind = kind.clone();
+            view.connect_button_press_event(move |view, ev| {
+                if ev.get_event_type() != gdk::EventType::ButtonPress || ev.get_button() != 3 {
+                    return gtk::Inhibit(false);
+                }
+                let (x, y) = ev.get_position();
+                let path = match view.get_path_at_pos(x as i32, y as i32) {
+                    Some((Some(path), _, _, _)) => path,
+                    _ => {
+                        return gtk::Inhibit(false);
+                    }
+                };
+                let row = match get_symbol_row(view, &path) {
+                    None => {
+                        return gtk::Inhibit(false);
+                    }
+                    Some(row) => row,
+                };
+                view.get_selection().select_path(&path);
+                break_item.set_sensitive(kind.get() == SymbolKind::Function);
+                source_item.set_sensitive(!row.full_name.is_empty());
+                expr_item.set_sensitive(kind.get() == SymbolKind::Variable);
+                *menu_row.borrow_mut() = Some(row);
+                menu.popup_at_pointer(Some(&**ev));
+                gtk::Inhibit(true)
+            });
+        }
+
+        // Row activated (double click, enter) -> open the source
+        {
+            let open_source = open_source.clone();
+            view.connect_row_activated(move |view, path, _| {
+                if let Some(row) = get_symbol_row(view, path) {
+                    if !row.full_name.is_empty() {
+                        call_open_source_cb(&open_source, &row);
+                    }
+                }
+            });
+        }
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        widget.pack_start(&search_box, false, false, 0);
+        widget.pack_start(&status_label, false, false, 0);
+        widget.pack_start(&scrolled, true, true, 0);
+
+        SymbolsW {
+            widget,
+            model,
+            view,
+            status_label,
+            kind,
+            search,
+            break_on,
+            open_source,
+            add_expr,
+        }
+    }
+
+    /// ONLY USE TO ADD THIS TO CONTAINERS!
+    pub fn get_widget(&self) -> &gtk::Widget {
+        self.widget.upcast_ref()
+    }
+
+    /// Set "search" callback. Arguments are: what to search, name regex (empty to list all
+    /// symbols), whether to include non-debugging symbols. Results should be rendered with
+    /// `show_symbols`.
+    pub fn connect_search(&mut self, cb: Box<Fn(SymbolKind, &str, bool)>) {
+        *self.search.borrow_mut() = Some(cb);
+    }
+
+    /// Set "break on" callback. Argument is the location of a function, e.g. "test.c:main".
+    pub fn connect_break_on(&mut self, cb: Box<Fn(&str)>) {
+        *self.break_on.borrow_mut() = Some(cb);
+    }
+
+    /// Set "open source" callback. Arguments are: path of the file, line of the symbol.
+    pub fn connect_open_source(&mut self, cb: Box<Fn(&str, Option<u32>)>) {
+        *self.open_source.borrow_mut() = Some(cb);
+    }
+
+    /// Set "add expression" callback. Argument is the expression of a variable.
+    pub fn connect_add_expr(&mut self, cb: Box<Fn(String)>) {
+        *self.add_expr.borrow_mut() = Some(cb);
+    }
+
+    /// Show why the search failed. The last results are kept.
+    pub fn show_error(&self, msg: &str) {
+        self.status_label.set_text(msg);
+    }
+
+    /// Replace the list with search results.
+    pub fn show_symbols(&self, kind: SymbolKind, info: &SymbolInfo) {
+        self.kind.set(kind);
+        self.model.clear();
+
+        let mut n_symbols = 0;
+        for file in &info.debug {
+            let full_name = file.fullname.as_ref().map(String::as_str).unwrap_or("");
+            let file_iter = self.model.append(None);
+            let values: [&dyn gtk::ToValue; NUM_COLS] =
+                [&file.filename, &"", &"", &file.filename, &full_name, &false];
+            self.model.set(&file_iter, &COL_INDICES, &values);
+            for symbol in &file.symbols {
+                let line = symbol.line.map(|line| line.to_string()).unwrap_or_default();
+                let details = symbol
+                    .description
+                    .as_ref()
+                    .or(symbol.type_.as_ref())
+                    .map(String::as_str)
+                    .unwrap_or("");
+                let values: [&dyn gtk::ToValue; NUM_COLS] = [
+                    &symbol.name,
+                    &line,
+                    &details,
+                    &file.filename,
+                    &full_name,
+                    &true,
+                ];
+                self.model
+                    .set(&self.model.append(Some(&file_iter)), &COL_INDICES, &values);
+            }
+            n_symbols += file.symbols.len();
+        }
+
+        if !info.nondebug.is_empty() {
+            let group_iter = self.model.append(None);
+            let values: [&dyn gtk::ToValue; NUM_COLS] =
+                [&"Non-debugging symbols", &"", &"", &"", &"", &false];
+            self.model.set(&group_iter, &COL_INDICES, &values);
+            for symbol in &info.nondebug {
+                let values: [&dyn gtk::ToValue; NUM_COLS] =
+                    [&symbol.name, &"", &symbol.address, &"", &"", &true];
+                self.model
+                    .set(&self.model.append(Some(&group_iter)), &COL_INDICES, &values);
+            }
+            n_symbols += info.nondebug.len();
+        }
+
+        // Expand files when there are only a few results
+        if info.debug.len() <= 5 {
+            self.view.expand_all();
+        }
+
+        self.status_label.set_text(&if n_symbols >= MAX_SYMBOLS {
+            format!(
+                "First {} symbols, refine the search to see others",
+                n_symbols
+            )
+        } else {
+            format!("{} symbols", n_symbols)
+        });
+    }
+}
+
+fn get_symbol_row(view: &gtk::TreeView, path: &gtk::TreePath) -> Option<SymbolRow> {
+    let model = view.get_model()?;
+    let iter = model.get_iter(path)?;
+    let get_string = |col: Cols| {
+        model
+            .get_value(&iter, col as i32)
+            .get::<String>()
+            .unwrap_or_default()
+    };
+    if !model
+        .get_value(&iter, Cols::IsSymbol as i32)
+        .get::<bool>()?
+    {
+        return None;
+    }
+    Some(SymbolRow {
+        name: get_string(Cols::Name),
+        line: get_string(Cols::Line).parse::<u32>().ok(),
+        details: get_string(Cols::Details),
+        file_name: get_string(Cols::FileName),
+        full_name: get_string(Cols::FullName),
+    })
+}
+
+fn call_open_source_cb(cb: &OpenSourceCb, row: &SymbolRow) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't open {}: callback not set", row.full_name);
+        }
+        Some(ref cb) => {
+            cb(&row.full_name, row.line);
+        }
+    }
+}
+
+/// Breakpoint location of a function. The file is needed for static functions with the same name
+/// in multiple files.
+fn break_location(row: &SymbolRow) -> String {
+    if row.file_name.is_empty() {
+        row.name.clone()
+    } else {
+        format!("{}:{}", row.file_name, row.name)
+    }
+}
+
+/// Expression of a variable. Static variables are qualified with the file name, as there may be
+/// static variables with the same name in multiple files.
+fn variable_expr(row: &SymbolRow) -> String {
+    if row.details.starts_with("static ") && !row.file_name.is_empty() {
+        format!("'{}'::{}", row.file_name, row.name)
+    } else {
+        row.name.clone()
+    }
+}
@@ -1,19 +1,127 @@
 //! A scrolled widget that shows thread backtraces.
+//!
+//! Programs can have hundreds of threads, so backtraces are fetched lazily: the thread list only
+//! shows the top frame of each thread (which is available in `-thread-info` results), and full
+//! backtraces are requested when a thread is expanded. Deep stacks are fetched in pages.
+//!
+//! In non-stop mode threads run and stop independently. Running threads are shown as such (their
+//! backtraces are greyed out) and each thread has a context menu for continuing, interrupting and
+//! stepping it.
+//!
+//! Alternatively threads can be shown as "parallel stacks": threads with common call stack
+//! prefixes are merged into a tree (see `ParallelStacksW`). Full backtraces of all threads are
+//! needed for this, so they're fetched for all threads while this view is active.
+//!
+//! Threads are grouped by inferior (process). Group headers are only shown when there's more than
+//! one inferior, e.g. after a fork with `detach-on-fork` disabled.
+//!
+//! The filter bar hides threads that don't match the filter text. Threads are matched by id,
+//! name, state and functions and files in their backtraces, so full backtraces are needed while
+//! filtering as well.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
 
 use gtk::prelude::*;
 
-use crate::types::Backtrace;
+use crate::types::{Backtrace, Event, Inferior, Thread, ThreadAction, ThreadState};
 use crate::widgets::backtrace::BacktraceW;
+use crate::widgets::parallel_stacks::ParallelStacksW;
+
+// TODO: Make the threads draggable. Positions are already remembered when updating (threads are
+// updated in place, new threads are added to the end), so only the dragging part is missing.
+
+/// Actions in the context menu of a thread.
+static THREAD_ACTIONS: [(ThreadAction, &str); 4] = [
+    (ThreadAction::Continue, "Continue"),
+    (ThreadAction::Interrupt, "Interrupt"),
+    (ThreadAction::Step, "Step"),
+    (ThreadAction::Next, "Next"),
+];
+
+/// Number of frames to ask for at once.
+pub const FRAMES_PAGE_SIZE: usize = 50;
+
+/// Type of the reference for the callback for asking for frames of a thread. Arguments are:
+/// thread id, index of the first frame, index of the last frame + 1. Frames should be added with
+/// `set_frames`.
+type GetStackCb = Rc<RefCell<Option<Box<Fn(i32, usize, usize)>>>>;
 
-// TODO: Make the threads draggable. We should remember the positions when updating. (so if I
-// re-order threads to 1-3-2, after updating this widget with new backtraces I should still get
-// 1-3-2)
+/// Type of the reference for the callback for per-thread execution commands (non-stop mode).
+type ThreadActionCb = Rc<RefCell<Option<Box<Fn(i32, ThreadAction)>>>>;
+
+/// Type of the reference for the callback for selecting a thread.
+type SelectThreadCb = Rc<RefCell<Option<Box<Fn(i32)>>>>;
+
+/// Type of the reference for the callback for filter text changes.
+type FilterChangedCb = Rc<RefCell<Option<Box<Fn()>>>>;
 
 pub struct ThreadsW {
-    // scrolled -> box -> [expander -> BacktraceW]
-    widget: gtk::ScrolledWindow,
+    // box -> [ box -> [ filter entry, filter label, toggle button ],
+    //          stack -> [ scrolled -> box -> [ label, box -> [ expander -> box ->
+    //                                                         [ BacktraceW, "load more" button ] ]
+    //                                      (one label and box per inferior) ]
+    //                   , ParallelStacksW ] ]
+    widget: gtk::Box,
     box_: gtk::Box,
-    threads: Vec<BacktraceW>,
+    inferiors: Vec<InferiorW>,
+    /// Inferiors of threads, from `=thread-created` records. Thread ids are unique across
+    /// inferiors.
+    thread_groups: HashMap<i32, String>,
+    filter_entry: gtk::SearchEntry,
+    // e.g. "3 of 200 threads"
+    filter_label: gtk::Label,
+    filter_changed: FilterChangedCb,
+    parallel_stacks_button: gtk::ToggleButton,
+    parallel_stacks_w: ParallelStacksW,
+    threads: Vec<ThreadW>,
+    /// Full backtraces of stopped threads, for the parallel stacks view and filtering. Backtraces
+    /// of threads are dropped when the threads resume or stop again.
+    full_stacks: HashMap<i32, Backtrace>,
+    get_stack: GetStackCb,
+    thread_action: ThreadActionCb,
+    select_thread: SelectThreadCb,
+    /// Per-thread actions are only available in non-stop mode.
+    non_stop: Rc<Cell<bool>>,
+}
+
+/// Page names in the stack
+static LIST_PAGE: &str = "list";
+static PARALLEL_STACKS_PAGE: &str = "parallel";
+
+/// Threads of an inferior.
+struct InferiorW {
+    /// Thread group id, e.g. "i1". Threads with unknown inferiors are in a group with empty id.
+    id: String,
+    header: gtk::Label,
+    box_: gtk::Box,
+}
+
+/// A thread in the list.
+struct ThreadW {
+    thread_id: i32,
+    /// Box of the inferior of the thread.
+    inferior_box: gtk::Box,
+    /// Information from the last `-thread-info`, used in the title.
+    info: RefCell<Thread>,
+    /// Whether this is the current thread (selected with `-thread-select`).
+    current: Cell<bool>,
+    expander: gtk::Expander,
+    bt_w: BacktraceW,
+    load_more: gtk::Button,
+    /// Whether the rendered backtrace is from a previous stop. Stale backtraces are fetched again
+    /// when the thread is expanded.
+    stale: Rc<Cell<bool>>,
+    /// Number of frames rendered.
+    n_frames: Rc<Cell<usize>>,
+    /// Depth of the stack, if known.
+    depth: Cell<Option<usize>>,
+    /// Depth of the stack in the rendered backtrace, used to highlight changed frames.
+    old_depth: Cell<Option<usize>>,
+    /// Whether the thread is running. Only in non-stop mode some threads can be running while
+    /// others are stopped.
+    running: Rc<Cell<bool>>,
 }
 
 impl ThreadsW {
@@ -25,10 +133,69 @@ impl ThreadsW {
         box_.set_baseline_position(gtk::BaselinePosition::Top);
         scrolled.add(&box_);
 
+        let parallel_stacks_w = ParallelStacksW::new();
+
+        let stack = gtk::Stack::new();
+        stack.add_named(&scrolled, LIST_PAGE);
+        stack.add_named(parallel_stacks_w.get_widget(), PARALLEL_STACKS_PAGE);
+
+        let parallel_stacks_button = gtk::ToggleButton::new_with_label("Parallel stacks");
+        parallel_stacks_button.set_halign(gtk::Align::Start);
+        parallel_stacks_button
+            .set_tooltip_text("Group threads with common call stack prefixes into a tree");
+        {
+            let stack = stack.clone();
+            parallel_stacks_button.connect_toggled(move |button| {
+                if button.get_active() {
+                    stack.set_visible_child_name(PARALLEL_STACKS_PAGE);
+                } else {
+                    stack.set_visible_child_name(LIST_PAGE);
+                }
+            });
+        }
+
+        let filter_entry = gtk::SearchEntry::new();
+        filter_entry.set_placeholder_text("Filter by id, name, state, function or file");
+        filter_entry.set_hexpand(true);
+
+        let filter_label = gtk::Label::new(None);
+        filter_label.set_no_show_all(true);
+
+        let filter_changed: FilterChangedCb = Rc::new(RefCell::new(None));
+        {
+            let filter_changed = filter_changed.clone();
+            filter_entry.connect_search_changed(move |_| {
+                if let Some(ref cb) = *filter_changed.borrow() {
+                    cb();
+                }
+            });
+        }
+
+        let header = gtk::Box::new(gtk::Orientation::Horizontal, 0);
+        header.pack_start(&filter_entry, true, true, 0);
+        header.pack_start(&filter_label, false, false, 4);
+        header.pack_start(&parallel_stacks_button, false, false, 0);
+
+        let widget = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        widget.pack_start(&header, false, false, 0);
+        widget.pack_start(&stack, true, true, 0);
+
         ThreadsW {
-            widget: scrolled,
+            widget,
             box_,
+            inferiors: vec![],
+            thread_groups: HashMap::new(),
+            filter_entry,
+            filter_label,
+            filter_changed,
+            parallel_stacks_button,
+            parallel_stacks_w,
             threads: vec![],
+            full_stacks: HashMap::new(),
+            get_stack: Rc::new(RefCell::new(None)),
+            thread_action: Rc::new(RefCell::new(None)),
+            select_thread: Rc::new(RefCell::new(None)),
+            non_stop: Rc::new(Cell::new(false)),
         }
     }
 
@@ -37,24 +204,476 @@ impl ThreadsW {
         self.widget.upcast_ref()
     }
 
+    /// Set "get stack" callback. Called when a thread with a stale (or no) backtrace is expanded,
+    /// and when more frames are requested. Arguments are: thread id, index of the first frame,
+    /// index of the last frame + 1.
+    pub fn connect_get_stack(&mut self, cb: Box<Fn(i32, usize, usize)>) {
+        *self.get_stack.borrow_mut() = Some(cb);
+    }
+
+    /// Set "thread action" callback. Called when continue, interrupt etc. is selected in the
+    /// context menu of a thread.
+    pub fn connect_thread_action(&mut self, cb: Box<Fn(i32, ThreadAction)>) {
+        *self.thread_action.borrow_mut() = Some(cb);
+    }
+
+    /// Set "select thread" callback. Called when "Select thread" is clicked in the context menu of
+    /// a thread. The current thread should be updated with `set_current_thread`.
+    pub fn connect_select_thread(&mut self, cb: Box<Fn(i32)>) {
+        *self.select_thread.borrow_mut() = Some(cb);
+    }
+
+    /// Mark a thread as the current thread.
+    pub fn set_current_thread(&self, thread_id: i32) {
+        for thread in &self.threads {
+            let current = thread.thread_id == thread_id;
+            if thread.current.get() != current {
+                thread.current.set(current);
+                update_title(thread);
+            }
+        }
+    }
+
+    /// Set "parallel stacks toggled" callback. When the parallel stacks view is enabled full
+    /// backtraces of all threads should be fetched and added with `set_full_stacks` (see
+    /// `needs_full_stacks`).
+    pub fn connect_parallel_stacks_toggled(&self, cb: Box<Fn(bool)>) {
+        self.parallel_stacks_button
+            .connect_toggled(move |button| cb(button.get_active()));
+    }
+
+    /// Set "filter changed" callback. The filter should be applied with `apply_filter`. Full
+    /// backtraces are needed for filtering (see `needs_full_stacks`).
+    pub fn connect_filter_changed(&mut self, cb: Box<Fn()>) {
+        *self.filter_changed.borrow_mut() = Some(cb);
+    }
+
+    /// Whether full backtraces of all threads are needed, for the parallel stacks view or for
+    /// filtering. When true full backtraces should be fetched after every stop.
+    pub fn needs_full_stacks(&self) -> bool {
+        self.parallel_stacks_button.get_active() || !self.get_filter().is_empty()
+    }
+
+    /// Whether full backtraces of all stopped threads are available.
+    pub fn has_full_stacks(&self) -> bool {
+        self.threads
+            .iter()
+            .all(|thread| thread.running.get() || self.full_stacks.contains_key(&thread.thread_id))
+    }
+
+    /// Add full backtraces of threads. Updates the parallel stacks view and the filter.
+    pub fn set_full_stacks(&mut self, stacks: Vec<(i32, Backtrace)>) {
+        for (thread_id, bt) in stacks {
+            self.full_stacks.insert(thread_id, bt);
+        }
+        self.apply_filter();
+    }
+
+    fn get_filter(&self) -> String {
+        match self.filter_entry.get_text() {
+            None => "".to_string(),
+            Some(text) => text.as_str().trim().to_lowercase(),
+        }
+    }
+
+    /// Hide threads that don't match the filter text, and render the parallel stacks view with
+    /// the matching threads.
+    pub fn apply_filter(&self) {
+        let filter = self.get_filter();
+        let mut n_matching = 0;
+        let mut stacks = vec![];
+        for thread in &self.threads {
+            let bt = self.full_stacks.get(&thread.thread_id);
+            let matches = filter.is_empty() || thread_matches(&thread.info.borrow(), bt, &filter);
+            thread.expander.set_visible(matches);
+            if matches {
+                n_matching += 1;
+                if let Some(bt) = bt {
+                    stacks.push((thread.thread_id, bt));
+                }
+            }
+        }
+
+        if filter.is_empty() {
+            self.filter_label.hide();
+        } else {
+            self.filter_label.set_text(&format!(
+                "{} of {} threads",
+                n_matching,
+                self.threads.len()
+            ));
+            self.filter_label.show();
+        }
+
+        if self.parallel_stacks_button.get_active() {
+            stacks.sort_by_key(|(thread_id, _)| *thread_id);
+            self.parallel_stacks_w.set_stacks(&stacks);
+        }
+    }
+
+    /// Enable or disable per-thread actions.
+    pub fn set_non_stop(&self, non_stop: bool) {
+        self.non_stop.set(non_stop);
+    }
+
     pub fn clear(&mut self) {
         for w in self.box_.get_children() {
             self.box_.remove(&w);
         }
+        self.inferiors.clear();
         self.threads.clear();
+        self.full_stacks.clear();
+        self.parallel_stacks_w.clear();
+    }
+
+    /// Remove threads that are not in the given list of thread ids (e.g. exited threads).
+    pub fn retain_threads(&mut self, thread_ids: &[i32]) {
+        self.threads.retain(|thread| {
+            let retain = thread_ids.contains(&thread.thread_id);
+            if !retain {
+                thread.inferior_box.remove(&thread.expander);
+            }
+            retain
+        });
+        self.full_stacks
+            .retain(|thread_id, _| thread_ids.contains(thread_id));
+    }
+
+    pub fn has_thread(&self, thread_id: i32) -> bool {
+        self.threads
+            .iter()
+            .any(|thread| thread.thread_id == thread_id)
+    }
+
+    /// Track inferiors of threads, running threads and thread selection in the console. Threads
+    /// are added and removed with `update_thread_info` and `retain_threads` after stops.
+    pub fn handle_event(&mut self, event: &Event) {
+        match event {
+            Event::ThreadCreated { id, group_id } => {
+                // Inferior of the thread is needed when the thread is added
+                self.thread_groups.insert(*id, group_id.clone());
+            }
+            Event::ThreadExited { id, .. } => {
+                self.thread_groups.remove(id);
+            }
+            Event::ThreadSelected { id } => {
+                // Thread selected with "thread N" in the console
+                self.set_current_thread(*id);
+            }
+            Event::Running { thread_ids } => match thread_ids {
+                None => self.set_running(None),
+                Some(thread_ids) => {
+                    for thread_id in thread_ids {
+                        self.set_running(Some(*thread_id));
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+
+    /// Update the header of an inferior's threads, adding the group if it doesn't exist.
+    pub fn update_inferior(&mut self, inferior: &Inferior) {
+        let inferior_w = self.get_inferior(&inferior.id);
+        let mut title = format!("Inferior {}", inferior.id.trim_start_matches('i'));
+        if let Some(pid) = inferior.pid {
+            title.push_str(&format!(" (process {})", pid));
+        }
+        if let Some(ref executable) = inferior.executable {
+            title.push_str(&format!(" {}", executable));
+        }
+        inferior_w
+            .header
+            .set_markup(&format!("<b>{}</b>", glib::markup_escape_text(&title)));
+    }
+
+    /// Remove the group of an inferior (e.g. after `remove-inferiors`). Threads of the inferior
+    /// should be removed first.
+    pub fn remove_inferior(&mut self, id: &str) {
+        let box_ = &self.box_;
+        self.inferiors.retain(|inferior_w| {
+            let retain = inferior_w.id != id;
+            if !retain {
+                box_.remove(&inferior_w.header);
+                box_.remove(&inferior_w.box_);
+            }
+            retain
+        });
+        self.update_inferior_headers();
     }
 
-    pub fn add_thread(&mut self, thread_id: i32, target_id: &str, bt: &Backtrace) {
-        let expander = gtk::Expander::new(Some(format!("#{} {}", thread_id, target_id).as_str()));
-        expander.set_expanded(true);
+    /// Get group of an inferior, adding it if it doesn't exist.
+    fn get_inferior(&mut self, id: &str) -> &InferiorW {
+        match self
+            .inferiors
+            .iter()
+            .position(|inferior_w| inferior_w.id == id)
+        {
+            Some(idx) => &self.inferiors[idx],
+            None => {
+                let header = gtk::Label::new(None);
+                header.set_halign(gtk::Align::Start);
+                header.set_no_show_all(true);
+                if id.is_empty() {
+                    header.set_text("Unknown inferior");
+                } else {
+                    header.set_text(&format!("Inferior {}", id.trim_start_matches('i')));
+                }
+                let box_ = gtk::Box::new(gtk::Orientation::Vertical, 0);
+                self.box_.pack_start(&header, false, false, 0);
+                self.box_.pack_start(&box_, false, false, 0);
+                box_.show();
+                self.inferiors.push(InferiorW {
+                    id: id.to_string(),
+                    header,
+                    box_,
+                });
+                self.update_inferior_headers();
+                self.inferiors.last().unwrap()
+            }
+        }
+    }
+
+    /// Headers are only shown when there are multiple inferiors.
+    fn update_inferior_headers(&self) {
+        let show = self.inferiors.len() > 1;
+        for inferior_w in &self.inferiors {
+            inferior_w.header.set_visible(show);
+        }
+    }
+
+    /// Mark a thread as running. `None` means all threads. Backtraces of running threads are
+    /// greyed out and fetched again when the threads stop.
+    pub fn set_running(&mut self, thread_id: Option<i32>) {
+        for thread in &self.threads {
+            if thread_id.is_none() || thread_id == Some(thread.thread_id) {
+                set_thread_running(thread);
+                self.full_stacks.remove(&thread.thread_id);
+            }
+        }
+    }
+
+    /// Update a thread after a stop with information from `-thread-info`. Adds the thread to the
+    /// end of the list if it's not in the list. Existing threads are updated in place, so expanded
+    /// state, position and selected frames are preserved.
+    ///
+    /// Backtraces of collapsed threads are fetched when they're expanded. For expanded threads
+    /// this returns the number of frames to fetch; the caller should fetch the frames and add them
+    /// with `set_frames`. New threads are collapsed, except the current thread. Nothing is
+    /// fetched for running threads.
+    pub fn update_thread_info(&mut self, info: &Thread, current: bool) -> Option<usize> {
+        let running = info.state == ThreadState::Running;
+        // Full backtrace is fetched again when needed
+        self.full_stacks.remove(&info.id);
+        for thread in &self.threads {
+            if thread.thread_id == info.id {
+                *thread.info.borrow_mut() = info.clone();
+                thread.current.set(current);
+                if running {
+                    set_thread_running(thread);
+                    return None;
+                }
+                thread.running.set(false);
+                thread.bt_w.get_widget().set_sensitive(true);
+                update_title(thread);
+                if let Some(depth) = thread.depth.take() {
+                    thread.old_depth.set(Some(depth));
+                }
+                if thread.expander.get_expanded() {
+                    thread.stale.set(false);
+                    return Some(std::cmp::max(thread.n_frames.get(), FRAMES_PAGE_SIZE));
+                } else {
+                    thread.stale.set(true);
+                    return None;
+                }
+            }
+        }
+
+        let thread = self.add_thread(info, current);
+        if running {
+            thread.running.set(true);
+            thread.bt_w.get_widget().set_sensitive(false);
+            return None;
+        }
+        if current {
+            // Mark as not stale before expanding to avoid fetching in the "expanded" handler
+            thread.stale.set(false);
+            thread.expander.set_expanded(true);
+            Some(FRAMES_PAGE_SIZE)
+        } else {
+            None
+        }
+    }
+
+    fn add_thread(&mut self, info: &Thread, current: bool) -> &ThreadW {
+        let thread_id = info.id;
+        let expander = gtk::Expander::new(None);
+        expander.set_use_markup(true);
+        expander.set_expanded(false);
         expander.set_vexpand(false);
-        let w = BacktraceW::new(bt);
-        expander.add(w.get_widget());
-        self.box_.pack_start(&expander, false, false, 0);
-        self.threads.push(w);
-        self.box_.show_all();
+
+        let box_ = gtk::Box::new(gtk::Orientation::Vertical, 0);
+        expander.add(&box_);
+
+        let bt_w = BacktraceW::new(&Backtrace(vec![]));
+        box_.pack_start(bt_w.get_widget(), false, false, 0);
+
+        let load_more = gtk::Button::new_with_label("Load more frames");
+        load_more.set_halign(gtk::Align::Start);
+        // Only shown when there are more frames to load
+        load_more.set_no_show_all(true);
+        box_.pack_start(&load_more, false, false, 0);
+
+        let stale = Rc::new(Cell::new(true));
+        let n_frames = Rc::new(Cell::new(0));
+        let running = Rc::new(Cell::new(false));
+
+        // Fetch the backtrace when expanded. Backtraces of running threads are fetched when they
+        // stop.
+        {
+            let stale = stale.clone();
+            let running = running.clone();
+            let get_stack = self.get_stack.clone();
+            expander.connect_property_expanded_notify(move |expander| {
+                if expander.get_expanded() && stale.get() && !running.get() {
+                    stale.set(false);
+                    call_get_stack(&get_stack, thread_id, 0, FRAMES_PAGE_SIZE);
+                }
+            });
+        }
+
+        // Fetch more frames when "load more" is clicked
+        {
+            let n_frames = n_frames.clone();
+            let get_stack = self.get_stack.clone();
+            load_more.connect_clicked(move |_| {
+                let n_frames = n_frames.get();
+                call_get_stack(&get_stack, thread_id, n_frames, n_frames + FRAMES_PAGE_SIZE);
+            });
+        }
+
+        //
+        // Context menu for selecting the thread, and continuing, interrupting and stepping the
+        // thread (non-stop mode). Selecting is not done on click, as clicking expands or collapses
+        // the thread.
+        //
+
+        let menu = gtk::Menu::new();
+        let select_item = gtk::MenuItem::new_with_label("Select thread");
+        {
+            let select_thread = self.select_thread.clone();
+            select_item.connect_activate(move |_| match *select_thread.borrow() {
+                None => {
+                    println!("Can't select thread {}: callback not set", thread_id);
+                }
+                Some(ref cb) => {
+                    cb(thread_id);
+                }
+            });
+        }
+        menu.append(&select_item);
+        let separator = gtk::SeparatorMenuItem::new();
+        menu.append(&separator);
+        let mut items = vec![];
+        for (action, label) in THREAD_ACTIONS.iter().cloned() {
+            let item = gtk::MenuItem::new_with_label(label);
+            let thread_action = self.thread_action.clone();
+            item.connect_activate(move |_| {
+                call_thread_action(&thread_action, thread_id, action);
+            });
+            menu.append(&item);
+            items.push((action, item));
+        }
+        menu.show_all();
+        menu.set_attach_widget(Some(&expander));
+
+        {
+            let non_stop = self.non_stop.clone();
+            let running = running.clone();
+            expander.connect_button_press_event(move |_, ev| {
+                if ev.get_event_type() != gdk::EventType::ButtonPress || ev.get_button() != 3 {
+                    return gtk::Inhibit(false);
+                }
+                let non_stop = non_stop.get();
+                separator.set_visible(non_stop);
+                let running = running.get();
+                for (action, item) in &items {
+                    item.set_visible(non_stop);
+                    // Only running threads can be interrupted, only stopped threads can be
+                    // resumed
+                    item.set_sensitive((*action == ThreadAction::Interrupt) == running);
+                }
+                menu.popup_at_pointer(Some(&**ev));
+                gtk::Inhibit(true)
+            });
+        }
+
+        let group_id = self
+            .thread_groups
+            .get(&thread_id)
+            .cloned()
+            .unwrap_or_default();
+        let inferior_box = self.get_inferior(&group_id).box_.clone();
+        inferior_box.pack_start(&expander, false, false, 0);
+        expander.show_all();
+        self.threads.push(ThreadW {
+            thread_id,
+            inferior_box,
+            info: RefCell::new(info.clone()),
+            current: Cell::new(current),
+            expander,
+            bt_w,
+            load_more,
+            stale,
+            n_frames,
+            depth: Cell::new(None),
+            old_depth: Cell::new(None),
+            running,
+        });
         // This traversel all rows in every addition but OK
         // self.reset_cols();
+        let thread = self.threads.last().unwrap();
+        update_title(thread);
+        // Backtrace of the new thread is not available yet, filter with the thread info only
+        let filter = self.get_filter();
+        if !filter.is_empty() && !thread_matches(info, None, &filter) {
+            thread.expander.set_visible(false);
+        }
+        thread
+    }
+
+    /// Set stack depth of a thread, used to decide whether to show the "load more" button.
+    pub fn set_depth(&self, thread_id: i32, depth: usize) {
+        for thread in &self.threads {
+            if thread.thread_id == thread_id {
+                thread.depth.set(Some(depth));
+                update_load_more(thread);
+                return;
+            }
+        }
+    }
+
+    /// Render frames of a thread. `low` is the level of the first frame in `bt`. When `low` is 0
+    /// the backtrace replaces the old one and changed frames are highlighted, otherwise the frames
+    /// are added to the end.
+    pub fn set_frames(&self, thread_id: i32, low: usize, bt: &Backtrace) {
+        for thread in &self.threads {
+            if thread.thread_id == thread_id {
+                if low == 0 {
+                    let depth_change = match (thread.old_depth.get(), thread.depth.get()) {
+                        (Some(old), Some(new)) => Some(new as isize - old as isize),
+                        _ => None,
+                    };
+                    thread.bt_w.update_bt(bt, depth_change);
+                    thread.n_frames.set(bt.0.len());
+                } else {
+                    thread.bt_w.append_bt(bt);
+                    thread.n_frames.set(thread.n_frames.get() + bt.0.len());
+                }
+                update_load_more(thread);
+                return;
+            }
+        }
     }
 
     /// Make same columns of different thread views the same. Note that this only works after
@@ -65,7 +684,7 @@ impl ThreadsW {
         let mut max_3 = 0;
         let mut max_4 = 0;
         for t in &self.threads {
-            let (c1, c2, c3, c4) = t.get_col_widths();
+            let (c1, c2, c3, c4) = t.bt_w.get_col_widths();
             if c1 > max_1 {
                 max_1 = c1;
             }
@@ -81,7 +700,123 @@ impl ThreadsW {
         }
         println!("{} {} {} {}", max_1, max_2, max_3, max_4);
         for t in &self.threads {
-            t.set_col_widths(max_1, max_2, max_3, max_4);
+            t.bt_w.set_col_widths(max_1, max_2, max_3, max_4);
+        }
+    }
+}
+
+fn set_thread_running(thread: &ThreadW) {
+    thread.running.set(true);
+    // Fetch the backtrace again when the thread stops
+    thread.stale.set(true);
+    {
+        let mut info = thread.info.borrow_mut();
+        info.state = ThreadState::Running;
+        info.frame = None;
+    }
+    update_title(thread);
+    thread.bt_w.get_widget().set_sensitive(false);
+    thread.load_more.hide();
+}
+
+/// Show or hide the "load more" button of a thread.
+fn update_load_more(thread: &ThreadW) {
+    match thread.depth.get() {
+        Some(depth) if thread.n_frames.get() < depth && !thread.running.get() => {
+            thread.load_more.set_label(&format!(
+                "Load more frames ({} of {})",
+                thread.n_frames.get(),
+                depth
+            ));
+            thread.load_more.show();
+        }
+        _ => {
+            thread.load_more.hide();
+        }
+    }
+}
+
+/// Whether a thread matches a filter. `filter` should be lowercase. `bt` is the full backtrace of
+/// the thread, if available. Numbers (e.g. "12" or "#12") only match the thread with the id,
+/// other filters match states, and parts of names, functions and files.
+fn thread_matches(info: &Thread, bt: Option<&Backtrace>, filter: &str) -> bool {
+    if let Ok(id) = filter.trim_start_matches('#').parse::<i32>() {
+        return id == info.id;
+    }
+    let state = match info.state {
+        ThreadState::Stopped => "stopped",
+        ThreadState::Running => "running",
+    };
+    let matches = |str: &str| str.to_lowercase().contains(filter);
+    if filter == state || info.name.as_ref().map_or(false, |name| matches(name)) {
+        return true;
+    }
+    let frames = bt.map(|bt| &bt.0[..]).unwrap_or(&[]);
+    frames.iter().chain(info.frame.iter()).any(|frame| {
+        matches(&frame.func)
+            || frame.file.as_ref().map_or(false, |file| matches(file))
+            || frame.fullname.as_ref().map_or(false, |file| matches(file))
+    })
+}
+
+/// Render the expander title of a thread, e.g.
+/// "#2 gmain (Thread 0x7fffed538700 (LWP 19789)) — stopped on core 4 — __GI___poll at poll.c:29".
+/// The current thread is shown in bold.
+fn update_title(thread: &ThreadW) {
+    let info = thread.info.borrow();
+    let mut title = format!("#{}", info.id);
+    if let Some(ref name) = info.name {
+        title.push_str(&format!(" {}", name));
+    }
+    title.push_str(&format!(" ({})", info.target_id));
+    title.push_str(match info.state {
+        ThreadState::Stopped => " — stopped",
+        ThreadState::Running => " — running",
+    });
+    if let Some(core) = info.core {
+        title.push_str(&format!(" on core {}", core));
+    }
+    if let Some(ref frame) = info.frame {
+        match (&frame.file, &frame.line) {
+            (Some(file), Some(line)) => {
+                title.push_str(&format!(" — {} at {}:{}", frame.func, file, line));
+            }
+            _ => {
+                title.push_str(&format!(" — {} ({})", frame.func, frame.addr));
+            }
+        }
+    }
+    let title = glib::markup_escape_text(&title).to_string();
+    if thread.current.get() {
+        thread
+            .expander
+            .set_label(Some(format!("<b>{}</b>", title).as_str()));
+    } else {
+        thread.expander.set_label(Some(title.as_str()));
+    }
+}
+
+fn call_thread_action(cb: &ThreadActionCb, thread_id: i32, action: ThreadAction) {
+    match *cb.borrow() {
+        None => {
+            println!(
+                "Can't send {:?} to thread {}: callback not set",
+                action, thread_id
+            );
+        }
+        Some(ref cb) => {
+            cb(thread_id, action);
+        }
+    }
+}
+
+fn call_get_stack(cb: &GetStackCb, thread_id: i32, low: usize, high: usize) {
+    match *cb.borrow() {
+        None => {
+            println!("Can't get stack of thread {}: callback not set", thread_id);
+        }
+        Some(ref cb) => {
+            cb(thread_id, low, high);
         }
     }
 }